@@ -0,0 +1,12 @@
+use crate::{
+    common::Position,
+    error::diagnostic::{Diagnostic, Label},
+};
+
+pub(crate) fn variadic_unsupported(pos: &Position, target: String) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("variadic functions aren't supported on target `{}`", target)
+        })))
+        .with_note("its calling convention passes variadic arguments differently".into())
+}
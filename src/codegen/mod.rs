@@ -1,28 +1,88 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
 use crate::common::NodeID;
 use crate::error::InternalError;
+use crate::error::context::Context;
+use crate::symtable::SymTable;
+use cranelift_codegen::ir::condcodes::IntCC;
 use cranelift_codegen::ir::{
-    InstBuilder, MemFlags, Signature, StackSlotData, StackSlotKind, Value, types::*,
+    Block, Endianness, InstBuilder, MemFlags, Signature, StackSlotData, StackSlotKind, TrapCode,
+    Value, types::*,
 };
 use cranelift_codegen::settings::Configurable;
 use cranelift_codegen::{ir::AbiParam, isa, settings};
 
-use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
-use cranelift_module::{FuncId, Linkage, Module};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_module::{DataDescription, DataId, FuncId, Linkage, Module};
 
 use cranelift_object::{ObjectModule, ObjectProduct};
+use target_lexicon::{CallingConvention, Triple};
 
 use crate::core::ast;
 
-pub fn translate(prog: ast::Program) -> Result<ObjectProduct, InternalError> {
+mod error;
+
+/// Trap code for code that is never reached.
+const UNREACHABLE: TrapCode = TrapCode::unwrap_user(1);
+/// Trap code for a failed runtime check.
+const CHECK_FAILED: TrapCode = TrapCode::unwrap_user(2);
+
+/// Parse the target triple, if no target is specified the host triple is used.
+pub fn target_triple(target: Option<&str>) -> Result<Triple, InternalError> {
+    match target {
+        Some(name) => Triple::from_str(name)
+            .map_err(|e| InternalError::AnyMsg(format!("invalid target triple `{}`: {}", name, e))),
+        None => Ok(Triple::host()),
+    }
+}
+
+/// Report features of the program the target can't support.
+///
+/// Variadic calls use the default calling convention of the target,
+/// with a signature listing every argument of the call. That is only
+/// correct where the variadic arguments are passed like the fixed ones,
+/// which excludes e.g. `aarch64-apple-darwin`, where they all go on the stack.
+/// System V on x86-64 also expects `%al` to bound the number of vector
+/// registers used, but callees only test it before saving them.
+pub fn check_target(ctx: &mut Context, triple: &Triple, sym_table: &SymTable) {
+    let variadic_ok = matches!(
+        triple.default_calling_convention(),
+        Ok(CallingConvention::SystemV | CallingConvention::WindowsFastcall)
+    );
+    if variadic_ok {
+        return;
+    }
+    for info in sym_table.get_items().values() {
+        if let Some(pos) = &info.variadic {
+            ctx.report(error::variadic_unsupported(pos, triple.to_string()));
+        }
+    }
+}
+
+/// Lower the program to an object file for the given target triple.
+pub fn translate(
+    prog: ast::Program,
+    triple: Triple,
+    opt_level: u8,
+) -> Result<ObjectProduct, InternalError> {
     let mut settings_builder = settings::builder();
-    settings_builder.set("opt_level", "speed").unwrap();
+    let opt_level = match opt_level {
+        0 => "none",
+        1 => "speed",
+        _ => "speed_and_size",
+    };
+    settings_builder.set("opt_level", opt_level).unwrap();
+    // imported symbols are resolved through the GOT/PLT
+    settings_builder.set("is_pic", "true").unwrap();
     let flags = settings::Flags::new(settings_builder);
-    let isa = isa::lookup_by_name("x86_64-linux-elf")
-        .unwrap()
+    let isa = isa::lookup(triple.clone())
+        .map_err(|e| InternalError::AnyMsg(format!("unsupported target `{}`: {}", triple, e)))?
         .finish(flags)
-        .unwrap();
+        .map_err(|e| {
+            InternalError::AnyMsg(format!("failed to build target `{}`: {}", triple, e))
+        })?;
 
     let module_builder = cranelift_object::ObjectBuilder::new(
         isa,
@@ -35,16 +95,16 @@ pub fn translate(prog: ast::Program) -> Result<ObjectProduct, InternalError> {
 
     let mut l = Lowerer::new(&mut module);
 
+    let defined: HashSet<NodeID> = prog.functions.iter().map(|f| f.id).collect();
+
     for (id, sym) in &prog.symbols {
-        l.declare_sym(*id, sym);
+        l.declare_sym(*id, sym, defined.contains(id));
     }
 
     for f in prog.functions {
-        l.emit_func(f);
+        l.emit_func(f)?;
     }
 
-    println!("{:#?}", module.declarations());
-
     let obj = module.finish();
     Ok(obj)
 }
@@ -52,7 +112,15 @@ pub fn translate(prog: ast::Program) -> Result<ObjectProduct, InternalError> {
 struct Lowerer<'ctx> {
     m: &'ctx mut ObjectModule,
     id_fn_map: HashMap<NodeID, FuncId>,
-    variables: HashMap<ast::VarID, Value>,
+    /// Data objects of statics.
+    id_data_map: HashMap<NodeID, DataId>,
+    variables: HashMap<ast::VarID, Variable>,
+    /// Blocks `continue` and `break` jump to, for each enclosing loop.
+    loops: Vec<(Block, Block)>,
+    /// Read-only data objects, shared by all uses of the same contents.
+    data: HashMap<Vec<u8>, DataId>,
+    /// Functions taking arguments past the declared ones.
+    variadic: HashSet<NodeID>,
 }
 
 impl<'ctx> Lowerer<'ctx> {
@@ -60,11 +128,26 @@ impl<'ctx> Lowerer<'ctx> {
         Self {
             m,
             id_fn_map: HashMap::new(),
+            id_data_map: HashMap::new(),
             variables: HashMap::new(),
+            loops: vec![],
+            data: HashMap::new(),
+            variadic: HashSet::new(),
         }
     }
 
-    fn declare_sym(&mut self, id: NodeID, f: &ast::Symbol) {
+    /// Declare a symbol in the module.
+    ///
+    /// Functions without a definition in this object are imported,
+    /// so that the linker resolves them from other objects or libraries.
+    /// Statics are defined right away, their contents are already known.
+    /// `@link_name` takes precedence over the source name of the symbol.
+    fn declare_sym(&mut self, id: NodeID, f: &ast::Symbol, is_defined: bool) {
+        let name = match &f.link_name {
+            Some(link_name) => link_name,
+            None if f.mangle => &format!("id_{}", id.get()),
+            None => &f.name,
+        };
         match &f.kind {
             ast::SymKind::Func { args, returns } => {
                 let mut sig = self.m.make_signature();
@@ -78,21 +161,38 @@ impl<'ctx> Lowerer<'ctx> {
                     sig.returns.push(param);
                 }
 
-                let name = if f.mangle {
-                    &format!("id_{}", id.get())
-                } else {
-                    &f.name
-                };
-
-                let link = if f.is_extern {
-                    Linkage::Export
-                } else {
-                    Linkage::Local
+                let link = match (is_defined, f.is_extern) {
+                    (false, _) => Linkage::Import,
+                    (true, true) => Linkage::Export,
+                    (true, false) => Linkage::Local,
                 };
 
                 let func_id = self.m.declare_function(name, link, &sig).unwrap();
 
                 self.id_fn_map.insert(id, func_id);
+                if f.is_variadic {
+                    self.variadic.insert(id);
+                }
+            }
+            ast::SymKind::Static { tp, is_mut, value } => {
+                let link = match f.is_extern {
+                    true => Linkage::Export,
+                    false => Linkage::Local,
+                };
+                // immutable statics end up in read-only data
+                let data_id = self.m.declare_data(name, link, *is_mut, false).unwrap();
+
+                let size = tp.size() as usize;
+                let bytes = match self.m.isa().endianness() {
+                    Endianness::Little => (*value as u64).to_le_bytes()[..size].to_vec(),
+                    Endianness::Big => (*value as u64).to_be_bytes()[8 - size..].to_vec(),
+                };
+                let mut desc = DataDescription::new();
+                desc.define(bytes.into_boxed_slice());
+                desc.set_align(size as u64);
+                self.m.define_data(data_id, &desc).unwrap();
+
+                self.id_data_map.insert(id, data_id);
             }
         }
     }
@@ -101,7 +201,7 @@ impl<'ctx> Lowerer<'ctx> {
         *self.id_fn_map.get(&id).unwrap()
     }
 
-    pub fn emit_func(&mut self, f: ast::Func) {
+    pub fn emit_func(&mut self, f: ast::Func) -> Result<(), InternalError> {
         let func = self.get_func_id(f.id);
 
         let mut ctx = self.m.make_context();
@@ -121,31 +221,41 @@ impl<'ctx> Lowerer<'ctx> {
         b.switch_to_block(block);
         b.seal_block(block);
 
-        let fn_args = b.block_params(block);
+        let fn_args = b.block_params(block).to_vec();
 
-        for (val, (var, _)) in fn_args.iter().zip(f.args) {
-            self.variables.insert(var, *val);
+        for (val, (var, _)) in fn_args.into_iter().zip(f.args) {
+            self.define_var(&mut b, var, val);
         }
 
         let val = self.lower_expr(&mut b, f.body);
 
-        if let Some(v) = val {
-            b.ins().return_(&[v]);
-        } else {
-            b.ins().return_(&[]);
+        match val {
+            Some(v) => {
+                b.ins().return_(&[v]);
+            }
+            None if b.func.signature.returns.is_empty() => {
+                b.ins().return_(&[]);
+            }
+            // body always returns early, the end of it is unreachable
+            None => {
+                b.ins().trap(UNREACHABLE);
+            }
         }
 
-        println!("{}", b.func.display());
-
         b.finalize();
 
-        match self.m.define_function(func, &mut ctx) {
-            Ok(o) => (),
-            Err(e) => println!("{:#?}", e),
-        }
+        self.m.define_function(func, &mut ctx).map_err(|e| {
+            let decl = self.m.declarations().get_function_decl(func);
+            InternalError::AnyMsg(format!(
+                "failed to define function `{}`: {}",
+                decl.linkage_name(func),
+                e
+            ))
+        })?;
 
         self.variables.clear();
         self.m.clear_context(&mut ctx);
+        Ok(())
     }
 
     pub fn lower_expr(&mut self, b: &mut FunctionBuilder, e: ast::Expr) -> Option<Value> {
@@ -157,7 +267,24 @@ impl<'ctx> Lowerer<'ctx> {
                         fn_args.push(v)
                     }
                 }
-                let callee = self.lower_expr(b, *expr).unwrap();
+                // call known functions directly, so the relocation
+                // carries the linkage of the callee
+                let callee = match *expr {
+                    ast::Expr::Value(ast::Value::Var(ast::VarRef::Global(id))) => {
+                        let f_id = self.get_func_id(id);
+                        let f_ref = self.m.declare_func_in_func(f_id, b.func);
+                        if !self.variadic.contains(&id) {
+                            let inst = b.ins().call(f_ref, &fn_args);
+                            let ret = b.inst_results(inst);
+                            return ret.get(0).map(|f| *f);
+                        }
+                        // the declared signature has only the fixed arguments,
+                        // so the call goes through one made for this call site,
+                        // targets where that is wrong are rejected by `check_target`
+                        b.ins().func_addr(I64, f_ref)
+                    }
+                    expr => self.lower_expr(b, expr).unwrap(),
+                };
                 let sig = self.sig_from_core(sig);
                 let sig_ref = b.import_signature(sig);
                 let inst = b.ins().call_indirect(sig_ref, callee, &fn_args);
@@ -170,21 +297,140 @@ impl<'ctx> Lowerer<'ctx> {
                 } else {
                     b.ins().return_(&[]);
                 }
+                // code after return is dead, but it still needs a block
+                let block = b.create_block();
+                b.switch_to_block(block);
+                b.seal_block(block);
+                None
+            }
+            ast::Expr::StaticData(bytes) => {
+                let id = self.static_data(bytes);
+                let gv = self.m.declare_data_in_func(id, b.func);
+                let v = b.ins().global_value(I64, gv);
+                Some(v)
+            }
+            ast::Expr::Trap => {
+                b.ins().trap(CHECK_FAILED);
+                // code after the trap is dead, but it still needs a block
+                let block = b.create_block();
+                b.switch_to_block(block);
+                b.seal_block(block);
+                None
+            }
+            ast::Expr::Unreachable => {
+                b.ins().trap(UNREACHABLE);
+                // code after the trap is dead, but it still needs a block
+                let block = b.create_block();
+                b.switch_to_block(block);
+                b.seal_block(block);
                 None
             }
             ast::Expr::Let { id, e1 } => {
                 if let Some(v) = self.lower_expr(b, *e1) {
-                    self.variables.insert(id, v);
+                    self.define_var(b, id, v);
+                }
+                None
+            }
+            ast::Expr::Assign { id, e1 } => {
+                if let Some(v) = self.lower_expr(b, *e1) {
+                    let var = *self.variables.get(&id).unwrap();
+                    b.def_var(var, v);
+                }
+                None
+            }
+            ast::Expr::While { pred, block } => {
+                let header_block = b.create_block();
+                let body_block = b.create_block();
+                let exit_block = b.create_block();
+
+                b.ins().jump(header_block, &[]);
+                b.switch_to_block(header_block);
+                let p = self.lower_expr(b, *pred).unwrap();
+                b.ins().brif(p, body_block, &[], exit_block, &[]);
+
+                b.switch_to_block(body_block);
+                b.seal_block(body_block);
+                self.loops.push((header_block, exit_block));
+                self.lower_expr(b, *block);
+                self.loops.pop();
+                b.ins().jump(header_block, &[]);
+                b.seal_block(header_block);
+
+                b.switch_to_block(exit_block);
+                b.seal_block(exit_block);
+                None
+            }
+            ast::Expr::Loop { body, tp } => {
+                let body_block = b.create_block();
+                let exit_block = b.create_block();
+                if let Some(tp) = &tp {
+                    b.append_block_param(exit_block, tp.to_cl_type());
                 }
+
+                b.ins().jump(body_block, &[]);
+                b.switch_to_block(body_block);
+                self.loops.push((body_block, exit_block));
+                self.lower_expr(b, *body);
+                self.loops.pop();
+                b.ins().jump(body_block, &[]);
+                b.seal_block(body_block);
+
+                b.switch_to_block(exit_block);
+                b.seal_block(exit_block);
+                b.block_params(exit_block).first().copied()
+            }
+            ast::Expr::Break(expr) => {
+                let v = self.lower_expr(b, *expr);
+                let (_, exit_block) = *self.loops.last().expect("break outside of a loop");
+                let has_param = !b.block_params(exit_block).is_empty();
+                jump_to_merge(b, exit_block, has_param, v);
+                // code after the jump is dead, but it still needs a block
+                let block = b.create_block();
+                b.switch_to_block(block);
+                b.seal_block(block);
                 None
             }
-            ast::Expr::While { pred, block } => todo!(),
+            ast::Expr::Continue => {
+                let (header_block, _) = *self.loops.last().expect("continue outside of a loop");
+                b.ins().jump(header_block, &[]);
+                // code after the jump is dead, but it still needs a block
+                let block = b.create_block();
+                b.switch_to_block(block);
+                b.seal_block(block);
+                None
+            }
+            ast::Expr::If { pred, th, el, tp } => {
+                let p = self.lower_expr(b, *pred).unwrap();
+
+                let th_block = b.create_block();
+                let el_block = b.create_block();
+                let merge_block = b.create_block();
+                if let Some(tp) = &tp {
+                    b.append_block_param(merge_block, tp.to_cl_type());
+                }
+
+                b.ins().brif(p, th_block, &[], el_block, &[]);
+                b.seal_block(th_block);
+                b.seal_block(el_block);
+
+                b.switch_to_block(th_block);
+                let v = self.lower_expr(b, *th);
+                jump_to_merge(b, merge_block, tp.is_some(), v);
+
+                b.switch_to_block(el_block);
+                let v = self.lower_expr(b, *el);
+                jump_to_merge(b, merge_block, tp.is_some(), v);
+
+                b.switch_to_block(merge_block);
+                b.seal_block(merge_block);
+                tp.map(|_| b.block_params(merge_block)[0])
+            }
             ast::Expr::Value(value) => self.tr_value(b, value),
-            ast::Expr::StackSlot { size } => {
+            ast::Expr::StackSlot { size, align } => {
                 let ss = b.create_sized_stack_slot(StackSlotData {
                     kind: StackSlotKind::ExplicitSlot,
                     size,
-                    align_shift: 0,
+                    align_shift: align.trailing_zeros() as u8,
                 });
                 let v = b.ins().stack_addr(I64, ss, 0);
                 Some(v)
@@ -200,6 +446,16 @@ impl<'ctx> Lowerer<'ctx> {
                 let v = b.ins().load(tp.to_cl_type(), MemFlags::new(), p, offset);
                 Some(v)
             }
+            ast::Expr::Cast { expr, from, to } => {
+                let x = self.lower_expr(b, *expr).unwrap();
+                let v = match from.size().cmp(&to.size()) {
+                    Ordering::Less if from.is_signed() => b.ins().sextend(to.to_cl_type(), x),
+                    Ordering::Less => b.ins().uextend(to.to_cl_type(), x),
+                    Ordering::Greater => b.ins().ireduce(to.to_cl_type(), x),
+                    Ordering::Equal => x,
+                };
+                Some(v)
+            }
             ast::Expr::Builtin { name, mut args } => match name.as_str() {
                 "iadd" => {
                     let y = args.pop().unwrap();
@@ -209,7 +465,53 @@ impl<'ctx> Lowerer<'ctx> {
                     let v = b.ins().iadd(x, y);
                     Some(v)
                 }
-                s => panic!("unknown instruction {}", s),
+                "ineg" => {
+                    let x = args.pop().unwrap();
+                    let x = self.lower_expr(b, x).unwrap();
+                    let v = b.ins().ineg(x);
+                    Some(v)
+                }
+                "isub" => {
+                    let y = args.pop().unwrap();
+                    let x = args.pop().unwrap();
+                    let x = self.lower_expr(b, x).unwrap();
+                    let y = self.lower_expr(b, y).unwrap();
+                    let v = b.ins().isub(x, y);
+                    Some(v)
+                }
+                "sdiv" | "udiv" => {
+                    let y = args.pop().unwrap();
+                    let x = args.pop().unwrap();
+                    let x = self.lower_expr(b, x).unwrap();
+                    let y = self.lower_expr(b, y).unwrap();
+                    let v = match name.as_str() {
+                        "sdiv" => b.ins().sdiv(x, y),
+                        _ => b.ins().udiv(x, y),
+                    };
+                    Some(v)
+                }
+                "imul" => {
+                    let y = args.pop().unwrap();
+                    let x = args.pop().unwrap();
+                    let x = self.lower_expr(b, x).unwrap();
+                    let v = match y {
+                        ast::Expr::Value(ast::Value::Const(n, _)) => b.ins().imul_imm(x, n as i64),
+                        y => {
+                            let y = self.lower_expr(b, y).unwrap();
+                            b.ins().imul(x, y)
+                        }
+                    };
+                    Some(v)
+                }
+                s => {
+                    let cond = int_cc(s).unwrap_or_else(|| panic!("unknown instruction {}", s));
+                    let y = args.pop().unwrap();
+                    let x = args.pop().unwrap();
+                    let x = self.lower_expr(b, x).unwrap();
+                    let y = self.lower_expr(b, y).unwrap();
+                    let v = b.ins().icmp(cond, x, y);
+                    Some(v)
+                }
             },
             ast::Expr::Block { exprs, last_expr } => {
                 for expr in exprs {
@@ -220,12 +522,27 @@ impl<'ctx> Lowerer<'ctx> {
         }
     }
 
+    /// Declare a new local variable holding given value.
+    fn define_var(&mut self, b: &mut FunctionBuilder, id: ast::VarID, v: Value) {
+        let var = Variable::from_u32(id.get() as u32);
+        b.declare_var(var, b.func.dfg.value_type(v));
+        b.def_var(var, v);
+        self.variables.insert(id, var);
+    }
+
     pub fn tr_value(&mut self, b: &mut FunctionBuilder, v: ast::Value) -> Option<Value> {
         match v {
             ast::Value::Unit => None,
             ast::Value::Var(var_ref) => match var_ref {
                 ast::VarRef::Local(var_id) => {
-                    let v = *self.variables.get(&var_id).unwrap();
+                    let var = *self.variables.get(&var_id).unwrap();
+                    Some(b.use_var(var))
+                }
+                // address of a static
+                ast::VarRef::Global(node_id) if self.id_data_map.contains_key(&node_id) => {
+                    let data_id = self.id_data_map[&node_id];
+                    let gv = self.m.declare_data_in_func(data_id, b.func);
+                    let v = b.ins().global_value(I64, gv);
                     Some(v)
                 }
                 ast::VarRef::Global(node_id) => {
@@ -242,6 +559,19 @@ impl<'ctx> Lowerer<'ctx> {
         }
     }
 
+    /// Data object with given contents, defined on first use.
+    fn static_data(&mut self, bytes: Vec<u8>) -> DataId {
+        if let Some(id) = self.data.get(&bytes) {
+            return *id;
+        }
+        let id = self.m.declare_anonymous_data(false, false).unwrap();
+        let mut desc = DataDescription::new();
+        desc.define(bytes.clone().into_boxed_slice());
+        self.m.define_data(id, &desc).unwrap();
+        self.data.insert(bytes, id);
+        id
+    }
+
     fn sig_from_core(&self, fn_sig: ast::FnSig) -> Signature {
         let mut sig = self.m.make_signature();
         for param in fn_sig.params {
@@ -253,3 +583,36 @@ impl<'ctx> Lowerer<'ctx> {
         sig
     }
 }
+
+/// Condition of an integer comparison builtin.
+fn int_cc(name: &str) -> Option<IntCC> {
+    Some(match name {
+        "eq" => IntCC::Equal,
+        "ne" => IntCC::NotEqual,
+        "slt" => IntCC::SignedLessThan,
+        "sle" => IntCC::SignedLessThanOrEqual,
+        "sgt" => IntCC::SignedGreaterThan,
+        "sge" => IntCC::SignedGreaterThanOrEqual,
+        "ult" => IntCC::UnsignedLessThan,
+        "ule" => IntCC::UnsignedLessThanOrEqual,
+        "ugt" => IntCC::UnsignedGreaterThan,
+        "uge" => IntCC::UnsignedGreaterThanOrEqual,
+        _ => return None,
+    })
+}
+
+/// Finish a branch of a conditional by jumping to the merge block.
+fn jump_to_merge(b: &mut FunctionBuilder, merge_block: Block, has_param: bool, v: Option<Value>) {
+    match (has_param, v) {
+        (true, Some(v)) => {
+            b.ins().jump(merge_block, &[v]);
+        }
+        // branch returned early, the rest of it is unreachable
+        (true, None) => {
+            b.ins().trap(UNREACHABLE);
+        }
+        (false, _) => {
+            b.ins().jump(merge_block, &[]);
+        }
+    }
+}
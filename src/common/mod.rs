@@ -76,12 +76,13 @@ impl Path {
 /// Attribute.
 ///
 /// ```mst
-/// @attribute(arg1, arg2, arg3)
+/// @attribute("arg1", "arg2", "arg3")
 /// ```
 #[derive(Debug)]
 pub struct RAttribute {
     pub name: Ident,
     pub pos: Position,
+    /// Arguments, all of them string literals, already unescaped.
     pub args: Vec<String>,
 }
 
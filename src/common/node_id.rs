@@ -1,4 +1,7 @@
-static mut COUNTER: usize = 64;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Last used id, smaller ids are reserved.
+static COUNTER: AtomicUsize = AtomicUsize::new(64);
 
 /// Id representing a top-level declaration.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -9,10 +12,8 @@ pub struct NodeID {
 impl NodeID {
     /// Create a fresh node id.
     pub(crate) fn new_global() -> NodeID {
-        unsafe {
-            COUNTER += 1;
-            NodeID { id: COUNTER }
-        }
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+        NodeID { id }
     }
 
     /// Get the id of root node.
@@ -40,6 +41,7 @@ impl NodeID {
             "i32" => 10,
             "i64" => 11,
             "isize" => 12,
+            "char" => 13,
             _ => panic!("not a builtin name: {}", name),
         };
         NodeID { id }
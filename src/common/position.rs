@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use ariadne::Span;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Position {
     pub filename: Arc<str>,
     pub start: usize,
@@ -39,12 +39,23 @@ pub struct Symbol {
     pub name: String,
     pub kind: SymKind,
     pub is_extern: bool,
+    pub is_variadic: bool,
     pub mangle: bool,
+    pub link_name: Option<String>,
 }
 
 #[derive(Debug)]
 pub enum SymKind {
-    Func { args: Vec<Type>, returns: Vec<Type> },
+    Func {
+        args: Vec<Type>,
+        returns: Vec<Type>,
+    },
+    /// Global variable of a primitive type, with bits of its initial value.
+    Static {
+        tp: Type,
+        is_mut: bool,
+        value: usize,
+    },
 }
 
 #[derive(Debug)]
@@ -105,12 +116,21 @@ pub enum Expr {
         id: VarID,
         e1: Box<Expr>,
     },
+    /// Rebind a variable declared by [Expr::Let] to a new value.
+    Assign {
+        id: VarID,
+        e1: Box<Expr>,
+    },
     Block {
         exprs: Vec<Expr>,
         last_expr: Box<Expr>,
     },
+    /// Address of read-only data with given contents.
+    StaticData(Vec<u8>),
+    /// Address of fresh stack memory, `align` is in bytes.
     StackSlot {
         size: u32,
+        align: u32,
     },
     Store {
         ptr: Box<Expr>,
@@ -122,12 +142,39 @@ pub enum Expr {
         ptr: Box<Expr>,
         offset: i32,
     },
+    /// Sign- or zero-extension, or truncation, of an integer.
+    Cast {
+        expr: Box<Expr>,
+        from: Type,
+        to: Type,
+    },
     While {
         pred: Box<Expr>,
         block: Box<Expr>,
     },
+    /// Loop without a condition, yielding a value of type `tp` if it's not unit.
+    Loop {
+        body: Box<Expr>,
+        tp: Option<Type>,
+    },
+    /// Jump past the end of the innermost loop, yielding the value.
+    Break(Box<Expr>),
+    /// Jump back to the start of the innermost loop,
+    /// re-evaluating the condition of [Expr::While].
+    Continue,
+    /// Conditional, yielding a value of type `tp` if it's not unit.
+    If {
+        pred: Box<Expr>,
+        th: Box<Expr>,
+        el: Box<Expr>,
+        tp: Option<Type>,
+    },
     Builtin {
         name: String,
         args: Vec<Expr>,
     },
+    /// Abort the program, used when a runtime check fails.
+    Trap,
+    /// Mark code that is never reached.
+    Unreachable,
 }
@@ -1,10 +1,15 @@
 use std::collections::HashMap;
 
-use crate::core::ast::VarSpawner;
+use crate::{core::ast::VarSpawner, symtable::layout::Type};
 
 pub struct Env {
     map: HashMap<crate::mir::ast::VarID, crate::core::ast::VarID>,
     var_gen: crate::core::ast::VarSpawner,
+    var_needs_stack: HashMap<crate::mir::ast::VarID, bool>,
+    stack_vars: HashMap<crate::core::ast::VarID, Type>,
+    field_reads: HashMap<(crate::mir::ast::VarID, usize), usize>,
+    cached_fields: HashMap<(crate::core::ast::VarID, usize), crate::core::ast::VarID>,
+    debug_assertions: bool,
 }
 impl Env {
     pub(crate) fn fresh_var(&mut self) -> super::ast::VarID {
@@ -17,14 +22,77 @@ impl Env {
         new_id
     }
 
-    pub(crate) fn new() -> Self {
+    /// Add a scalar variable that lives in a stack slot.
+    ///
+    /// The new variable holds address of the slot.
+    pub(crate) fn add_stack_var(
+        &mut self,
+        id: crate::mir::ast::VarID,
+        tp: Type,
+    ) -> super::ast::VarID {
+        let new_id = self.add_var(id);
+        self.stack_vars.insert(new_id, tp);
+        new_id
+    }
+
+    pub(crate) fn new(
+        var_needs_stack: HashMap<crate::mir::ast::VarID, bool>,
+        field_reads: HashMap<(crate::mir::ast::VarID, usize), usize>,
+        debug_assertions: bool,
+    ) -> Self {
         Self {
             map: HashMap::new(),
             var_gen: VarSpawner::new(),
+            var_needs_stack,
+            stack_vars: HashMap::new(),
+            field_reads,
+            cached_fields: HashMap::new(),
+            debug_assertions,
         }
     }
 
+    /// Check if runtime checks should be inserted.
+    pub(crate) fn debug_assertions(&self) -> bool {
+        self.debug_assertions
+    }
+
     pub(crate) fn lookup(&self, var_id: crate::mir::ast::VarID) -> super::ast::VarID {
         *self.map.get(&var_id).unwrap()
     }
+
+    /// Check if the address of a variable is taken.
+    pub(crate) fn needs_stack(&self, var_id: crate::mir::ast::VarID) -> bool {
+        self.var_needs_stack.get(&var_id).copied().unwrap_or(false)
+    }
+
+    /// Returns how many times a field of a variable is read.
+    pub(crate) fn field_reads(&self, var_id: crate::mir::ast::VarID, field_id: usize) -> usize {
+        self.field_reads
+            .get(&(var_id, field_id))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Remember that a field of an immutable variable is held in another variable.
+    pub(crate) fn cache_field(
+        &mut self,
+        var_id: super::ast::VarID,
+        field_id: usize,
+        cache: super::ast::VarID,
+    ) {
+        self.cached_fields.insert((var_id, field_id), cache);
+    }
+
+    pub(crate) fn cached_field(
+        &self,
+        var_id: super::ast::VarID,
+        field_id: usize,
+    ) -> Option<super::ast::VarID> {
+        self.cached_fields.get(&(var_id, field_id)).copied()
+    }
+
+    /// Returns type of the value stored in the slot, if variable lives on the stack.
+    pub(crate) fn stack_var(&self, var_id: super::ast::VarID) -> Option<&Type> {
+        self.stack_vars.get(&var_id)
+    }
 }
@@ -1,32 +1,73 @@
 pub mod ast;
 mod env;
+mod reads;
 
-use std::mem::transmute;
+use std::{collections::HashMap, mem::transmute};
 
 use crate::{
     core::env::Env,
     mir::ast as in_a,
-    symtable::layout::{Layout, LayoutKind},
+    symtable::layout::{Layout, LayoutKind, Type},
 };
 use ast as out_a;
 
-pub fn translate(prog: in_a::Program) -> out_a::Program {
+/// Arrays with more repeated elements are initialized in a loop.
+const MAX_UNROLLED_ELEMS: usize = 16;
+
+/// Lower the program to core IR.
+///
+/// With optimizations enabled, fields of immutable aggregates that are read
+/// more than once are loaded only once, right after initialization.
+pub fn translate(prog: in_a::Program, opt_level: u8, debug_assertions: bool) -> out_a::Program {
     let symbols = unsafe { transmute(prog.symbols) };
-    let functions = prog.functions.into_iter().map(|f| tr_func(f)).collect();
+    let functions = prog
+        .functions
+        .into_iter()
+        .map(|f| tr_func(f, opt_level, debug_assertions))
+        .collect();
 
     out_a::Program { symbols, functions }
 }
 
-fn tr_func(f: in_a::Func) -> out_a::Func {
+fn tr_func(f: in_a::Func, opt_level: u8, debug_assertions: bool) -> out_a::Func {
     let mut args = vec![];
-    let mut env = Env::new();
+    let mut field_reads = HashMap::new();
+    if opt_level > 0 {
+        reads::count_field_reads(&f.body, &mut field_reads);
+    }
+    let mut env = Env::new(f.var_needs_stack, field_reads, debug_assertions);
+    let mut prologue = vec![];
     for (id, _, tp) in f.args {
-        let id = env.add_var(id);
-        args.push((id, tp));
+        if env.needs_stack(id) {
+            // the argument itself is a register, copy it to the stack
+            let arg_id = env.fresh_var();
+            let slot = env.add_stack_var(id, tp.clone());
+            let val = out_a::Expr::Value(ast::Value::Var(out_a::VarRef::Local(arg_id)));
+            prologue.extend(spill(slot, &tp, val));
+            args.push((arg_id, tp));
+        } else {
+            let id = env.add_var(id);
+            args.push((id, tp));
+        }
     }
     let returns = f.returns;
 
-    let body = tr_expr(&mut env, f.body);
+    let mut body = tr_expr(&mut env, f.body);
+    if returns.is_empty() {
+        // the value of the body isn't returned, drop it
+        body = out_a::Expr::Block {
+            exprs: vec![body],
+            last_expr: Box::new(out_a::Expr::Value(ast::Value::Unit)),
+        };
+    }
+    let body = if prologue.is_empty() {
+        body
+    } else {
+        out_a::Expr::Block {
+            exprs: prologue,
+            last_expr: Box::new(body),
+        }
+    };
 
     out_a::Func {
         id: f.id,
@@ -39,10 +80,45 @@ fn tr_func(f: in_a::Func) -> out_a::Func {
 fn tr_expr(env: &mut Env, e: in_a::Expr) -> out_a::Expr {
     match e {
         in_a::Expr::NumLit(n, tp) => out_a::Expr::Value(ast::Value::Const(n, tp)),
-        in_a::Expr::StringLit(_, layout) => todo!(),
+        in_a::Expr::StringLit(s, layout) => match layout.kind {
+            // pointer to the bytes, NUL terminated so they can be passed to C
+            LayoutKind::Primitive(_) => {
+                let mut bytes = s;
+                bytes.push(0);
+                out_a::Expr::StaticData(bytes)
+            }
+            LayoutKind::Struct(items) => {
+                // byte array initialized in place, copy the bytes into a slot
+                let ss = out_a::Expr::StackSlot {
+                    size: layout.size,
+                    align: layout.align,
+                };
+                let id = env.fresh_var();
+                let s_v = ast::Value::Var(out_a::VarRef::Local(id));
+                let mut exprs = vec![out_a::Expr::Let {
+                    id,
+                    e1: Box::new(ss),
+                }];
+                for (byte, (_, offset)) in s.into_iter().zip(items) {
+                    let val = ast::Value::Const(byte as usize, Type::Tu8);
+                    let st = out_a::Expr::Store {
+                        ptr: Box::new(out_a::Expr::Value(s_v.clone())),
+                        val: Box::new(out_a::Expr::Value(val)),
+                        offset,
+                    };
+                    exprs.push(st);
+                }
+                out_a::Expr::Block {
+                    exprs,
+                    last_expr: Box::new(out_a::Expr::Value(s_v)),
+                }
+            }
+            LayoutKind::Union(_) => unreachable!("string literal is never a union"),
+        },
         in_a::Expr::Tuple { fields, layout } => {
             let ss = out_a::Expr::StackSlot {
-                size: layout.size as u32,
+                size: layout.size,
+                align: layout.align,
             };
             let id = env.fresh_var();
             let var = out_a::VarRef::Local(id);
@@ -59,13 +135,9 @@ fn tr_expr(env: &mut Env, e: in_a::Expr) -> out_a::Expr {
             };
             for (id, field) in fields.into_iter().enumerate() {
                 let field = tr_expr(env, field);
-                let (layout, offset) = layouts[id].clone();
-                let st = out_a::Expr::Store {
-                    ptr: Box::new(ast::Expr::Value(s_v.clone())),
-                    val: Box::new(field),
-                    offset,
-                };
-                exprs.push(st);
+                let (layout, offset) = &layouts[id];
+                let ptr = ast::Expr::Value(s_v.clone());
+                exprs.push(store(env, ptr, *offset, field, layout));
             }
             out_a::Expr::Block {
                 exprs,
@@ -78,22 +150,55 @@ fn tr_expr(env: &mut Env, e: in_a::Expr) -> out_a::Expr {
             args_tp,
             ret_tp,
         } => {
-            let expr = tr_expr(env, *expr);
+            let expr = Box::new(tr_expr(env, *expr));
+            let mut args: Vec<_> = args
+                .into_iter()
+                .zip(&args_tp)
+                .map(|(a, layout)| {
+                    let a = tr_expr(env, a);
+                    match layout.kind {
+                        LayoutKind::Primitive(_) => a,
+                        // the callee gets the address of its own copy
+                        LayoutKind::Struct(_) | LayoutKind::Union(_) => {
+                            copy_to_slot(env, a, layout)
+                        }
+                    }
+                })
+                .collect();
+            let sig = make_sig(args_tp, &ret_tp);
 
             match &ret_tp.kind {
-                LayoutKind::Primitive(tp) => {
-                    let args = args.into_iter().map(|a| tr_expr(env, a)).collect();
-                    let sig = make_sig(args_tp, ret_tp);
-                    out_a::Expr::FunCall {
-                        expr: Box::new(expr),
-                        args,
-                        sig,
+                LayoutKind::Primitive(_) => out_a::Expr::FunCall { expr, args, sig },
+                // nothing is returned, the result is an empty temporary
+                _ if ret_tp.size == 0 => {
+                    let unit = in_a::Expr::Tuple {
+                        fields: vec![],
+                        layout: ret_tp,
+                    };
+                    out_a::Expr::Block {
+                        exprs: vec![out_a::Expr::FunCall { expr, args, sig }],
+                        last_expr: Box::new(tr_expr(env, unit)),
                     }
                 }
-                LayoutKind::Struct(_) => {
-                    todo!("sret is not implemented yet")
+                // the callee writes the result to a slot of the caller
+                LayoutKind::Struct(_) | LayoutKind::Union(_) => {
+                    let slot = env.fresh_var();
+                    args.insert(0, local(slot));
+                    let ss = out_a::Expr::StackSlot {
+                        size: ret_tp.size,
+                        align: ret_tp.align,
+                    };
+                    out_a::Expr::Block {
+                        exprs: vec![
+                            out_a::Expr::Let {
+                                id: slot,
+                                e1: Box::new(ss),
+                            },
+                            out_a::Expr::FunCall { expr, args, sig },
+                        ],
+                        last_expr: Box::new(local(slot)),
+                    }
                 }
-                LayoutKind::Union(layouts) => todo!(),
             }
         }
         in_a::Expr::FieldAccess {
@@ -101,23 +206,24 @@ fn tr_expr(env: &mut Env, e: in_a::Expr) -> out_a::Expr {
             field_id,
             struct_layout,
             element_layout,
-        } => match struct_layout.kind {
-            LayoutKind::Primitive(tp) => todo!(),
-            LayoutKind::Struct(items) => {
-                let (layout, offset) = items[field_id].clone();
-                let ptr = Box::new(tr_expr(env, *object));
-                match layout.kind {
-                    LayoutKind::Primitive(tp) => out_a::Expr::Load {
-                        tp,
-                        ptr,
-                        offset: offset,
-                    },
-                    LayoutKind::Struct(items) => todo!(),
-                    LayoutKind::Union(layouts) => todo!(),
-                }
+        } => {
+            if let in_a::Expr::Var(in_a::VarRef::Local(var)) = object.as_ref()
+                && let Some(cache) = env.cached_field(env.lookup(*var), field_id)
+            {
+                return out_a::Expr::Value(ast::Value::Var(out_a::VarRef::Local(cache)));
             }
-            LayoutKind::Union(layouts) => todo!(),
-        },
+            let (ptr, offset) = tr_place(env, *object);
+            let offset = offset + field_offset(&struct_layout, field_id);
+            match element_layout.kind {
+                LayoutKind::Primitive(tp) => out_a::Expr::Load {
+                    tp,
+                    ptr: Box::new(ptr),
+                    offset,
+                },
+                // aggregates are pointers, point into the object instead of copying
+                LayoutKind::Struct(_) | LayoutKind::Union(_) => offset_ptr(ptr, offset),
+            }
+        }
         in_a::Expr::Block {
             exprs,
             last_expr,
@@ -132,33 +238,187 @@ fn tr_expr(env: &mut Env, e: in_a::Expr) -> out_a::Expr {
         }
         in_a::Expr::Return { expr, ret_tp } => {
             let expr = tr_expr(env, *expr);
-            out_a::Expr::Return {
-                expr: Box::new(expr),
+            match ret_tp {
+                Some(_) => out_a::Expr::Return {
+                    expr: Box::new(expr),
+                },
+                None => out_a::Expr::Block {
+                    exprs: vec![expr],
+                    last_expr: Box::new(out_a::Expr::Return {
+                        expr: Box::new(out_a::Expr::Value(ast::Value::Unit)),
+                    }),
+                },
             }
         }
         in_a::Expr::Assign {
             lval,
             rval,
             assign_tp,
-        } => todo!(),
-        in_a::Expr::Ref { var, tp } => todo!(),
-        in_a::Expr::RefMut { var, tp } => todo!(),
-        in_a::Expr::Deref { expr, in_tp } => todo!(),
-        in_a::Expr::Char(_) => todo!(),
-        in_a::Expr::ArrayInitRepeat(expr, _, layout) => todo!(),
-        in_a::Expr::ArrayInitExact(exprs, layout) => todo!(),
-        in_a::Expr::While { pred, block } => todo!(),
+        } => {
+            let rval = tr_expr(env, *rval);
+            match (*lval, &assign_tp.kind) {
+                (in_a::Expr::Var(in_a::VarRef::Local(id)), LayoutKind::Primitive(_))
+                    if env.stack_var(env.lookup(id)).is_none() =>
+                {
+                    out_a::Expr::Assign {
+                        id: env.lookup(id),
+                        e1: Box::new(rval),
+                    }
+                }
+                (lval, _) => {
+                    let (ptr, offset) = tr_place(env, lval);
+                    store(env, ptr, offset, rval, &assign_tp)
+                }
+            }
+        }
+        in_a::Expr::Ref { expr, in_tp } | in_a::Expr::RefMut { expr, in_tp } => {
+            match in_tp.kind {
+                _ if is_place(&expr) => {
+                    let (ptr, offset) = tr_place(env, *expr);
+                    if offset == 0 {
+                        ptr
+                    } else {
+                        let offset = ast::Value::Const(offset as usize, Type::Tusize);
+                        out_a::Expr::Builtin {
+                            name: "iadd".into(),
+                            args: vec![ptr, out_a::Expr::Value(offset)],
+                        }
+                    }
+                }
+                // reference to a temporary, it needs a slot of its own
+                LayoutKind::Primitive(tp) => {
+                    let slot = env.fresh_var();
+                    let val = tr_expr(env, *expr);
+                    out_a::Expr::Block {
+                        exprs: spill(slot, &tp, val),
+                        last_expr: Box::new(out_a::Expr::Value(ast::Value::Var(
+                            out_a::VarRef::Local(slot),
+                        ))),
+                    }
+                }
+                // aggregates are already represented by their address
+                LayoutKind::Struct(_) | LayoutKind::Union(_) => tr_expr(env, *expr),
+            }
+        }
+        in_a::Expr::Deref { expr, in_tp } => {
+            let ptr = Box::new(tr_expr(env, *expr));
+            match in_tp.kind {
+                LayoutKind::Primitive(tp) => out_a::Expr::Load { tp, ptr, offset: 0 },
+                // the pointer already is the address of the aggregate
+                LayoutKind::Struct(_) | LayoutKind::Union(_) => *ptr,
+            }
+        }
+        in_a::Expr::Cast { expr, from, to } => out_a::Expr::Cast {
+            expr: Box::new(tr_expr(env, *expr)),
+            from,
+            to,
+        },
+        in_a::Expr::ArrayInitRepeat(expr, n, layout) => {
+            let slot = env.fresh_var();
+            let elem = env.fresh_var();
+            let mut exprs = vec![
+                out_a::Expr::Let {
+                    id: slot,
+                    e1: Box::new(out_a::Expr::StackSlot {
+                        size: layout.size * n as u32,
+                        align: layout.align,
+                    }),
+                },
+                out_a::Expr::Let {
+                    id: elem,
+                    e1: Box::new(tr_expr(env, *expr)),
+                },
+            ];
+            if n <= MAX_UNROLLED_ELEMS {
+                for i in 0..n {
+                    let offset = (i as u32 * layout.size) as i32;
+                    exprs.push(store(env, local(slot), offset, local(elem), &layout));
+                }
+            } else {
+                exprs.push(fill_loop(env, slot, elem, n, &layout));
+            }
+            out_a::Expr::Block {
+                exprs,
+                last_expr: Box::new(local(slot)),
+            }
+        }
+        in_a::Expr::ArrayInitExact(elems, layout) => {
+            let slot = env.fresh_var();
+            let mut exprs = vec![out_a::Expr::Let {
+                id: slot,
+                e1: Box::new(out_a::Expr::StackSlot {
+                    size: layout.size * elems.len() as u32,
+                    align: layout.align,
+                }),
+            }];
+            for (i, elem) in elems.into_iter().enumerate() {
+                let offset = (i as u32 * layout.size) as i32;
+                let val = tr_expr(env, elem);
+                exprs.push(store(env, local(slot), offset, val, &layout));
+            }
+            out_a::Expr::Block {
+                exprs,
+                last_expr: Box::new(local(slot)),
+            }
+        }
+        in_a::Expr::While { pred, block } => out_a::Expr::While {
+            pred: Box::new(tr_expr(env, *pred)),
+            block: Box::new(tr_expr(env, *block)),
+        },
+        in_a::Expr::Loop { body, tp } => out_a::Expr::Loop {
+            body: Box::new(tr_expr(env, *body)),
+            tp: value_type(&tp),
+        },
+        in_a::Expr::Break(expr) => out_a::Expr::Break(Box::new(tr_expr(env, *expr))),
+        in_a::Expr::Continue => out_a::Expr::Continue,
+        in_a::Expr::Unreachable => out_a::Expr::Unreachable,
+        in_a::Expr::If {
+            pred,
+            th,
+            el,
+            block_tp,
+        } => out_a::Expr::If {
+            pred: Box::new(tr_expr(env, *pred)),
+            th: Box::new(tr_expr(env, *th)),
+            el: Box::new(tr_expr(env, *el)),
+            tp: value_type(&block_tp),
+        },
         in_a::Expr::IndexAccess {
             arr,
             index,
             arr_layout,
             elem_layout,
-        } => todo!(),
+        } => {
+            let arr = tr_expr(env, *arr);
+            let ptr = Box::new(elem_addr(env, arr, *index, &arr_layout, &elem_layout));
+            match elem_layout.kind {
+                LayoutKind::Primitive(tp) => out_a::Expr::Load { tp, ptr, offset: 0 },
+                LayoutKind::Struct(_) | LayoutKind::Union(_) => *ptr,
+            }
+        }
+        in_a::Expr::SliceIndex {
+            slice,
+            index,
+            elem_layout,
+        } => {
+            let ptr = Box::new(slice_elem_addr(env, *slice, *index, &elem_layout));
+            match elem_layout.kind {
+                LayoutKind::Primitive(tp) => out_a::Expr::Load { tp, ptr, offset: 0 },
+                LayoutKind::Struct(_) | LayoutKind::Union(_) => *ptr,
+            }
+        }
         in_a::Expr::Var(var_ref) => match var_ref {
             in_a::VarRef::Local(var_id) => {
                 let id = env.lookup(var_id);
-
-                out_a::Expr::Value(ast::Value::Var(ast::VarRef::Local(id)))
+                let var = out_a::Expr::Value(ast::Value::Var(ast::VarRef::Local(id)));
+                match env.stack_var(id) {
+                    Some(tp) => out_a::Expr::Load {
+                        tp: tp.clone(),
+                        ptr: Box::new(var),
+                        offset: 0,
+                    },
+                    None => var,
+                }
             }
             in_a::VarRef::Global(id) => {
                 out_a::Expr::Value(ast::Value::Var(ast::VarRef::Global(id)))
@@ -170,11 +430,81 @@ fn tr_expr(env: &mut Env, e: in_a::Expr) -> out_a::Expr {
             is_mut,
             expr,
         } => {
-            let e1 = tr_expr(env, *expr);
-            let id = env.add_var(id);
-            out_a::Expr::Let {
-                id,
-                e1: Box::new(e1),
+            let e1 = if layout.require_stack() && !is_fresh(&expr) {
+                // aggregates are pointers, binding one that lives elsewhere
+                // would alias it
+                let ss = out_a::Expr::StackSlot {
+                    size: layout.size,
+                    align: layout.align,
+                };
+                let slot = env.fresh_var();
+                let s_v = ast::Value::Var(out_a::VarRef::Local(slot));
+                let src = tr_expr(env, *expr);
+                let copy = store(env, out_a::Expr::Value(s_v.clone()), 0, src, &layout);
+                out_a::Expr::Block {
+                    exprs: vec![
+                        out_a::Expr::Let {
+                            id: slot,
+                            e1: Box::new(ss),
+                        },
+                        copy,
+                    ],
+                    last_expr: Box::new(out_a::Expr::Value(s_v)),
+                }
+            } else {
+                tr_expr(env, *expr)
+            };
+            match layout.kind {
+                LayoutKind::Primitive(tp) if env.needs_stack(id) => {
+                    let slot = env.add_stack_var(id, tp.clone());
+                    out_a::Expr::Block {
+                        exprs: spill(slot, &tp, e1),
+                        last_expr: Box::new(out_a::Expr::Value(ast::Value::Unit)),
+                    }
+                }
+                LayoutKind::Struct(items) if !is_mut => {
+                    let var_id = env.add_var(id);
+                    let var = || out_a::Expr::Value(ast::Value::Var(out_a::VarRef::Local(var_id)));
+                    let mut exprs = vec![out_a::Expr::Let {
+                        id: var_id,
+                        e1: Box::new(e1),
+                    }];
+                    // the variable never changes, so fields read repeatedly
+                    // can be loaded once
+                    for (field_id, (layout, offset)) in items.into_iter().enumerate() {
+                        let LayoutKind::Primitive(tp) = layout.kind else {
+                            continue;
+                        };
+                        if env.field_reads(id, field_id) < 2 {
+                            continue;
+                        }
+                        let cache = env.fresh_var();
+                        let load = out_a::Expr::Load {
+                            tp,
+                            ptr: Box::new(var()),
+                            offset,
+                        };
+                        exprs.push(out_a::Expr::Let {
+                            id: cache,
+                            e1: Box::new(load),
+                        });
+                        env.cache_field(var_id, field_id, cache);
+                    }
+                    if exprs.len() == 1 {
+                        return exprs.pop().unwrap();
+                    }
+                    out_a::Expr::Block {
+                        exprs,
+                        last_expr: Box::new(out_a::Expr::Value(ast::Value::Unit)),
+                    }
+                }
+                _ => {
+                    let id = env.add_var(id);
+                    out_a::Expr::Let {
+                        id,
+                        e1: Box::new(e1),
+                    }
+                }
             }
         }
         in_a::Expr::Builtin(name, exprs) => {
@@ -184,19 +514,391 @@ fn tr_expr(env: &mut Env, e: in_a::Expr) -> out_a::Expr {
     }
 }
 
-fn make_sig(args_tp: Vec<Layout>, ret_tp: Layout) -> ast::FnSig {
+/// Check if expression evaluates to an aggregate in a slot of its own,
+/// which nothing else points to.
+fn is_fresh(e: &in_a::Expr) -> bool {
+    match e {
+        in_a::Expr::Tuple { .. }
+        | in_a::Expr::StringLit(..)
+        | in_a::Expr::ArrayInitRepeat(..)
+        | in_a::Expr::ArrayInitExact(..)
+        | in_a::Expr::FunCall { .. } => true,
+        in_a::Expr::Block { last_expr, .. } => is_fresh(last_expr),
+        in_a::Expr::If { th, el, .. } => is_fresh(th) && is_fresh(el),
+        _ => false,
+    }
+}
+
+/// Check if expression denotes a memory location.
+fn is_place(e: &in_a::Expr) -> bool {
+    matches!(
+        e,
+        in_a::Expr::Var(_)
+            | in_a::Expr::FieldAccess { .. }
+            | in_a::Expr::Deref { .. }
+            | in_a::Expr::IndexAccess { .. }
+            | in_a::Expr::SliceIndex { .. }
+    )
+}
+
+/// Translate an assignable expression to its address and offset.
+fn tr_place(env: &mut Env, e: in_a::Expr) -> (out_a::Expr, i32) {
+    match e {
+        in_a::Expr::FieldAccess {
+            object,
+            field_id,
+            struct_layout,
+            ..
+        } => {
+            let (ptr, offset) = tr_place(env, *object);
+            (ptr, offset + field_offset(&struct_layout, field_id))
+        }
+        in_a::Expr::Deref { expr, .. } => (tr_expr(env, *expr), 0),
+        in_a::Expr::IndexAccess {
+            arr,
+            index,
+            arr_layout,
+            elem_layout,
+        } => {
+            let (ptr, offset) = tr_place(env, *arr);
+            (
+                elem_addr(env, ptr, *index, &arr_layout, &elem_layout),
+                offset,
+            )
+        }
+        // the slice is only read, the element is elsewhere
+        in_a::Expr::SliceIndex {
+            slice,
+            index,
+            elem_layout,
+        } => (slice_elem_addr(env, *slice, *index, &elem_layout), 0),
+        // variables in memory hold the address of their slot
+        in_a::Expr::Var(in_a::VarRef::Local(id)) => {
+            let id = env.lookup(id);
+            (
+                out_a::Expr::Value(ast::Value::Var(ast::VarRef::Local(id))),
+                0,
+            )
+        }
+        e => (tr_expr(env, e), 0),
+    }
+}
+
+/// Offset of a field within a struct.
+fn field_offset(struct_layout: &Layout, field_id: usize) -> i32 {
+    match &struct_layout.kind {
+        LayoutKind::Struct(items) => items[field_id].1,
+        LayoutKind::Primitive(_) | LayoutKind::Union(_) => {
+            unreachable!("field access on a non-struct")
+        }
+    }
+}
+
+/// Address `offset` bytes past `ptr`.
+fn offset_ptr(ptr: out_a::Expr, offset: i32) -> out_a::Expr {
+    if offset == 0 {
+        return ptr;
+    }
+    out_a::Expr::Builtin {
+        name: "iadd".into(),
+        args: vec![
+            ptr,
+            out_a::Expr::Value(ast::Value::Const(offset as usize, Type::Tusize)),
+        ],
+    }
+}
+
+/// Copy an aggregate to a fresh stack slot, evaluating to its address.
+fn copy_to_slot(env: &mut Env, val: out_a::Expr, layout: &Layout) -> out_a::Expr {
+    let slot = env.fresh_var();
+    let ss = out_a::Expr::StackSlot {
+        size: layout.size,
+        align: layout.align,
+    };
+    out_a::Expr::Block {
+        exprs: vec![
+            out_a::Expr::Let {
+                id: slot,
+                e1: Box::new(ss),
+            },
+            store(env, local(slot), 0, val, layout),
+        ],
+        last_expr: Box::new(local(slot)),
+    }
+}
+
+/// Store value of given layout at `ptr + offset`.
+///
+/// Aggregates are copied from the address `val` evaluates to.
+fn store(
+    env: &mut Env,
+    ptr: out_a::Expr,
+    offset: i32,
+    val: out_a::Expr,
+    layout: &Layout,
+) -> out_a::Expr {
+    if let LayoutKind::Primitive(_) = layout.kind {
+        return out_a::Expr::Store {
+            ptr: Box::new(ptr),
+            val: Box::new(val),
+            offset,
+        };
+    }
+    let dst = env.fresh_var();
+    let src = env.fresh_var();
+    let dst_v = || {
+        Box::new(out_a::Expr::Value(ast::Value::Var(out_a::VarRef::Local(
+            dst,
+        ))))
+    };
+    let src_v = || {
+        Box::new(out_a::Expr::Value(ast::Value::Var(out_a::VarRef::Local(
+            src,
+        ))))
+    };
+    let mut exprs = vec![
+        out_a::Expr::Let {
+            id: src,
+            e1: Box::new(val),
+        },
+        out_a::Expr::Let {
+            id: dst,
+            e1: Box::new(ptr),
+        },
+    ];
+    let mut leaves = vec![];
+    primitive_leaves(layout, 0, &mut leaves);
+    for (tp, leaf_offset) in leaves {
+        let load = out_a::Expr::Load {
+            tp,
+            ptr: src_v(),
+            offset: leaf_offset,
+        };
+        exprs.push(out_a::Expr::Store {
+            ptr: dst_v(),
+            val: Box::new(load),
+            offset: offset + leaf_offset,
+        });
+    }
+    out_a::Expr::Block {
+        exprs,
+        last_expr: Box::new(out_a::Expr::Value(ast::Value::Unit)),
+    }
+}
+
+/// Collect primitive parts of a layout together with their offsets.
+fn primitive_leaves(layout: &Layout, base: i32, out: &mut Vec<(Type, i32)>) {
+    match &layout.kind {
+        LayoutKind::Primitive(tp) => out.push((tp.clone(), base)),
+        LayoutKind::Struct(items) => {
+            for (layout, offset) in items {
+                primitive_leaves(layout, base + offset, out)
+            }
+        }
+        // the active member is unknown, copy the bytes in aligned chunks
+        LayoutKind::Union(_) => {
+            let (tp, size) = match layout.align {
+                1 => (Type::Tu8, 1),
+                2 => (Type::Tu16, 2),
+                4 => (Type::Tu32, 4),
+                _ => (Type::Tu64, 8),
+            };
+            for offset in (0..layout.size as i32).step_by(size) {
+                out.push((tp.clone(), base + offset))
+            }
+        }
+    }
+}
+
+/// Store `n` copies of the element held in `elem` in consecutive memory
+/// starting at the address held in `slot`, using a loop.
+fn fill_loop(
+    env: &mut Env,
+    slot: out_a::VarID,
+    elem: out_a::VarID,
+    n: usize,
+    layout: &Layout,
+) -> out_a::Expr {
+    let ptr = env.fresh_var();
+    let end = env.fresh_var();
+    let add = |x: out_a::Expr, y: usize| out_a::Expr::Builtin {
+        name: "iadd".into(),
+        args: vec![x, out_a::Expr::Value(ast::Value::Const(y, Type::Tusize))],
+    };
+    let body = out_a::Expr::Block {
+        exprs: vec![
+            store(env, local(ptr), 0, local(elem), layout),
+            out_a::Expr::Assign {
+                id: ptr,
+                e1: Box::new(add(local(ptr), layout.size as usize)),
+            },
+        ],
+        last_expr: Box::new(out_a::Expr::Value(ast::Value::Unit)),
+    };
+    out_a::Expr::Block {
+        exprs: vec![
+            out_a::Expr::Let {
+                id: ptr,
+                e1: Box::new(local(slot)),
+            },
+            out_a::Expr::Let {
+                id: end,
+                e1: Box::new(add(local(slot), layout.size as usize * n)),
+            },
+            out_a::Expr::While {
+                pred: Box::new(out_a::Expr::Builtin {
+                    name: "ult".into(),
+                    args: vec![local(ptr), local(end)],
+                }),
+                block: Box::new(body),
+            },
+        ],
+        last_expr: Box::new(out_a::Expr::Value(ast::Value::Unit)),
+    }
+}
+
+/// Type of the value an expression of given layout evaluates to,
+/// `None` for unit.
+fn value_type(layout: &Layout) -> Option<Type> {
+    match &layout.kind {
+        LayoutKind::Primitive(tp) => Some(tp.clone()),
+        LayoutKind::Struct(_) | LayoutKind::Union(_) if layout.size == 0 => None,
+        // aggregates are passed around as pointers to their slots
+        LayoutKind::Struct(_) | LayoutKind::Union(_) => Some(Type::Tusize),
+    }
+}
+
+/// Address of the array element with given index.
+///
+/// With debug assertions enabled, the index is checked against the length
+/// of the array first.
+fn elem_addr(
+    env: &mut Env,
+    arr: out_a::Expr,
+    index: in_a::Expr,
+    arr_layout: &Layout,
+    elem: &Layout,
+) -> out_a::Expr {
+    let LayoutKind::Struct(items) = &arr_layout.kind else {
+        unreachable!()
+    };
+    let len = out_a::Expr::Value(ast::Value::Const(items.len(), Type::Tusize));
+    checked_elem_addr(env, arr, index, len, elem)
+}
+
+/// Address of the slice element with given index.
+///
+/// With debug assertions enabled, the index is checked against the length
+/// of the slice first.
+fn slice_elem_addr(
+    env: &mut Env,
+    slice: in_a::Expr,
+    index: in_a::Expr,
+    elem: &Layout,
+) -> out_a::Expr {
+    let slice = tr_expr(env, slice);
+    let id = env.fresh_var();
+    // the slice is a pointer to the elements followed by their count
+    let word = |offset| out_a::Expr::Load {
+        tp: Type::Tusize,
+        ptr: Box::new(local(id)),
+        offset,
+    };
+    let addr = checked_elem_addr(env, word(0), index, word(8), elem);
+    out_a::Expr::Block {
+        exprs: vec![out_a::Expr::Let {
+            id,
+            e1: Box::new(slice),
+        }],
+        last_expr: Box::new(addr),
+    }
+}
+
+/// Address of the element with given index in elements starting at `arr`,
+/// checked against `len` if debug assertions are enabled.
+fn checked_elem_addr(
+    env: &mut Env,
+    arr: out_a::Expr,
+    index: in_a::Expr,
+    len: out_a::Expr,
+    elem: &Layout,
+) -> out_a::Expr {
+    let index = tr_expr(env, index);
+    if !env.debug_assertions() {
+        return offset_elem(arr, index, elem);
+    }
+    let id = env.fresh_var();
+    let check = out_a::Expr::If {
+        pred: Box::new(out_a::Expr::Builtin {
+            name: "ult".into(),
+            args: vec![local(id), len],
+        }),
+        th: Box::new(out_a::Expr::Value(ast::Value::Unit)),
+        el: Box::new(out_a::Expr::Trap),
+        tp: None,
+    };
+    out_a::Expr::Block {
+        exprs: vec![
+            out_a::Expr::Let {
+                id,
+                e1: Box::new(index),
+            },
+            check,
+        ],
+        last_expr: Box::new(offset_elem(arr, local(id), elem)),
+    }
+}
+
+/// Address of the array element, without any checks.
+fn offset_elem(arr: out_a::Expr, index: out_a::Expr, elem: &Layout) -> out_a::Expr {
+    let size = ast::Value::Const(elem.size as usize, Type::Tusize);
+    let offset = out_a::Expr::Builtin {
+        name: "imul".into(),
+        args: vec![index, out_a::Expr::Value(size)],
+    };
+    out_a::Expr::Builtin {
+        name: "iadd".into(),
+        args: vec![arr, offset],
+    }
+}
+
+/// Reference to a local variable.
+fn local(id: out_a::VarID) -> out_a::Expr {
+    out_a::Expr::Value(ast::Value::Var(out_a::VarRef::Local(id)))
+}
+
+/// Allocate a stack slot for a scalar and store the value in it.
+fn spill(slot: out_a::VarID, tp: &Type, val: out_a::Expr) -> Vec<out_a::Expr> {
+    let size = tp.to_cl_type().bytes();
+    let ss = out_a::Expr::StackSlot { size, align: size };
+    let ptr = out_a::Expr::Value(ast::Value::Var(out_a::VarRef::Local(slot)));
+    vec![
+        out_a::Expr::Let {
+            id: slot,
+            e1: Box::new(ss),
+        },
+        out_a::Expr::Store {
+            ptr: Box::new(ptr),
+            val: Box::new(val),
+            offset: 0,
+        },
+    ]
+}
+
+fn make_sig(args_tp: Vec<Layout>, ret_tp: &Layout) -> ast::FnSig {
     let mut params = vec![];
     let mut returns = vec![];
-    match ret_tp.kind {
-        LayoutKind::Primitive(tp) => returns.push(tp),
-        LayoutKind::Struct(items) => todo!(),
-        LayoutKind::Union(layouts) => todo!(),
+    match &ret_tp.kind {
+        LayoutKind::Primitive(tp) => returns.push(tp.clone()),
+        _ if ret_tp.size == 0 => (),
+        // address of the result slot
+        LayoutKind::Struct(_) | LayoutKind::Union(_) => params.push(Type::Tusize),
     }
     for arg in args_tp {
         match arg.kind {
             LayoutKind::Primitive(tp) => params.push(tp),
-            LayoutKind::Struct(items) => todo!(),
-            LayoutKind::Union(layouts) => todo!(),
+            // aggregates are passed by address
+            LayoutKind::Struct(_) | LayoutKind::Union(_) => params.push(Type::Tusize),
         }
     }
     ast::FnSig { params, returns }
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use crate::mir::ast::{Expr, VarID, VarRef};
+
+/// Count reads of fields of local variables, by variable and field id.
+pub(crate) fn count_field_reads(e: &Expr, counts: &mut HashMap<(VarID, usize), usize>) {
+    match e {
+        Expr::NumLit(_, _)
+        | Expr::StringLit(_, _)
+        | Expr::Var(_)
+        | Expr::Continue
+        | Expr::Unreachable => (),
+        Expr::FieldAccess {
+            object, field_id, ..
+        } => match object.as_ref() {
+            Expr::Var(VarRef::Local(id)) => *counts.entry((*id, *field_id)).or_default() += 1,
+            object => count_field_reads(object, counts),
+        },
+        Expr::Tuple { fields: exprs, .. }
+        | Expr::ArrayInitExact(exprs, _)
+        | Expr::Builtin(_, exprs) => {
+            for e in exprs {
+                count_field_reads(e, counts)
+            }
+        }
+        Expr::FunCall { expr, args, .. } => {
+            count_field_reads(expr, counts);
+            for e in args {
+                count_field_reads(e, counts)
+            }
+        }
+        Expr::Block {
+            exprs, last_expr, ..
+        } => {
+            for e in exprs {
+                count_field_reads(e, counts)
+            }
+            count_field_reads(last_expr, counts)
+        }
+        Expr::Return { expr, .. }
+        | Expr::Loop { body: expr, .. }
+        | Expr::Break(expr)
+        | Expr::Let { expr, .. }
+        | Expr::Ref { expr, .. }
+        | Expr::RefMut { expr, .. }
+        | Expr::Deref { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::ArrayInitRepeat(expr, _, _) => count_field_reads(expr, counts),
+        Expr::Assign { lval, rval, .. } => {
+            count_field_reads(lval, counts);
+            count_field_reads(rval, counts)
+        }
+        Expr::While { pred, block } => {
+            count_field_reads(pred, counts);
+            count_field_reads(block, counts)
+        }
+        Expr::If { pred, th, el, .. } => {
+            count_field_reads(pred, counts);
+            count_field_reads(th, counts);
+            count_field_reads(el, counts)
+        }
+        Expr::IndexAccess { arr, index, .. }
+        | Expr::SliceIndex {
+            slice: arr, index, ..
+        } => {
+            count_field_reads(arr, counts);
+            count_field_reads(index, counts)
+        }
+    }
+}
@@ -2,54 +2,102 @@ use std::process::exit;
 
 use crate::{
     Cli, codegen, core,
-    error::{InternalError, ariadne_renderer::AriadneRenderer, context::Context},
-    mir, mod_tree,
+    error::{InternalError, context::Context, diagnostic::DiagnosticRenderer},
+    mir, mod_tree, mono,
     parser::parse_project,
     resolve, typecheck,
 };
 
-/// Run the compiler.
-pub fn run(config: Cli) -> Result<(), InternalError> {
-    let mut ctx = Context::init(Box::new(AriadneRenderer::new()));
+/// Stage of the pipeline to stop after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Emit {
+    /// Parsed AST.
+    Ast,
+    /// AST with names resolved.
+    Resolved,
+    /// AST after typechecking, printed with the inferred types.
+    Typed,
+    /// Mid-level IR, with generic functions specialized.
+    Mir,
+    /// Core IR.
+    Core,
+    /// Object file, the whole pipeline.
+    Obj,
+}
+
+/// Run the compiler, showing diagnostics with given renderer.
+pub fn run(config: Cli, renderer: Box<dyn DiagnosticRenderer>) -> Result<(), InternalError> {
+    let mut ctx = Context::init(renderer);
+    for lint in config.warn {
+        ctx.enable_lint(lint);
+    }
+    ctx.set_error_limit(config.error_limit);
 
     let prog = parse_project(&config.dir, &mut ctx)?;
 
-    if config.print_input_ast {
+    if config.emit == Emit::Ast {
         println!("{:#?}", prog);
-        ctx.finish()?;
-        return Ok(());
+        return finish(ctx);
     }
 
     let prog = mod_tree::translate(&mut ctx, prog)?;
 
     let prog = resolve::translate(&mut ctx, prog)?;
 
+    if config.emit == Emit::Resolved {
+        println!("{:#?}", prog);
+        return finish(ctx);
+    }
+
     let prog = typecheck::translate(&mut ctx, prog)?;
 
-    let error_count = ctx.finish()?;
+    let triple = codegen::target_triple(config.target.as_deref())?;
+    codegen::check_target(&mut ctx, &triple, &prog.sym_table);
 
-    if error_count != 0 {
-        println!("{} errors occurred, compilation aborted.", error_count);
-        exit(1)
+    if config.emit == Emit::Typed {
+        print!("{}", prog);
+        return finish(ctx);
     }
 
-    if config.typecheck_only {
-        return Ok(());
-    }
+    finish(ctx)?;
+
+    let prog = mono::translate(prog)?;
 
     let prog = mir::translate(prog)?;
 
-    let prog = core::translate(prog);
+    if config.emit == Emit::Mir {
+        println!("{:#?}", prog);
+        return Ok(());
+    }
+
+    let prog = core::translate(prog, config.opt_level, config.debug_assertions);
 
-    if config.core_dump {
+    if config.emit == Emit::Core {
         println!("{:#?}", prog);
         return Ok(());
     }
 
-    let obj = codegen::translate(prog)?;
+    let obj = codegen::translate(prog, triple, config.opt_level)?;
 
-    let obj_bytes = obj.emit().unwrap();
-    std::fs::write("output.o", obj_bytes).unwrap();
+    let obj_bytes = obj
+        .emit()
+        .map_err(|e| InternalError::AnyMsg(format!("failed to emit object file: {}", e)))?;
 
+    let output = match config.output {
+        Some(path) => path,
+        None => config.dir.join("a.o"),
+    };
+    std::fs::write(&output, obj_bytes)?;
+
+    Ok(())
+}
+
+/// Show collected diagnostics, aborting compilation if there were errors.
+fn finish(ctx: Context) -> Result<(), InternalError> {
+    let error_count = ctx.finish()?;
+    if error_count != 0 {
+        println!("{} errors occurred, compilation aborted.", error_count);
+        exit(1)
+    }
     Ok(())
 }
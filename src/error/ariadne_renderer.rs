@@ -1,3 +1,5 @@
+use std::io::Write;
+
 use crate::{
     common::{Position, sources::SourceMap},
     error::diagnostic::{Diagnostic, DiagnosticRenderer, Label, Severity},
@@ -24,6 +26,14 @@ impl DiagnosticRenderer for AriadneRenderer {
         ))?;
         report.eprint((filename, source))
     }
+
+    fn show_suppressed(&self, count: usize) -> std::io::Result<()> {
+        writeln!(
+            std::io::stderr(),
+            "Note: {} further errors were suppressed, raise `--error-limit` to see them",
+            count
+        )
+    }
 }
 
 impl<'a> From<Diagnostic> for ariadne::Report<'a, Position> {
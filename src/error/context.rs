@@ -1,21 +1,30 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use ariadne::Source;
 
 use crate::{
-    common::sources::SourceMap,
+    common::{Position, sources::SourceMap},
     error::{
-        InternalError,
-        diagnostic::{Diagnostic, DiagnosticRenderer},
+        InternalError, Lint,
+        diagnostic::{Diagnostic, DiagnosticRenderer, Severity},
     },
 };
 
+/// Identity of a diagnostic: its span, severity, and spans and messages
+/// of its labels.
+type DiagnosticKey = (Position, Severity, Vec<(Position, String)>);
+
 /// Represents context of compilation.
 pub struct Context {
     renderer: Box<dyn DiagnosticRenderer>,
     diagnostics: Vec<Diagnostic>,
+    /// Keys of the reported diagnostics, to drop repeated ones.
+    reported: HashSet<DiagnosticKey>,
     sources: SourceMap,
     err_count: usize,
+    /// Number of errors kept for rendering, `0` keeps all of them.
+    error_limit: usize,
+    lints: Vec<Lint>,
 }
 
 impl Context {
@@ -24,27 +33,80 @@ impl Context {
         Self {
             renderer,
             diagnostics: vec![],
+            reported: HashSet::new(),
             sources: SourceMap::new(),
             err_count: 0,
+            error_limit: 0,
+            lints: vec![],
         }
     }
 
+    /// Enable an optional warning.
+    pub(crate) fn enable_lint(&mut self, lint: Lint) {
+        self.lints.push(lint);
+    }
+
+    /// Keep only the first `limit` errors, `0` means no limit.
+    ///
+    /// Errors past the limit are still counted.
+    pub(crate) fn set_error_limit(&mut self, limit: usize) {
+        self.error_limit = limit;
+    }
+
+    /// Check if an optional warning is enabled.
+    pub(crate) fn lint_enabled(&self, lint: Lint) -> bool {
+        self.lints.contains(&lint)
+    }
+
     /// Print all diagnostic using provided renderer and destroy context.
-    pub(crate) fn finish(self) -> Result<usize, InternalError> {
+    ///
+    /// Diagnostics are shown in the order of their positions in the source,
+    /// the ones at the same position in the order they were reported.
+    pub(crate) fn finish(mut self) -> Result<usize, InternalError> {
+        self.diagnostics
+            .sort_by(|a, b| (&a.pos.filename, a.pos.start).cmp(&(&b.pos.filename, b.pos.start)));
         for diag in self.diagnostics {
             self.renderer
                 .show(diag, &self.sources)
                 .map_err(|e| InternalError::AnyMsg(format!("Failed to show diagnostic: {e}")))?
         }
+        if self.error_limit != 0 && self.err_count > self.error_limit {
+            self.renderer
+                .show_suppressed(self.err_count - self.error_limit)
+                .map_err(|e| InternalError::AnyMsg(format!("Failed to show diagnostic: {e}")))?
+        }
         Ok(self.err_count)
     }
 
     /// Add a diagnostic to this context.
+    ///
+    /// Only errors are counted, warnings never abort compilation.
+    /// Diagnostics identical to one reported before are dropped, the same
+    /// mistake can be found more than once.
     pub(crate) fn report(&mut self, diag: Diagnostic) {
-        self.err_count += 1;
+        let labels = diag
+            .labels
+            .iter()
+            .map(|label| (label.pos.clone(), (*label.msg)()))
+            .collect();
+        let key = (diag.pos.clone(), diag.severity.clone(), labels);
+        if !self.reported.insert(key) {
+            return;
+        }
+        if diag.severity == Severity::Error {
+            self.err_count += 1;
+            if self.error_limit != 0 && self.err_count > self.error_limit {
+                return;
+            }
+        }
         self.diagnostics.push(diag);
     }
 
+    /// Number of errors reported so far.
+    pub(crate) fn err_count(&self) -> usize {
+        self.err_count
+    }
+
     /// Add source.
     pub(crate) fn add_source(&mut self, filename: Arc<str>, source: String) {
         self.sources.add(filename, source);
@@ -4,8 +4,8 @@ use crate::common::{Position, sources::SourceMap};
 
 /// Severity of a diagnostic.
 ///
-/// Any error aborts compilation.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Any error aborts compilation, warnings are only reported.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Severity {
     Error,
     Warning,
@@ -31,6 +31,15 @@ impl Diagnostic {
         }
     }
 
+    pub(crate) fn warning(pos: &Position) -> Diagnostic {
+        Self {
+            severity: Severity::Warning,
+            pos: pos.clone(),
+            labels: vec![],
+            notes: vec![],
+        }
+    }
+
     pub fn with_label(mut self, label: Label) -> Self {
         self.labels.push(label);
         self
@@ -62,10 +71,18 @@ impl Label {
         self.msg = msg;
         self
     }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
 }
 
 /// Implementors of this trait can be used as diagnostic sinks.
 pub trait DiagnosticRenderer: Send + Sync + std::fmt::Debug {
     /// Show the diagnostic.
     fn show(&self, diag: Diagnostic, sources: &SourceMap) -> std::io::Result<()>;
+
+    /// Tell that `count` more errors were found but not shown.
+    fn show_suppressed(&self, count: usize) -> std::io::Result<()>;
 }
@@ -0,0 +1,85 @@
+use std::io::Write;
+
+use crate::{
+    common::{Position, sources::SourceMap},
+    error::diagnostic::{Diagnostic, DiagnosticRenderer, Severity},
+};
+
+/// Renders diagnostics as JSON, one object per line, for use by tools.
+///
+/// Every diagnostic has the following fields:
+/// - `severity`: `"error"` or `"warning"`,
+/// - `file`, `start`, `end`: file and byte span of the diagnostic,
+/// - `message`: message of the first label,
+/// - `labels`: list of labels, with `file`, `start`, `end` and `message`,
+/// - `notes`: list of notes.
+#[derive(Debug)]
+pub struct JsonRenderer;
+
+impl JsonRenderer {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl DiagnosticRenderer for JsonRenderer {
+    fn show(&self, diag: Diagnostic, _sources: &SourceMap) -> std::io::Result<()> {
+        let severity = match diag.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let labels: Vec<(Position, String)> = diag
+            .labels
+            .iter()
+            .map(|label| (label.pos.clone(), (*label.msg)()))
+            .collect();
+        let message = labels.first().map(|(_, msg)| msg.as_str()).unwrap_or("");
+
+        let mut out = format!("{{\"severity\":{},{}", string(severity), span(&diag.pos));
+        out += &format!(",\"message\":{},\"labels\":[", string(message));
+        for (i, (pos, msg)) in labels.iter().enumerate() {
+            if i != 0 {
+                out.push(',');
+            }
+            out += &format!("{{{},\"message\":{}}}", span(pos), string(msg));
+        }
+        out += "],\"notes\":[";
+        let notes: Vec<String> = diag.notes.iter().map(|note| string(note)).collect();
+        out += &notes.join(",");
+        out += "]}";
+        writeln!(std::io::stderr(), "{}", out)
+    }
+
+    /// Tools get all errors they asked for, the rest is left out silently.
+    fn show_suppressed(&self, _count: usize) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Fields describing a position.
+fn span(pos: &Position) -> String {
+    format!(
+        "\"file\":{},\"start\":{},\"end\":{}",
+        string(&pos.filename),
+        pos.start,
+        pos.end
+    )
+}
+
+/// JSON string literal with given contents.
+fn string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\r' => out += "\\r",
+            '\t' => out += "\\t",
+            c if c.is_control() => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -8,6 +8,39 @@ use crate::{
 pub mod ariadne_renderer;
 pub mod context;
 pub mod diagnostic;
+pub mod json_renderer;
+
+/// How diagnostics are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// Rendered with the source code, for people.
+    Human,
+    /// JSON objects, one per line, for tools.
+    Json,
+}
+
+/// Optional warnings, reported only when enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Lint {
+    /// Cast to a narrower integer type, which may lose information.
+    #[value(name = "narrowing_cast")]
+    NarrowingCast,
+    /// Local variable or parameter that is never read.
+    #[value(name = "unused_variables")]
+    UnusedVariables,
+    /// Value assigned to a mutable local that is never read.
+    #[value(name = "dead_stores")]
+    DeadStores,
+    /// Expression placed after one that never finishes, like `return`.
+    #[value(name = "unreachable_code")]
+    UnreachableCode,
+    /// Integer literal whose type isn't constrained, so it defaults to `i32`.
+    #[value(name = "defaulted_literals")]
+    DefaultedLiterals,
+    /// Enum with one variant much bigger than all the others.
+    #[value(name = "large_enum_variant")]
+    LargeEnumVariant,
+}
 
 #[derive(Debug)]
 pub enum InternalError {
@@ -17,7 +50,7 @@ pub enum InternalError {
 
 impl From<std::io::Error> for InternalError {
     fn from(value: std::io::Error) -> Self {
-        todo!()
+        InternalError::AnyMsg(format!("io error: {}", value))
     }
 }
 
@@ -45,6 +78,8 @@ impl From<ParsingError> for Diagnostic {
                     format!("Unexpected token: {}", token.bright_red())
                 })))
             }
+            ParsingError::BadLiteral { pos, msg } => Diagnostic::error(&pos)
+                .with_label(Label::new(&pos).with_msg(Box::new(move || msg.clone()))),
         }
     }
 }
@@ -67,4 +102,8 @@ pub enum ParsingError {
         pos: Position,
         token: String,
     },
+    BadLiteral {
+        pos: Position,
+        msg: String,
+    },
 }
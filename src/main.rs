@@ -2,7 +2,13 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use crate::error::InternalError;
+use crate::{
+    driver::Emit,
+    error::{
+        ErrorFormat, InternalError, Lint, ariadne_renderer::AriadneRenderer,
+        diagnostic::DiagnosticRenderer, json_renderer::JsonRenderer,
+    },
+};
 
 mod codegen;
 mod common;
@@ -11,6 +17,7 @@ mod driver;
 mod error;
 mod mir;
 mod mod_tree;
+mod mono;
 mod parser;
 mod resolve;
 mod symtable;
@@ -24,21 +31,51 @@ pub struct Cli {
     #[arg(value_name = "PATH", default_value = ".", value_hint = clap::ValueHint::DirPath)]
     dir: PathBuf,
 
-    /// Only print parsed AST and exit
-    #[arg(short, long, default_value_t = false)]
-    print_input_ast: bool,
+    /// Stop after given stage of the pipeline and print its output
+    #[arg(long, value_name = "STAGE", value_enum, default_value_t = Emit::Obj)]
+    emit: Emit,
 
-    /// Only check types and exit
-    #[arg(short, long, default_value_t = false)]
-    typecheck_only: bool,
+    /// Path of the emitted object file, defaults to `a.o` in project root
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
 
-    /// Print program in core IR
-    #[arg(short, long, default_value_t = false)]
-    core_dump: bool,
+    /// Target triple to compile for, defaults to the host
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+
+    /// Optimization level, 0 disables optimizations
+    #[arg(
+        short = 'O',
+        long,
+        value_name = "LEVEL",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u8).range(0..=2)
+    )]
+    opt_level: u8,
+
+    /// Insert runtime checks, like bounds checks of array accesses
+    #[arg(long, default_value_t = false)]
+    debug_assertions: bool,
+
+    /// Enable an optional warning
+    #[arg(short = 'W', long = "warn", value_name = "LINT")]
+    warn: Vec<Lint>,
+
+    /// Show at most this many errors, 0 shows all of them
+    #[arg(long, value_name = "N", default_value_t = 50)]
+    error_limit: usize,
+
+    /// Format of reported errors and warnings
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
 }
 
 /// Entry point, parses command line arguments and starts the compiler pipeline.
 pub fn main() -> Result<(), InternalError> {
     let cli = Cli::parse();
-    driver::run(cli)
+    let renderer: Box<dyn DiagnosticRenderer> = match cli.error_format {
+        ErrorFormat::Human => Box::new(AriadneRenderer::new()),
+        ErrorFormat::Json => Box::new(JsonRenderer::new()),
+    };
+    driver::run(cli, renderer)
 }
@@ -16,12 +16,23 @@ pub struct Symbol {
     pub name: String,
     pub kind: SymKind,
     pub is_extern: bool,
+    pub is_variadic: bool,
     pub mangle: bool,
+    pub link_name: Option<String>,
 }
 
 #[derive(Debug)]
 pub enum SymKind {
-    Func { args: Vec<Type>, returns: Vec<Type> },
+    Func {
+        args: Vec<Type>,
+        returns: Vec<Type>,
+    },
+    /// Global variable of a primitive type, with bits of its initial value.
+    Static {
+        tp: Type,
+        is_mut: bool,
+        value: usize,
+    },
 }
 
 #[derive(Debug)]
@@ -30,6 +41,7 @@ pub struct Func {
     pub args: Vec<(VarID, bool, Type)>,
     pub returns: Vec<Type>,
     pub body: Expr,
+    /// Scalar locals whose address is taken, so they can't live in registers.
     pub var_needs_stack: HashMap<VarID, bool>,
 }
 
@@ -64,7 +76,7 @@ pub enum VarRef {
 #[derive(Debug)]
 pub enum Expr {
     NumLit(usize, Type),
-    StringLit(String, Layout),
+    StringLit(Vec<u8>, Layout),
     Tuple {
         fields: Vec<Expr>,
         layout: Layout,
@@ -86,9 +98,13 @@ pub enum Expr {
         last_expr: Box<Expr>,
         block_tp: Layout,
     },
+    /// Return from the function.
+    ///
+    /// Without `ret_tp` nothing is returned in registers, the expression
+    /// is evaluated only for its effects.
     Return {
         expr: Box<Expr>,
-        ret_tp: Type,
+        ret_tp: Option<Type>,
     },
     Let {
         id: VarID,
@@ -102,30 +118,58 @@ pub enum Expr {
         assign_tp: Layout,
     },
     Ref {
-        var: VarRef,
-        tp: Type,
+        expr: Box<Expr>,
+        in_tp: Layout,
     },
     RefMut {
-        var: VarRef,
-        tp: Type,
+        expr: Box<Expr>,
+        in_tp: Layout,
     },
     Deref {
         expr: Box<Expr>,
         in_tp: Layout,
     },
-    Char(u8),
+    /// Conversion of a scalar to another scalar type.
+    Cast {
+        expr: Box<Expr>,
+        from: Type,
+        to: Type,
+    },
+    /// Array of `n` copies of an element, with layout of the element.
     ArrayInitRepeat(Box<Expr>, usize, Layout),
+    /// Array of given elements, with layout of a single element.
     ArrayInitExact(Vec<Expr>, Layout),
     While {
         pred: Box<Expr>,
         block: Box<Expr>,
     },
+    Loop {
+        body: Box<Expr>,
+        tp: Layout,
+    },
+    Break(Box<Expr>),
+    Continue,
+    If {
+        pred: Box<Expr>,
+        th: Box<Expr>,
+        el: Box<Expr>,
+        block_tp: Layout,
+    },
     IndexAccess {
         arr: Box<Expr>,
         index: Box<Expr>,
         arr_layout: Layout,
         elem_layout: Layout,
     },
+    /// Element of a slice, behind the pointer the slice holds.
+    SliceIndex {
+        slice: Box<Expr>,
+        index: Box<Expr>,
+        elem_layout: Layout,
+    },
     Var(VarRef),
     Builtin(String, Vec<Expr>),
+    /// Mark code that is never reached, like the end of a call
+    /// to a function that never returns.
+    Unreachable,
 }
@@ -37,6 +37,15 @@ impl Env {
     //     (id, stmt)
     // }
 
+    /// Variables visible at this point, to restore them when leaving a block.
+    pub(crate) fn save(&self) -> HashMap<String, VarID> {
+        self.vars.clone()
+    }
+
+    pub(crate) fn restore(&mut self, vars: HashMap<String, VarID>) {
+        self.vars = vars;
+    }
+
     pub(crate) fn lookup(&self, name: &str) -> VarID {
         *self.vars.get(name).unwrap()
     }
@@ -3,15 +3,17 @@ mod env;
 use std::collections::HashMap;
 
 use crate::{
+    common::NodeID,
     error::InternalError,
     mir::{
         ast::{VarID, VarRef},
         env::Env,
     },
     symtable::{
-        SymTable,
-        layout::{LayoutKind, Type},
+        SymKind, SymTable,
+        layout::{Layout, LayoutKind, Type},
     },
+    tp::TypeView,
     typecheck::ast as in_a,
 };
 use ast as out_a;
@@ -36,6 +38,8 @@ fn make_symtable(st: SymTable) -> HashMap<crate::common::NodeID, ast::Symbol> {
     let mut map = HashMap::new();
     for (id, info) in st.get_items() {
         let kind = match &info.kind {
+            // generic functions are only emitted as their specializations
+            crate::symtable::SymKind::Func { params, .. } if !params.is_empty() => continue,
             crate::symtable::SymKind::Func {
                 params,
                 args: old_args,
@@ -56,8 +60,11 @@ fn make_symtable(st: SymTable) -> HashMap<crate::common::NodeID, ast::Symbol> {
                     let layout = st.get_layout(&ret.clone());
                     match layout.kind {
                         LayoutKind::Primitive(tp) => returns.push(tp),
-                        LayoutKind::Struct(items) => args.push(Type::Tusize),
-                        LayoutKind::Union(layouts) => args.push(Type::Tusize),
+                        _ if layout.size == 0 => (),
+                        // address of the result slot
+                        LayoutKind::Struct(_) | LayoutKind::Union(_) => {
+                            args.insert(0, Type::Tusize)
+                        }
                     };
                 }
                 out_a::SymKind::Func { args, returns }
@@ -65,12 +72,28 @@ fn make_symtable(st: SymTable) -> HashMap<crate::common::NodeID, ast::Symbol> {
             crate::symtable::SymKind::Struct(tvar) => continue,
             crate::symtable::SymKind::Enum(tvar) => continue,
             crate::symtable::SymKind::EnumCons { id, args, parent } => continue,
+            // values of constants are inlined
+            crate::symtable::SymKind::Const { .. } => continue,
+            crate::symtable::SymKind::Static { tp, is_mut, value } => {
+                let tp = match st.get_layout(tp).kind {
+                    LayoutKind::Primitive(tp) => tp,
+                    LayoutKind::Struct(_) | LayoutKind::Union(_) => unreachable!(),
+                };
+                let value = value.expect("statics are evaluated by the typechecker");
+                out_a::SymKind::Static {
+                    tp,
+                    is_mut: *is_mut,
+                    value,
+                }
+            }
         };
         let new_info = out_a::Symbol {
             name: info.name.clone(),
             kind,
             is_extern: info.is_extern,
+            is_variadic: info.variadic.is_some(),
             mangle: info.mangle,
+            link_name: info.link_name.clone(),
         };
         map.insert(*id, new_info);
     }
@@ -87,28 +110,35 @@ fn tr_func(st: &SymTable, f: in_a::Func) -> Result<out_a::Func, InternalError> {
         let var_id = env.add_var(name);
         let tp = match layout.kind {
             LayoutKind::Primitive(tp) => tp,
-            _ => {
-                var_needs_stack.insert(var_id, true);
-                Type::Tusize
-            }
+            _ => Type::Tusize,
         };
         args.push((var_id, is_mut, tp))
     }
-    {
-        let layout = st.get_layout(&f.ret_type);
-        match layout.kind {
-            LayoutKind::Primitive(tp) => returns.push(tp),
-            LayoutKind::Struct(items) => {
-                let name = "__ret_var".into();
-                let id = env.add_var(name);
-                var_needs_stack.insert(id, true);
-                args.push((id, false, Type::Tusize))
-            }
-            LayoutKind::Union(layouts) => todo!(),
-        };
-    }
+    let layout = st.get_layout(&f.ret_type);
+    let ret_var = match &layout.kind {
+        LayoutKind::Primitive(tp) => {
+            returns.push(tp.clone());
+            None
+        }
+        _ if layout.size == 0 => None,
+        // aggregates are written to a slot of the caller,
+        // its address is passed as the first argument.
+        // There is no size threshold, even aggregates of one or two scalars
+        // take this path: every function returns at most one value
+        // in registers, which keeps calls in core and codegen single-valued.
+        LayoutKind::Struct(_) | LayoutKind::Union(_) => {
+            let name = "__ret_var".into();
+            let id = env.add_var(name);
+            args.insert(0, (id, false, Type::Tusize));
+            Some(id)
+        }
+    };
 
     let body = tr_expr(&mut env, &mut var_needs_stack, st, f.body)?;
+    let body = match ret_var {
+        Some(id) => store_result(id, body, &layout),
+        None => body,
+    };
 
     let func = out_a::Func {
         id: f.id,
@@ -135,16 +165,39 @@ fn tr_expr(
             };
             out_a::Expr::NumLit(n, tp)
         }
-        in_a::Expr::StringLit(_, _) => todo!(),
+        in_a::Expr::StringLit(s, tp) => {
+            let layout = st.get_layout(&tp);
+            out_a::Expr::StringLit(s, layout)
+        }
         in_a::Expr::LocalVar { name, tp } => {
             let id = env.lookup(&name);
             let var = out_a::VarRef::Local(id);
             out_a::Expr::Var(var)
         }
-        in_a::Expr::GlobalVar { id, tp } => {
-            let var = out_a::VarRef::Global(id);
-            out_a::Expr::Var(var)
-        }
+        in_a::Expr::GlobalVar { id, tp, .. } => match enum_cons(st, id) {
+            // constructor without arguments
+            Some(cons) if !matches!(tp.view(), TypeView::Fun(_, _)) => {
+                let layout = st.get_layout(&tp).enum_variant(cons);
+                let fields = vec![out_a::Expr::NumLit(cons, Type::Tu32)];
+                out_a::Expr::Tuple { fields, layout }
+            }
+            _ => match &st.find_sym_info(id).kind {
+                SymKind::Const { value, .. } => {
+                    let tp = match st.get_layout(&tp).kind {
+                        LayoutKind::Primitive(tp) => tp,
+                        LayoutKind::Struct(_) | LayoutKind::Union(_) => unreachable!(),
+                    };
+                    let n = value.expect("constants are evaluated by the typechecker");
+                    out_a::Expr::NumLit(n, tp)
+                }
+                // the symbol of a static is its address
+                SymKind::Static { .. } => out_a::Expr::Deref {
+                    expr: Box::new(out_a::Expr::Var(out_a::VarRef::Global(id))),
+                    in_tp: st.get_layout(&tp),
+                },
+                _ => out_a::Expr::Var(out_a::VarRef::Global(id)),
+            },
+        },
         in_a::Expr::Tuple(exprs, tp) => {
             let mut fields = vec![];
             let layout = st.get_layout(&tp);
@@ -167,18 +220,34 @@ fn tr_expr(
             args_tp,
             ret_tp,
         } => {
+            if let in_a::Expr::GlobalVar { id, .. } = *expr {
+                if let Some(cons) = enum_cons(st, id) {
+                    let mut fields = vec![out_a::Expr::NumLit(cons, Type::Tu32)];
+                    for arg in args {
+                        fields.push(tr_expr(env, vns, st, arg)?);
+                    }
+                    let layout = st.get_layout(&ret_tp).enum_variant(cons);
+                    return Ok(out_a::Expr::Tuple { fields, layout });
+                }
+            }
             let callee = tr_expr(env, vns, st, *expr)?;
             let args = args
                 .into_iter()
                 .map(|e| tr_expr(env, vns, st, e))
                 .collect::<Result<_, _>>()?;
             let args_tp = args_tp.into_iter().map(|tp| st.get_layout(&tp)).collect();
+            let diverges = is_never(&ret_tp);
             let ret_tp = st.get_layout(&ret_tp);
-            out_a::Expr::FunCall {
+            let call = out_a::Expr::FunCall {
                 expr: Box::new(callee),
                 args,
                 args_tp,
                 ret_tp,
+            };
+            if diverges {
+                unreachable_after(call)
+            } else {
+                call
             }
         }
         in_a::Expr::FieldAccess {
@@ -197,16 +266,44 @@ fn tr_expr(
                 element_layout,
             }
         }
+        // The tag and the fields are read like fields of the variant.
+        in_a::Expr::EnumTag { object, enum_tp } => {
+            let object = Box::new(tr_expr(env, vns, st, *object)?);
+            out_a::Expr::FieldAccess {
+                object,
+                field_id: 0,
+                struct_layout: st.get_layout(&enum_tp).enum_variant(0),
+                element_layout: st.get_layout(&crate::tp::Type::builtin("u32")),
+            }
+        }
+        in_a::Expr::EnumField {
+            object,
+            enum_tp,
+            cons,
+            field_id,
+            field_tp,
+        } => {
+            let object = Box::new(tr_expr(env, vns, st, *object)?);
+            out_a::Expr::FieldAccess {
+                object,
+                field_id: field_id + 1,
+                struct_layout: st.get_layout(&enum_tp).enum_variant(cons),
+                element_layout: st.get_layout(&field_tp),
+            }
+        }
         in_a::Expr::Block {
             exprs,
             last_expr,
             block_tp,
         } => {
+            // variables declared in the block go out of scope at its end
+            let vars = env.save();
             let exprs = exprs
                 .into_iter()
                 .map(|e| tr_expr(env, vns, st, e))
                 .collect::<Result<_, _>>()?;
             let last_expr = Box::new(tr_expr(env, vns, st, *last_expr)?);
+            env.restore(vars);
             let block_tp = st.get_layout(&block_tp);
             out_a::Expr::Block {
                 exprs,
@@ -220,18 +317,25 @@ fn tr_expr(
             match &layout.kind {
                 LayoutKind::Primitive(tp) => out_a::Expr::Return {
                     expr: Box::new(expr),
-                    ret_tp: tp.clone(),
+                    ret_tp: Some(tp.clone()),
                 },
-                LayoutKind::Struct(items) => {
+                _ if layout.size == 0 => out_a::Expr::Return {
+                    expr: Box::new(expr),
+                    ret_tp: None,
+                },
+                LayoutKind::Struct(_) | LayoutKind::Union(_) => {
                     let ret_v = env.lookup("__ret_var");
                     let lval = Box::new(out_a::Expr::Var(VarRef::Local(ret_v)));
-                    out_a::Expr::Assign {
+                    let assign = out_a::Expr::Assign {
                         lval,
                         rval: Box::new(expr),
-                        assign_tp: (layout),
+                        assign_tp: layout,
+                    };
+                    out_a::Expr::Return {
+                        expr: Box::new(assign),
+                        ret_tp: None,
                     }
                 }
-                LayoutKind::Union(layouts) => todo!(),
             }
         }
         in_a::Expr::Let {
@@ -240,10 +344,10 @@ fn tr_expr(
             is_mut,
             expr,
         } => {
+            // the initializer still sees the variable this one shadows
+            let expr = tr_expr(env, vns, st, *expr)?;
             let id = env.add_var(name);
             let layout = st.get_layout(&tp);
-            let expr = tr_expr(env, vns, st, *expr)?;
-            vns.insert(id, layout.require_stack());
             out_a::Expr::Let {
                 id,
                 layout,
@@ -280,17 +384,61 @@ fn tr_expr(
                 assign_tp: layout,
             }
         }
-        in_a::Expr::Ref { expr, tp } => todo!(),
-        in_a::Expr::RefMut { expr, tp } => todo!(),
-        in_a::Expr::Deref { expr, in_tp } => todo!(),
+        in_a::Expr::Ref { expr, tp } => {
+            mark_addressed(env, vns, st, &expr);
+            let in_tp = st.get_layout(&pointee(&tp));
+            let expr = Box::new(tr_expr(env, vns, st, *expr)?);
+            out_a::Expr::Ref { expr, in_tp }
+        }
+        in_a::Expr::RefMut { expr, tp } => {
+            mark_addressed(env, vns, st, &expr);
+            let in_tp = st.get_layout(&pointee(&tp));
+            let expr = Box::new(tr_expr(env, vns, st, *expr)?);
+            out_a::Expr::RefMut { expr, in_tp }
+        }
+        in_a::Expr::Deref { expr, in_tp } => {
+            let expr = tr_expr(env, vns, st, *expr)?;
+            // there is no value to read
+            if is_never(&in_tp) {
+                return Ok(unreachable_after(expr));
+            }
+            let in_tp = st.get_layout(&in_tp);
+            out_a::Expr::Deref {
+                expr: Box::new(expr),
+                in_tp,
+            }
+        }
+        in_a::Expr::Cast { expr, from, to } => {
+            let expr = tr_expr(env, vns, st, *expr)?;
+            // there is no value to convert
+            if is_never(&from) {
+                return Ok(unreachable_after(expr));
+            }
+            let (LayoutKind::Primitive(from), LayoutKind::Primitive(to)) =
+                (st.get_layout(&from).kind, st.get_layout(&to).kind)
+            else {
+                unreachable!("only scalars can be cast")
+            };
+            out_a::Expr::Cast {
+                expr: Box::new(expr),
+                from,
+                to,
+            }
+        }
         in_a::Expr::Error => todo!(),
-        in_a::Expr::Char(_) => todo!(),
         in_a::Expr::ArrayInitRepeat(expr, n, tp) => {
             let e = tr_expr(env, vns, st, *expr)?;
             let layout = st.get_layout(&tp);
             out_a::Expr::ArrayInitRepeat(Box::new(e), n, layout)
         }
-        in_a::Expr::ArrayInitExact(exprs, _) => todo!(),
+        in_a::Expr::ArrayInitExact(exprs, tp) => {
+            let exprs = exprs
+                .into_iter()
+                .map(|e| tr_expr(env, vns, st, e))
+                .collect::<Result<_, _>>()?;
+            let layout = st.get_layout(&tp);
+            out_a::Expr::ArrayInitExact(exprs, layout)
+        }
         in_a::Expr::While { pred, block } => {
             let pred = tr_expr(env, vns, st, *pred)?;
             let block = tr_expr(env, vns, st, *block)?;
@@ -299,19 +447,170 @@ fn tr_expr(
                 block: Box::new(block),
             }
         }
-        in_a::Expr::IndexAccess { arr, index, tp } => todo!(),
+        in_a::Expr::Loop { body, tp } => {
+            let body = tr_expr(env, vns, st, *body)?;
+            let tp = st.get_layout(&tp);
+            out_a::Expr::Loop {
+                body: Box::new(body),
+                tp,
+            }
+        }
+        in_a::Expr::Break(expr) => out_a::Expr::Break(Box::new(tr_expr(env, vns, st, *expr)?)),
+        in_a::Expr::Continue => out_a::Expr::Continue,
+        in_a::Expr::IndexAccess {
+            arr,
+            index,
+            arr_tp,
+            tp,
+        } => {
+            let arr = tr_expr(env, vns, st, *arr)?;
+            let index = tr_expr(env, vns, st, *index)?;
+            if let TypeView::Slice(_) | TypeView::MutSlice(_) = arr_tp.view() {
+                return Ok(out_a::Expr::SliceIndex {
+                    slice: Box::new(arr),
+                    index: Box::new(index),
+                    elem_layout: st.get_layout(&tp),
+                });
+            }
+            out_a::Expr::IndexAccess {
+                arr: Box::new(arr),
+                index: Box::new(index),
+                arr_layout: st.get_layout(&arr_tp),
+                elem_layout: st.get_layout(&tp),
+            }
+        }
+        in_a::Expr::ArrayToSlice { ptr, len, tp } => {
+            let fields = vec![
+                tr_expr(env, vns, st, *ptr)?,
+                out_a::Expr::NumLit(len, Type::Tusize),
+            ];
+            let layout = st.get_layout(&tp);
+            out_a::Expr::Tuple { fields, layout }
+        }
         in_a::Expr::If {
             pred,
             th,
             el,
             block_tp,
-        } => todo!(),
-        in_a::Expr::Builtin(name, args) => {
-            let args = args
+        } => {
+            let pred = tr_expr(env, vns, st, *pred)?;
+            let th = tr_expr(env, vns, st, *th)?;
+            let el = tr_expr(env, vns, st, *el)?;
+            let block_tp = st.get_layout(&block_tp);
+            out_a::Expr::If {
+                pred: Box::new(pred),
+                th: Box::new(th),
+                el: Box::new(el),
+                block_tp,
+            }
+        }
+        in_a::Expr::Builtin(name, args, args_tp) => {
+            let args: Vec<_> = args
                 .into_iter()
                 .map(|e| tr_expr(env, vns, st, e))
                 .collect::<Result<_, _>>()?;
+            // Fold negative literals.
+            if name == "ineg"
+                && let [out_a::Expr::NumLit(n, tp)] = &args[..]
+            {
+                let bits = tp.size() * 8;
+                let n = (*n as u64).wrapping_neg() & (u64::MAX >> (64 - bits));
+                return Ok(out_a::Expr::NumLit(n as usize, tp.clone()));
+            }
+            // Ordering and division depend on signedness, which is known only now.
+            let name = match name.as_str() {
+                "lt" | "le" | "gt" | "ge" | "idiv" => {
+                    let signed = match st.get_layout(&args_tp[0]).kind {
+                        LayoutKind::Primitive(tp) => tp.is_signed(),
+                        _ => false,
+                    };
+                    match (name.as_str(), signed) {
+                        ("idiv", true) => "sdiv".into(),
+                        ("idiv", false) => "udiv".into(),
+                        (_, true) => format!("s{}", name),
+                        (_, false) => format!("u{}", name),
+                    }
+                }
+                _ => name,
+            };
             out_a::Expr::Builtin(name, args)
         }
     })
 }
+
+/// Mark the local variable that contains given place as needing an address.
+///
+/// Aggregates always live on the stack, so only scalars are marked.
+fn mark_addressed(env: &Env, vns: &mut HashMap<VarID, bool>, st: &SymTable, e: &in_a::Expr) {
+    match e {
+        in_a::Expr::LocalVar { name, tp } => {
+            if !st.get_layout(tp).require_stack() {
+                vns.insert(env.lookup(name), true);
+            }
+        }
+        in_a::Expr::FieldAccess { object, .. } => mark_addressed(env, vns, st, object),
+        in_a::Expr::IndexAccess { arr, .. } => mark_addressed(env, vns, st, arr),
+        _ => (),
+    }
+}
+
+/// Store the value of a function body to the result slot.
+///
+/// Explicit returns at the end of the body already do it themselves.
+fn store_result(ret_var: VarID, body: out_a::Expr, layout: &Layout) -> out_a::Expr {
+    match body {
+        out_a::Expr::Block {
+            exprs,
+            last_expr,
+            block_tp,
+        } => out_a::Expr::Block {
+            exprs,
+            last_expr: Box::new(store_result(ret_var, *last_expr, layout)),
+            block_tp,
+        },
+        e @ out_a::Expr::Return { .. } => e,
+        e => out_a::Expr::Assign {
+            lval: Box::new(out_a::Expr::Var(VarRef::Local(ret_var))),
+            rval: Box::new(e),
+            assign_tp: layout.clone(),
+        },
+    }
+}
+
+/// Check if the type is `never`.
+fn is_never(tp: &crate::tp::Type) -> bool {
+    match tp.view() {
+        TypeView::NamedVar(tvar, _) | TypeView::Var(tvar) => tvar.is_never(),
+        _ => false,
+    }
+}
+
+/// Evaluate the expression, the code after it is never reached.
+fn unreachable_after(e: out_a::Expr) -> out_a::Expr {
+    out_a::Expr::Block {
+        exprs: vec![e],
+        last_expr: Box::new(out_a::Expr::Unreachable),
+        block_tp: Layout {
+            size: 0,
+            align: 1,
+            kind: LayoutKind::Struct(vec![]),
+        },
+    }
+}
+
+/// Returns the type a pointer type points to.
+fn pointee(tp: &crate::tp::Type) -> crate::tp::Type {
+    match tp.view() {
+        TypeView::Ptr(tp) | TypeView::MutPtr(tp) => *tp,
+        _ => unreachable!("reference of a non-pointer type"),
+    }
+}
+
+/// Returns index of the enum constructor with given id,
+/// if the symbol is a constructor.
+fn enum_cons(st: &SymTable, id: NodeID) -> Option<usize> {
+    match st.find_sym_info(id).kind {
+        SymKind::EnumCons { id, .. } => Some(id),
+        _ => None,
+    }
+}
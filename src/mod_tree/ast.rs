@@ -2,7 +2,7 @@ use crate::common::{Ident, NodeID, Position, RAttribute, Visibility};
 
 pub use super::ScopeInfo;
 pub use crate::parser::ast::{
-    ExprData, ExprNode, FnArg, MatchClause, PatternData, PatternNode, RTypeNode,
+    BinOp, ExprData, ExprNode, FnArg, MatchClause, PatternData, PatternNode, RTypeNode,
 };
 
 #[derive(Debug)]
@@ -29,6 +29,9 @@ pub enum ModuleItem {
     Func(Func),
     Struct(Struct),
     Enum(Enum),
+    TypeAlias(TypeAlias),
+    Const(Const),
+    Static(Static),
 }
 
 // ==== Module items ===========================================================
@@ -41,6 +44,8 @@ pub struct Func {
     pub name: Ident,
     pub type_params: Vec<Ident>,
     pub args: Vec<FnArg>,
+    /// Position of the trailing `...` of a variadic function.
+    pub variadic: Option<Position>,
     pub ret_type: Option<RTypeNode>,
     pub body: Option<ExprNode>,
     pub pos: Position,
@@ -68,6 +73,40 @@ pub struct Enum {
     pub pos: Position,
 }
 
+#[derive(Debug)]
+pub struct TypeAlias {
+    pub attributes: Vec<RAttribute>,
+    pub visibility: Visibility,
+    pub id: NodeID,
+    pub name: Ident,
+    pub type_params: Vec<Ident>,
+    pub tp: RTypeNode,
+    pub pos: Position,
+}
+
+#[derive(Debug)]
+pub struct Const {
+    pub attributes: Vec<RAttribute>,
+    pub visibility: Visibility,
+    pub id: NodeID,
+    pub name: Ident,
+    pub tp: RTypeNode,
+    pub expr: ExprNode,
+    pub pos: Position,
+}
+
+#[derive(Debug)]
+pub struct Static {
+    pub attributes: Vec<RAttribute>,
+    pub visibility: Visibility,
+    pub is_mut: bool,
+    pub id: NodeID,
+    pub name: Ident,
+    pub tp: RTypeNode,
+    pub expr: ExprNode,
+    pub pos: Position,
+}
+
 // ==== Others =================================================================
 
 #[derive(Debug)]
@@ -1,7 +1,7 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::{
-    common::{Ident, NodeID},
+    common::{Ident, NodeID, Position},
     error::{InternalError, context::Context, diagnostic::Diagnostic},
     mod_tree::{
         error,
@@ -18,6 +18,8 @@ pub(super) struct Env {
     current_namespace_path: Vec<String>,
     scope_info: ScopeInfo,
     file_map: BTreeMap<Vec<String>, in_a::Module>,
+    /// Where items were defined, to point at both definitions of a duplicate.
+    item_pos: HashMap<(NodeID, String), Position>,
 }
 
 impl Env {
@@ -33,6 +35,7 @@ impl Env {
             current_namespace_path: vec![],
             scope_info: name_tree,
             file_map: mod_map,
+            item_pos: HashMap::new(),
         }
     }
 
@@ -72,10 +75,15 @@ impl Env {
         assert_ne!(name_s, "self");
         assert_ne!(name_s, "Self");
         let mod_info = self.scope_info.get_mut(self.current_namespace_id).unwrap();
+        let key = (self.current_namespace_id, name_s.clone());
         match mod_info.items.get_mut(&name_s) {
-            Some(bind) => return Err(error::already_bound(&name.pos, name_s)),
+            Some(bind) => {
+                let prev_pos = self.item_pos.get(&key);
+                return Err(error::already_bound(&name.pos, prev_pos, name_s, bind.kind));
+            }
             None => {
                 mod_info.items.insert(name_s, binding);
+                self.item_pos.insert(key, name.pos);
             }
         }
         Ok(())
@@ -1,6 +1,9 @@
+use colored::Color;
+
 use crate::{
     common::Position,
     error::diagnostic::{Diagnostic, Label},
+    mod_tree::scope::Kind,
 };
 
 pub fn missing_module(pos: &Position, name: String) -> Diagnostic {
@@ -8,10 +11,14 @@ pub fn missing_module(pos: &Position, name: String) -> Diagnostic {
         .with_label(Label::new(pos).with_msg(Box::new(move || format!("missing module: {}", name))))
 }
 
-pub fn unbound_variable(pos: &Position, name: String) -> Diagnostic {
-    Diagnostic::error(pos).with_label(
+pub fn unbound_variable(pos: &Position, name: String, similar: Option<String>) -> Diagnostic {
+    let diag = Diagnostic::error(pos).with_label(
         Label::new(pos).with_msg(Box::new(move || format!("unbound variable: {}", name))),
-    )
+    );
+    match similar {
+        Some(similar) => diag.with_note(format!("did you mean `{}`?", similar)),
+        None => diag,
+    }
 }
 
 pub fn ambiguous_symbol(pos: &Position, name: String) -> Diagnostic {
@@ -30,8 +37,35 @@ pub fn private_item(pos: &Position, name: String) -> Diagnostic {
         .with_label(Label::new(pos).with_msg(Box::new(move || format!("{} is private", name))))
 }
 
-pub fn already_bound(pos: &Position, name: String) -> Diagnostic {
-    Diagnostic::error(pos).with_label(
-        Label::new(pos).with_msg(Box::new(move || format!("{} is already bound", name))),
-    )
+pub fn already_bound(
+    pos: &Position,
+    prev_pos: Option<&Position>,
+    name: String,
+    kind: Kind,
+) -> Diagnostic {
+    let kind = match kind {
+        Kind::Module => "module",
+        Kind::Func => "function",
+        Kind::Struct => "struct",
+        Kind::Enum => "enum",
+        Kind::Cons => "constructor",
+        Kind::BuiltinType => "type",
+        Kind::TypeAlias => "type alias",
+        Kind::Const => "constant",
+        Kind::Static => "static",
+    };
+    let diag = Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("`{}` is defined again here", name)
+    })));
+    match prev_pos {
+        Some(prev_pos) => diag.with_label(
+            Label::new(prev_pos)
+                .with_msg(Box::new(move || {
+                    format!("{} is already defined here", kind)
+                }))
+                .with_color(Color::Yellow),
+        ),
+        None => diag,
+    }
+    .with_note("overloading is not supported, names in a module must be unique".into())
 }
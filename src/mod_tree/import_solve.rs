@@ -9,36 +9,43 @@ use crate::{
     },
 };
 
-// Idea: to remove cloning, in each iteration calculate the difference that
-// should be applied to the tree, and apply it in `while let Some(dif) = ...`
-
-pub(crate) fn solve(ctx: &mut Context, name_tree: ScopeInfo) -> Result<ScopeInfo, InternalError> {
-    let mut old_tree;
-    let mut new_tree = name_tree.clone();
-    let mut changed = true;
-    while changed {
-        old_tree = new_tree.clone();
-        changed = false;
-        for (id, scope) in new_tree.iter_mut() {
+/// Bind the names brought into scopes by imports.
+///
+/// Imports may refer to names bound by other imports, so they're resolved
+/// in rounds until a round doesn't change anything. Each round collects
+/// the bindings of all the imports first and only then adds them,
+/// so the result doesn't depend on the order in which scopes are visited.
+pub(crate) fn solve(ctx: &mut Context, mut tree: ScopeInfo) -> Result<ScopeInfo, InternalError> {
+    loop {
+        let mut pending = vec![];
+        for (id, scope) in tree.iter() {
             let imports = match &scope.kind {
                 ScopeKind::Module { imports, .. } => imports,
                 _ => continue,
             };
             for import in imports {
-                changed = changed | resolve_import(&old_tree, &mut scope.items, id, import)?;
+                resolve_import(&tree, *id, import, &mut pending);
             }
         }
+        let mut changed = false;
+        for (id, name, binding) in pending {
+            let scope = tree.get_mut(id).expect("import should be in a scope");
+            changed |= add_binding(&mut scope.items, name, binding);
+        }
+        if !changed {
+            break;
+        }
     }
-    for (id, scope) in new_tree.iter() {
+    for (id, scope) in tree.iter() {
         let imports = match &scope.kind {
             ScopeKind::Module { imports, .. } => imports,
             _ => continue,
         };
         for import in imports {
-            report_import_errors(ctx, &new_tree, id, import);
+            report_import_errors(ctx, &tree, id, import);
         }
     }
-    Ok(new_tree)
+    Ok(tree)
 }
 
 fn report_import_errors(ctx: &mut Context, old_tree: &ScopeInfo, id: &NodeID, import: &Import) {
@@ -72,18 +79,19 @@ fn report_import_errors(ctx: &mut Context, old_tree: &ScopeInfo, id: &NodeID, im
     }
 }
 
+/// Collect bindings added by the import to scope `id`,
+/// if its path can already be resolved.
 fn resolve_import(
-    old_tree: &ScopeInfo,
-    items: &mut BTreeMap<String, Binding>,
-    id: &NodeID,
+    tree: &ScopeInfo,
+    id: NodeID,
     import: &Import,
-) -> Result<bool, InternalError> {
+    pending: &mut Vec<(NodeID, String, Binding)>,
+) {
     let mut private_guard = true;
-    let binding: Binding = match old_tree.find_path(*id, import.path.clone(), &mut private_guard) {
+    let binding: Binding = match tree.find_path(id, import.path.clone(), &mut private_guard) {
         Ok(b) => b,
-        Err(_) => return Ok(false),
+        Err(_) => return,
     };
-    let mut changed = false;
     let binding_id = match binding.sym {
         Symbol::Ambiguous(_) => unreachable!("find_path doesn't return ambiguous nodes"),
         Symbol::Local(node_id) | Symbol::Imported(node_id) | Symbol::GlobImported(node_id) => {
@@ -100,37 +108,14 @@ fn resolve_import(
             Some(name) => name.data.clone(),
             None => import.path.try_last().unwrap().name_str(),
         };
-        let existing_binding = match items.get_mut(&name) {
-            Some(b) => b,
-            None => {
-                items.insert(name.clone(), new_binding);
-                return Ok(true);
-            }
-        };
-        changed = match existing_binding.sym {
-            // it can shadow glob import
-            Symbol::GlobImported(id) => {
-                existing_binding.sym = Symbol::Imported(id);
-                true
-            }
-
-            // maybe its just the same symbol
-            Symbol::Local(id) | Symbol::Imported(id) if id == binding_id => changed,
-
-            // otherwise it becomes ambiguous
-            Symbol::Local(id) | Symbol::Imported(id) => {
-                make_ambiguous(binding_id, existing_binding, id)
-            }
-
-            Symbol::Ambiguous(ref mut ids) => ids.insert(binding_id),
-        };
-        return Ok(changed);
+        pending.push((id, name, new_binding));
+        return;
     }
-    let scope = match old_tree.get(binding_id) {
+    let scope = match tree.get(binding_id) {
         Some(s) => s,
         None => {
             // not a namespace
-            return Ok(false);
+            return;
         }
     };
     for (name, binding) in &scope.items {
@@ -143,35 +128,63 @@ fn resolve_import(
             }
             _ => continue,
         };
-
         let new_binding = Binding {
             vis: import.vis,
             kind: binding.kind,
             sym: Symbol::GlobImported(binding_id),
         };
-        let existing_binding = match items.get_mut(name) {
-            Some(b) => b,
-            None => {
-                items.insert(name.clone(), new_binding);
-                changed = true;
-                continue;
-            }
-        };
-        changed = match existing_binding.sym {
-            // if its local or exact-imported, glob import cant shadow it
-            Symbol::Local(_) | Symbol::Imported(_) => changed,
+        pending.push((id, name.clone(), new_binding));
+    }
+}
 
-            // if its the same import, whatever
-            Symbol::GlobImported(id) if id == binding_id => changed,
+/// Add an imported binding to a scope.
+///
+/// Returns whether the scope changed.
+fn add_binding(items: &mut BTreeMap<String, Binding>, name: String, binding: Binding) -> bool {
+    let existing_binding = match items.get_mut(&name) {
+        Some(b) => b,
+        None => {
+            items.insert(name, binding);
+            return true;
+        }
+    };
+    match (&mut existing_binding.sym, &binding.sym) {
+        // exact import shadows glob import
+        (Symbol::GlobImported(_), Symbol::Imported(_)) => {
+            *existing_binding = binding;
+            true
+        }
 
-            // otherwise it becomes ambiguous
-            Symbol::GlobImported(id) => make_ambiguous(binding_id, existing_binding, id),
-            Symbol::Ambiguous(ref mut ids) => ids.insert(binding_id),
-        };
+        // glob import cant shadow local or exact-imported symbol
+        (Symbol::Local(_) | Symbol::Imported(_), Symbol::GlobImported(_)) => false,
+
+        // maybe its just the same symbol
+        (Symbol::Local(id) | Symbol::Imported(id), Symbol::Imported(binding_id))
+        | (Symbol::GlobImported(id), Symbol::GlobImported(binding_id))
+            if id == binding_id =>
+        {
+            false
+        }
+
+        // otherwise it becomes ambiguous
+        (
+            Symbol::Local(id) | Symbol::Imported(id) | Symbol::GlobImported(id),
+            Symbol::Imported(binding_id) | Symbol::GlobImported(binding_id),
+        ) => {
+            let id = *id;
+            make_ambiguous(*binding_id, existing_binding, id)
+        }
+
+        (
+            Symbol::Ambiguous(ids),
+            Symbol::Imported(binding_id) | Symbol::GlobImported(binding_id),
+        ) => ids.insert(*binding_id),
+
+        (_, Symbol::Local(_) | Symbol::Ambiguous(_)) => {
+            unreachable!("imports only add imported symbols")
+        }
     }
-    Ok(changed)
 }
-
 fn make_ambiguous(binding_id: NodeID, existing_binding: &mut Binding, id: NodeID) -> bool {
     let mut set = HashSet::new();
     set.insert(id);
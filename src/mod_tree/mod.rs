@@ -121,6 +121,27 @@ fn tr_module(
                     continue;
                 }
             }
+            in_a::ModuleItem::TypeAlias(it) => match tr_type_alias(env, it) {
+                Ok(it) => out_a::ModuleItem::TypeAlias(it),
+                Err(diag) => {
+                    ctx.report(diag);
+                    continue;
+                }
+            },
+            in_a::ModuleItem::Const(it) => match tr_const(env, it) {
+                Ok(it) => out_a::ModuleItem::Const(it),
+                Err(diag) => {
+                    ctx.report(diag);
+                    continue;
+                }
+            },
+            in_a::ModuleItem::Static(it) => match tr_static(env, it) {
+                Ok(it) => out_a::ModuleItem::Static(it),
+                Err(diag) => {
+                    ctx.report(diag);
+                    continue;
+                }
+            },
             in_a::ModuleItem::Error => continue,
         };
         items.push(item)
@@ -343,6 +364,7 @@ fn tr_func(env: &mut Env, it: in_a::Func) -> Result<out_a::Func, Diagnostic> {
         name: it.name,
         type_params: it.type_params,
         args: it.args,
+        variadic: it.variadic,
         ret_type: it.ret_type,
         body: it.body,
         pos: it.pos,
@@ -350,3 +372,76 @@ fn tr_func(env: &mut Env, it: in_a::Func) -> Result<out_a::Func, Diagnostic> {
 
     Ok(it)
 }
+
+fn tr_type_alias(env: &mut Env, it: in_a::TypeAlias) -> Result<out_a::TypeAlias, Diagnostic> {
+    let id = NodeID::new_global();
+
+    let binding = Binding {
+        vis: it.visibility,
+        kind: scope::Kind::TypeAlias,
+        sym: scope::Symbol::Local(id),
+    };
+
+    env.add_item(it.name.clone(), binding)?;
+
+    let it = out_a::TypeAlias {
+        attributes: it.attributes,
+        visibility: it.visibility,
+        id,
+        name: it.name,
+        type_params: it.type_params,
+        tp: it.tp,
+        pos: it.pos,
+    };
+
+    Ok(it)
+}
+
+fn tr_const(env: &mut Env, it: in_a::Const) -> Result<out_a::Const, Diagnostic> {
+    let id = NodeID::new_global();
+
+    let binding = Binding {
+        vis: it.visibility,
+        kind: scope::Kind::Const,
+        sym: scope::Symbol::Local(id),
+    };
+
+    env.add_item(it.name.clone(), binding)?;
+
+    let it = out_a::Const {
+        attributes: it.attributes,
+        visibility: it.visibility,
+        id,
+        name: it.name,
+        tp: it.tp,
+        expr: it.expr,
+        pos: it.pos,
+    };
+
+    Ok(it)
+}
+
+fn tr_static(env: &mut Env, it: in_a::Static) -> Result<out_a::Static, Diagnostic> {
+    let id = NodeID::new_global();
+
+    let binding = Binding {
+        vis: it.visibility,
+        kind: scope::Kind::Static,
+        sym: scope::Symbol::Local(id),
+    };
+
+    env.add_item(it.name.clone(), binding)?;
+
+    let it = out_a::Static {
+        attributes: it.attributes,
+        visibility: it.visibility,
+        is_mut: it.is_mut,
+        id,
+        name: it.name,
+        tp: it.tp,
+        expr: it.expr,
+        pos: it.pos,
+    };
+
+    Ok(it)
+}
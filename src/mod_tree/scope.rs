@@ -56,6 +56,9 @@ pub enum Kind {
     Enum,
     Cons,
     BuiltinType,
+    TypeAlias,
+    Const,
+    Static,
 }
 
 #[derive(Debug, Clone)]
@@ -45,10 +45,22 @@ impl ScopeInfo {
     /// Private guard will allow for one private access in the path,
     /// used for accessing items in the same module or parent.
     pub fn find_path(
+        &self,
+        scope_id: NodeID,
+        path: Path,
+        private_guard: &mut bool,
+    ) -> Result<Binding, Diagnostic> {
+        self.find_path_near(scope_id, path, private_guard, &[])
+    }
+
+    /// Like [ScopeInfo::find_path], but if the first name of the path
+    /// is unbound, `locals` are also considered when suggesting a similar one.
+    pub fn find_path_near(
         &self,
         scope_id: NodeID,
         mut path: Path,
         private_guard: &mut bool,
+        locals: &[&str],
     ) -> Result<Binding, Diagnostic> {
         let name = path.pop_front_inplace().unwrap();
         let namespace = self.get(scope_id).unwrap();
@@ -94,6 +106,18 @@ impl ScopeInfo {
                         return Err(error::cannot_import_from(&name.pos, name.data.clone())
                             .with_note(format!("{} is an enum constructor", name.data)));
                     }
+                    Kind::TypeAlias => {
+                        return Err(error::cannot_import_from(&name.pos, name.data.clone())
+                            .with_note(format!("{} is a type alias", name.data)));
+                    }
+                    Kind::Const => {
+                        return Err(error::cannot_import_from(&name.pos, name.data.clone())
+                            .with_note(format!("{} is a constant", name.data)));
+                    }
+                    Kind::Static => {
+                        return Err(error::cannot_import_from(&name.pos, name.data.clone())
+                            .with_note(format!("{} is a static", name.data)));
+                    }
                     Kind::BuiltinType => unreachable!(),
                 };
                 self.find_path(*id, path, &mut private_guard)
@@ -117,25 +141,75 @@ impl ScopeInfo {
                             self.find_path(parent, path, private_guard)
                         }
                         _ => {
+                            let root = self.get(NodeID::of_root()).unwrap();
+                            if !root.items.contains_key(&name.data) {
+                                let candidates = locals
+                                    .iter()
+                                    .copied()
+                                    .chain(visible_names(namespace, true))
+                                    .chain(visible_names(root, false));
+                                let similar = similar_name(&name.data, candidates);
+                                return Err(error::unbound_variable(&name.pos, name.data, similar));
+                            }
                             // TODO: its wrong
                             path.push_front_inplace(name);
                             self.find_path(NodeID::of_root(), path, &mut false)
                         }
                     }
                 } else {
-                    Err(error::unbound_variable(&name.pos, name.data))
+                    let similar = similar_name(&name.data, visible_names(namespace, false));
+                    Err(error::unbound_variable(&name.pos, name.data, similar))
                 }
             }
         }
     }
 
-    /// Iterate all scopes mutably.
-    pub(crate) fn iter_mut(&mut self) -> std::collections::hash_map::IterMut<'_, NodeID, Scope> {
-        self.data.iter_mut()
-    }
-
     /// Iterate all scopes.
     pub(crate) fn iter(&self) -> std::collections::hash_map::Iter<'_, NodeID, Scope> {
         self.data.iter()
     }
 }
+
+/// Names in the scope that can be accessed, private ones only with `private_guard`.
+fn visible_names(scope: &Scope, private_guard: bool) -> impl Iterator<Item = &str> {
+    scope
+        .items
+        .iter()
+        .filter(move |(_, binding)| private_guard || matches!(binding.vis, Visibility::Public))
+        .map(|(name, _)| name.as_str())
+}
+
+/// Returns the candidate closest to `name`, if it's close enough to be a typo.
+///
+/// Ties are broken alphabetically, so suggestions are deterministic.
+fn similar_name<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Edit distance between two strings, counting swaps of adjacent characters
+/// as a single edit, since they're a common typo.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // dist[i][j] is the distance between the first i chars of a and j chars of b
+    let mut dist = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    dist[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitute = dist[i - 1][j - 1] + (a[i - 1] != b[j - 1]) as usize;
+            dist[i][j] = substitute.min(dist[i - 1][j] + 1).min(dist[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                dist[i][j] = dist[i][j].min(dist[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    dist[a.len()][b.len()]
+}
@@ -0,0 +1,267 @@
+//! Monomorphization of generic functions.
+//!
+//! Generic functions are typechecked once, with their type parameters
+//! left abstract. Layouts of values depend on the actual types though,
+//! so before lowering every generic function is replaced by copies
+//! specialized for each list of type arguments it's used with.
+
+use std::collections::HashMap;
+
+use crate::{
+    common::NodeID,
+    error::InternalError,
+    symtable::{SymKind, SymTable},
+    tp::{TVar, Type},
+    typecheck::ast::{Expr, Func, Program},
+};
+
+/// How deep instantiations may nest, deeper ones come from polymorphic
+/// recursion that would never stop producing new specializations.
+const MAX_DEPTH: usize = 64;
+
+/// Specialize generic functions for all their uses.
+///
+/// Specializations are created on demand, starting from the functions
+/// without type parameters. Uses with the same type arguments share a
+/// specialization, and generic functions that are never used are dropped.
+pub(crate) fn translate(prog: Program) -> Result<Program, InternalError> {
+    let sym_table = prog.sym_table;
+    let (generic, mut functions): (Vec<Func>, Vec<Func>) = prog
+        .functions
+        .into_iter()
+        .partition(|f| !type_params(&sym_table, f.id).is_empty());
+    let mut mono = Mono {
+        sym_table,
+        generic: generic.into_iter().map(|f| (f.id, f)).collect(),
+        instances: HashMap::new(),
+        queue: vec![],
+    };
+    let no_subst = HashMap::new();
+    for func in &mut functions {
+        mono.visit(&mut func.body, &no_subst, 0)?;
+    }
+    while let Some(inst) = mono.queue.pop() {
+        // declarations without a body have nothing to specialize
+        let Some(generic) = mono.generic.get(&inst.generic) else {
+            continue;
+        };
+        let mut func = generic.clone();
+        let subst: HashMap<TVar, Type> = type_params(&mono.sym_table, inst.generic)
+            .iter()
+            .copied()
+            .zip(inst.type_args)
+            .collect();
+        func.id = inst.id;
+        func.name = mono.sym_table.find_sym_info(inst.id).name.clone();
+        for (_, _, tp) in &mut func.args {
+            *tp = tp.substitute(&subst);
+        }
+        func.ret_type = func.ret_type.substitute(&subst);
+        mono.visit(&mut func.body, &subst, inst.depth)?;
+        functions.push(func);
+    }
+    Ok(Program {
+        functions,
+        sym_table: mono.sym_table,
+    })
+}
+
+fn type_params(sym_table: &SymTable, id: NodeID) -> &[TVar] {
+    match &sym_table.find_sym_info(id).kind {
+        SymKind::Func { params, .. } => params,
+        _ => &[],
+    }
+}
+
+/// Specialization waiting for its body to be created.
+struct Instance {
+    id: NodeID,
+    generic: NodeID,
+    type_args: Vec<Type>,
+    /// Number of specializations it was reached through.
+    depth: usize,
+}
+
+struct Mono {
+    sym_table: SymTable,
+    generic: HashMap<NodeID, Func>,
+    /// Specializations of each generic function, by type arguments.
+    instances: HashMap<NodeID, Vec<(Vec<Type>, NodeID)>>,
+    queue: Vec<Instance>,
+}
+
+impl Mono {
+    /// Returns id of the specialization of `generic` for given type arguments,
+    /// registering it if it doesn't exist yet.
+    fn instance(
+        &mut self,
+        generic: NodeID,
+        type_args: Vec<Type>,
+        depth: usize,
+    ) -> Result<NodeID, InternalError> {
+        let instances = self.instances.entry(generic).or_default();
+        if let Some((_, id)) = instances.iter().find(|(args, _)| *args == type_args) {
+            return Ok(*id);
+        }
+        if depth == MAX_DEPTH {
+            let name = &self.sym_table.find_sym_info(generic).name;
+            return Err(InternalError::AnyMsg(format!(
+                "instantiations of generic function `{}` never stop nesting",
+                name
+            )));
+        }
+        let id = self.sym_table.add_specialization(generic, &type_args);
+        instances.push((type_args.clone(), id));
+        self.queue.push(Instance {
+            id,
+            generic,
+            type_args,
+            depth: depth + 1,
+        });
+        Ok(id)
+    }
+
+    /// Substitute type parameters in an expression of a function body,
+    /// and point uses of generic functions to their specializations.
+    fn visit(
+        &mut self,
+        e: &mut Expr,
+        subst: &HashMap<TVar, Type>,
+        depth: usize,
+    ) -> Result<(), InternalError> {
+        let sub = |tp: &mut Type| *tp = tp.substitute(subst);
+        match e {
+            Expr::NumLit(_, tp) | Expr::StringLit(_, tp) | Expr::LocalVar { tp, .. } => sub(tp),
+            Expr::GlobalVar { id, tp, type_args } => {
+                sub(tp);
+                if !type_args.is_empty() {
+                    let args = type_args.iter().map(|tp| tp.substitute(subst)).collect();
+                    *id = self.instance(*id, args, depth)?;
+                    type_args.clear();
+                }
+            }
+            Expr::Tuple(exprs, tp) | Expr::ArrayInitExact(exprs, tp) => {
+                sub(tp);
+                for e in exprs {
+                    self.visit(e, subst, depth)?;
+                }
+            }
+            Expr::FunCall {
+                expr,
+                args,
+                args_tp,
+                ret_tp,
+            } => {
+                self.visit(expr, subst, depth)?;
+                for e in args {
+                    self.visit(e, subst, depth)?;
+                }
+                args_tp.iter_mut().for_each(sub);
+                sub(ret_tp);
+            }
+            Expr::FieldAccess {
+                object,
+                struct_tp,
+                field_tp,
+                ..
+            } => {
+                self.visit(object, subst, depth)?;
+                sub(struct_tp);
+                sub(field_tp);
+            }
+            Expr::EnumTag { object, enum_tp } => {
+                self.visit(object, subst, depth)?;
+                sub(enum_tp);
+            }
+            Expr::EnumField {
+                object,
+                enum_tp,
+                field_tp,
+                ..
+            } => {
+                self.visit(object, subst, depth)?;
+                sub(enum_tp);
+                sub(field_tp);
+            }
+            Expr::Block {
+                exprs,
+                last_expr,
+                block_tp,
+            } => {
+                for e in exprs {
+                    self.visit(e, subst, depth)?;
+                }
+                self.visit(last_expr, subst, depth)?;
+                sub(block_tp);
+            }
+            Expr::Return { expr, ret_tp: tp }
+            | Expr::Let { expr, tp, .. }
+            | Expr::Ref { expr, tp }
+            | Expr::RefMut { expr, tp }
+            | Expr::Deref { expr, in_tp: tp }
+            | Expr::ArrayInitRepeat(expr, _, tp)
+            | Expr::ArrayToSlice { ptr: expr, tp, .. }
+            | Expr::Loop { body: expr, tp } => {
+                self.visit(expr, subst, depth)?;
+                sub(tp);
+            }
+            Expr::Cast { expr, from, to } => {
+                self.visit(expr, subst, depth)?;
+                sub(from);
+                sub(to);
+            }
+            Expr::If {
+                pred,
+                th,
+                el,
+                block_tp,
+            } => {
+                self.visit(pred, subst, depth)?;
+                self.visit(th, subst, depth)?;
+                self.visit(el, subst, depth)?;
+                sub(block_tp);
+            }
+            Expr::StructCons {
+                initializers, tp, ..
+            } => {
+                for (_, e) in initializers.values_mut() {
+                    self.visit(e, subst, depth)?;
+                }
+                sub(tp);
+            }
+            Expr::Assign {
+                lval,
+                rval,
+                assign_tp,
+            } => {
+                self.visit(lval, subst, depth)?;
+                self.visit(rval, subst, depth)?;
+                sub(assign_tp);
+            }
+            Expr::While { pred, block } => {
+                self.visit(pred, subst, depth)?;
+                self.visit(block, subst, depth)?;
+            }
+            Expr::Break(expr) => self.visit(expr, subst, depth)?,
+            Expr::IndexAccess {
+                arr,
+                index,
+                arr_tp,
+                tp,
+            } => {
+                self.visit(arr, subst, depth)?;
+                self.visit(index, subst, depth)?;
+                sub(arr_tp);
+                sub(tp);
+            }
+            Expr::Builtin(_, exprs, tps) => {
+                for e in exprs {
+                    self.visit(e, subst, depth)?;
+                }
+                tps.iter_mut().for_each(sub);
+            }
+            Expr::Error | Expr::Continue => {}
+        }
+        Ok(())
+    }
+}
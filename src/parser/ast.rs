@@ -47,6 +47,9 @@ pub enum ModuleItem {
     Func(Func),
     Struct(Struct),
     Enum(Enum),
+    TypeAlias(TypeAlias),
+    Const(Const),
+    Static(Static),
     Error,
 }
 
@@ -91,6 +94,9 @@ pub struct Import {
 ///
 /// @attributes
 /// (pub) fn builtin_fn(arg: type);
+///
+/// @extern
+/// (pub) fn variadic_fn(arg: type, ...);
 /// ```
 #[derive(Debug)]
 pub struct Func {
@@ -99,6 +105,8 @@ pub struct Func {
     pub name: Ident,
     pub type_params: Vec<Ident>,
     pub args: Vec<FnArg>,
+    /// Position of the trailing `...` of a variadic function.
+    pub variadic: Option<Position>,
     pub ret_type: Option<RTypeNode>,
     pub body: Option<ExprNode>,
     pub pos: Position,
@@ -141,6 +149,58 @@ pub struct Enum {
     pub pos: Position,
 }
 
+/// Another name for a type, which may be parameterized.
+///
+/// ```mst
+/// @attributes
+/// (pub) type Name<types> = type;
+/// ```
+#[derive(Debug)]
+pub struct TypeAlias {
+    pub attributes: Vec<RAttribute>,
+    pub visibility: Visibility,
+    pub name: Ident,
+    pub type_params: Vec<Ident>,
+    pub tp: RTypeNode,
+    pub pos: Position,
+}
+
+/// A named constant, its value is computed during compilation.
+///
+/// ```mst
+/// @attributes
+/// (pub) const NAME: type = expr;
+/// ```
+#[derive(Debug)]
+pub struct Const {
+    pub attributes: Vec<RAttribute>,
+    pub visibility: Visibility,
+    pub name: Ident,
+    pub tp: RTypeNode,
+    pub expr: ExprNode,
+    pub pos: Position,
+}
+
+/// A global variable, stored in the data of the program.
+///
+/// The initializer is evaluated during compilation, like that of a constant.
+/// Immutable statics are placed in read-only data.
+///
+/// ```mst
+/// @attributes
+/// (pub) static (mut) NAME: type = expr;
+/// ```
+#[derive(Debug)]
+pub struct Static {
+    pub attributes: Vec<RAttribute>,
+    pub visibility: Visibility,
+    pub is_mut: bool,
+    pub name: Ident,
+    pub tp: RTypeNode,
+    pub expr: ExprNode,
+    pub pos: Position,
+}
+
 // ==== Others =================================================================
 
 /// Convienience wrapper for import path.
@@ -255,12 +315,16 @@ pub enum ExprData {
     Error,
     /// Access of a variable.
     Var(Path),
-    /// Numeric literal.
-    Number(usize),
-    /// Character literal.
-    Char(u8),
+    /// Numeric literal, with optional type suffix.
+    Number(usize, Option<String>),
+    /// Character literal, a unicode scalar value.
+    Char(char),
+    /// Byte literal, like `b'a'`.
+    Byte(u8),
     /// String literal.
     String(String),
+    /// Byte string literal, like `b"\x7FELF"`.
+    ByteString(Vec<u8>),
     /// Tuple.
     Tuple(Vec<ExprNode>),
     /// Exact array initializer.
@@ -288,6 +352,10 @@ pub enum ExprData {
     MethodCall(Box<ExprNode>, Ident, Vec<ExprNode>),
     /// Field access to a struct type/variant.
     FieldAccess(Box<ExprNode>, Ident),
+    /// Access to a tuple field by its index.
+    ///
+    /// t.0
+    TupleField(Box<ExprNode>, usize),
     /// Block of semicolon-separated expressions.
     ClosedBlock(Vec<ExprNode>),
     /// Block of semicolon-separated expressions and a last expression
@@ -306,6 +374,17 @@ pub enum ExprData {
         tp: Option<RTypeNode>,
         expr: Box<ExprNode>,
     },
+    /// Bind variables of a pattern that may not match, running the else
+    /// block otherwise. The else block must not finish.
+    ///
+    /// ```mst
+    /// let Cons(x) = e else { return; };
+    /// ```
+    LetElse {
+        pattern: PatternNode,
+        expr: Box<ExprNode>,
+        els: Box<ExprNode>,
+    },
     /// Pattern matching on values.
     Match(Box<ExprNode>, Vec<MatchClause>),
     /// Get a pointer to value.
@@ -314,24 +393,69 @@ pub enum ExprData {
     RefMut(Box<ExprNode>),
     /// Dereference the pointer.
     Deref(Box<ExprNode>),
+    /// Negation of a signed integer.
+    Neg(Box<ExprNode>),
     /// If-then-else control statement.
     ///
     /// If else block is ommited, it defaults to `unit`.
     If(Box<ExprNode>, Box<ExprNode>, Option<Box<ExprNode>>),
     /// While control statement.
     While(Box<ExprNode>, Box<ExprNode>),
+    /// Infinite loop, left only with `break` or `return`.
+    Loop(Box<ExprNode>),
+    /// Leave the innermost loop.
+    ///
+    /// If no value is specified, it defaults to `unit`.
+    Break(Option<Box<ExprNode>>),
+    /// Skip to the next iteration of the innermost loop.
+    Continue,
     /// Struct type/variant constructor.
     StructCons(Path, Vec<(Ident, ExprNode)>),
     /// Assignment, (mut) LHS = RHS.
     Assign(Box<ExprNode>, Box<ExprNode>),
+    /// Compound assignment, (mut) LHS op= RHS.
+    ///
+    /// x += 1
+    OpAssign(BinOp, Box<ExprNode>, Box<ExprNode>),
     /// Type cast.
     ///
     /// x as u8
     Cast(Box<ExprNode>, RTypeNode),
+    /// Type ascription, the expression has to be of given type.
+    ///
+    /// (x : u8)
+    Ascription(Box<ExprNode>, RTypeNode),
     /// Builtin macro.
     ///
     /// @<name>(arg1, arg2, arg3)
     Builtin(Ident, Vec<ExprNode>),
+    /// Binary operator application.
+    ///
+    /// x < y
+    BinOp(BinOp, Box<ExprNode>, Box<ExprNode>),
+    /// Short-circuiting conjunction, RHS is evaluated only if LHS is true.
+    ///
+    /// x && y
+    And(Box<ExprNode>, Box<ExprNode>),
+    /// Short-circuiting disjunction, RHS is evaluated only if LHS is false.
+    ///
+    /// x || y
+    Or(Box<ExprNode>, Box<ExprNode>),
+}
+
+/// Binary operators.
+#[derive(Debug, Clone, Copy)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Gt,
+    Ge,
 }
 
 // ==== Pattern matching =======================================================
@@ -360,8 +484,8 @@ pub struct PatternNode {
 pub enum PatternData {
     /// `_` matches everything and discards the value.
     Wildcard,
-    /// Match numeric literal.
-    Number(usize),
+    /// Match numeric literal, possibly negative.
+    Number(i128),
     /// Match anything and bind it to a variable.
     Var(Ident),
     /// Match tuple.
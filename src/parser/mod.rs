@@ -1,7 +1,6 @@
 use std::{
     collections::BTreeMap,
     fs::read_to_string,
-    hint::unreachable_unchecked,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -17,8 +16,20 @@ lalrpop_util::lalrpop_mod!(pub parser, "/parser/parser.rs");
 
 /// Parses the entire `src` directory ignoring files without „mst” extension.
 pub fn parse_project(root: &Path, ctx: &mut Context) -> Result<ast::Program, InternalError> {
+    if !root.is_dir() {
+        return Err(InternalError::AnyMsg(format!(
+            "project directory `{}` does not exist",
+            root.display()
+        )));
+    }
     let mut path = PathBuf::from(root);
     path.push("src");
+    if !path.is_dir() {
+        return Err(InternalError::AnyMsg(format!(
+            "no `src` directory found in `{}`",
+            root.display()
+        )));
+    }
     let files = get_files(&mut path)?;
     let mut file_map = BTreeMap::new();
     for file in files {
@@ -72,7 +83,7 @@ fn get_module_path(path: &Path) -> Result<Vec<String>, InternalError> {
 fn get_files(arg: &mut PathBuf) -> Result<Vec<PathBuf>, InternalError> {
     let mut paths = vec![];
 
-    for file in arg.read_dir().expect("should be a directory") {
+    for file in arg.read_dir()? {
         if let Ok(file) = file {
             let ft = file
                 .file_type()
@@ -114,6 +125,10 @@ fn parse_file(ctx: &mut Context, filename: PathBuf) -> Result<Option<ast::Module
         .get_source(&filename)
         .expect("the source was added in previous line")
         .text();
+    let source = strip_comments(&source).unwrap_or_else(|(source, error)| {
+        errors.push(lalrpop_util::ParseError::User { error });
+        source
+    });
     let res = match parser::FileParser::new().parse(&mut errors, &pg, &source) {
         Ok(r) => Some(r),
         Err(e) => {
@@ -147,8 +162,13 @@ fn parse_file(ctx: &mut Context, filename: PathBuf) -> Result<Option<ast::Module
                 let token = token.1.to_string();
                 ParsingError::ExtraToken { pos, token }
             }
-            // There are no user-defined errors in the parser
-            lalrpop_util::ParseError::User { .. } => unsafe { unreachable_unchecked() },
+            lalrpop_util::ParseError::User { error } => {
+                let pos = pg.make(error.start, error.end);
+                ParsingError::BadLiteral {
+                    pos,
+                    msg: error.msg,
+                }
+            }
         })
         .collect();
 
@@ -159,14 +179,103 @@ fn parse_file(ctx: &mut Context, filename: PathBuf) -> Result<Option<ast::Module
     Ok(res)
 }
 
-/// TODO: enable reporting string errors through the parser.
-/// Now it will panic if this function doesn't succeed
+/// Replace `//` and `/* */` comments with spaces, so the parser skips them.
+///
+/// Block comments can be nested, which the lexer can't express. Line breaks
+/// and byte offsets are kept, so positions in the result match the source.
+/// If a block comment isn't closed, the rest of the file is blanked and
+/// returned along with the error.
+fn strip_comments(source: &str) -> Result<String, (String, LiteralError)> {
+    let bytes = source.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut unclosed = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match &bytes[i..] {
+            [b'"' | b'\'', ..] => i = skip_literal(bytes, i),
+            // `#` comments are skipped by the lexer
+            [b'#', ..] => i = skip_line(bytes, i),
+            [b'/', b'/', ..] => {
+                let end = skip_line(bytes, i);
+                out[i..end].fill(b' ');
+                i = end;
+            }
+            [b'/', b'*', ..] => {
+                let start = i;
+                let mut depth = 0;
+                while i < bytes.len() {
+                    match &bytes[i..] {
+                        [b'/', b'*', ..] => depth += 1,
+                        [b'*', b'/', ..] => depth -= 1,
+                        _ => {
+                            if bytes[i] != b'\n' {
+                                out[i] = b' ';
+                            }
+                            i += 1;
+                            continue;
+                        }
+                    }
+                    out[i..i + 2].fill(b' ');
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                if depth != 0 {
+                    unclosed = Some(start);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    // only whole characters are replaced, with ASCII spaces
+    let out = String::from_utf8(out).expect("result should be valid UTF-8");
+    match unclosed {
+        None => Ok(out),
+        Some(start) => {
+            let msg = "unterminated block comment".to_string();
+            let end = start + 2;
+            Err((out, LiteralError { start, end, msg }))
+        }
+    }
+}
+
+/// Returns the offset of the end of the line starting at `i`.
+fn skip_line(bytes: &[u8], i: usize) -> usize {
+    bytes[i..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or(bytes.len(), |len| i + len)
+}
+
+/// Returns the offset just past the string or char literal starting at `i`,
+/// or the end of the line if it isn't closed.
+fn skip_literal(bytes: &[u8], mut i: usize) -> usize {
+    let quote = bytes[i];
+    i += 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' => return i,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Malformed literal or comment, reported by the parser.
+pub struct LiteralError {
+    start: usize,
+    end: usize,
+    msg: String,
+}
+
+/// Replace escape sequences in a string literal with characters they denote.
 pub fn unescape_json_string(s: &str) -> Result<String, String> {
     // Strip surrounding quotes
     let raw = &s[1..s.len() - 1];
 
-    println!("{:#?}", raw);
-
     let mut result = String::new();
     let mut chars = raw.chars();
     while let Some(c) = chars.next() {
@@ -208,45 +317,132 @@ pub fn unescape_json_string(s: &str) -> Result<String, String> {
     Ok(result)
 }
 
-pub fn parse_char_literal(s: &str) -> Result<u8, String> {
-    // Expect format: `'x'` or `'\xNN'` or `'\n'`
-    if !s.starts_with('\'') || !s.ends_with('\'') {
-        return Err("invalid char literal".into());
+/// Integer types that can be used as suffixes of literals.
+const INT_SUFFIXES: [&str; 10] = [
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize",
+];
+
+/// Value of an integer literal, decimal or with `0x`, `0o` or `0b` prefix,
+/// and its type suffix, like in `255u8`.
+///
+/// Digits may be separated with underscores, like in `1_000_000`.
+pub fn parse_number(s: &str) -> Result<(usize, Option<String>), String> {
+    // suffixes start with letters that are never digits
+    let suffix = INT_SUFFIXES
+        .into_iter()
+        .find(|suffix| s.len() > suffix.len() && s.ends_with(suffix));
+    let s = &s[..s.len() - suffix.map_or(0, str::len)];
+    let (digits, radix, kind) = match s.get(..2) {
+        Some("0x") => (&s[2..], 16, "hexadecimal"),
+        Some("0o") => (&s[2..], 8, "octal"),
+        Some("0b") => (&s[2..], 2, "binary"),
+        _ => (s, 10, "decimal"),
+    };
+    let mut value: Option<usize> = None;
+    for c in digits.chars().filter(|c| *c != '_') {
+        let Some(digit) = c.to_digit(radix) else {
+            return Err(format!("invalid digit `{}` in {} literal", c, kind));
+        };
+        let n = value.unwrap_or(0);
+        value = Some(
+            n.checked_mul(radix as usize)
+                .and_then(|n| n.checked_add(digit as usize))
+                .ok_or("integer literal is too large")?,
+        );
     }
+    match value {
+        Some(value) => Ok((value, suffix.map(String::from))),
+        None => Err(format!("{} literal has no digits", kind)),
+    }
+}
 
+/// Value of a char literal, like `'a'`, `'\n'` or `'\u{1F600}'`.
+pub fn parse_char_literal(s: &str) -> Result<char, String> {
     let inner = &s[1..s.len() - 1];
-    let bytes = inner.as_bytes();
-
-    // Case 1: normal one-character literal: `'a'`
-    if bytes.len() == 1 {
-        return Ok(bytes[0]);
-    }
-
-    // Case 2: escaped literal: starts with '\'
-    if bytes.len() >= 2 && bytes[0] == b'\\' {
-        match bytes[1] {
-            b'a' => return Ok(0x07),
-            b'b' => return Ok(0x08),
-            b'f' => return Ok(0x0C),
-            b'n' => return Ok(0x0A),
-            b'r' => return Ok(0x0D),
-            b't' => return Ok(0x09),
-            b'v' => return Ok(0x0B),
-            b'\\' => return Ok(b'\\'),
-            b'\'' => return Ok(b'\''),
-            b'"' => return Ok(b'"'),
-            b'?' => return Ok(b'?'),
-            b'x' => {
-                // \xNN (1–2 hex digits)
-                let hex = &inner[2..];
-                if hex.is_empty() || hex.len() > 2 {
-                    return Err("invalid hex escape".into());
+    let Some(escape) = inner.strip_prefix('\\') else {
+        return Ok(inner.chars().next().expect("literal should not be empty"));
+    };
+    if let Some(hex) = escape.strip_prefix('x') {
+        let code = u8::from_str_radix(hex, 16).map_err(|_| "invalid hex escape")?;
+        if !code.is_ascii() {
+            return Err("hex escape in a char literal must be at most \\x7F".into());
+        }
+        return Ok(code as char);
+    }
+    if let Some(hex) = escape.strip_prefix("u{") {
+        let code =
+            u32::from_str_radix(&hex[..hex.len() - 1], 16).map_err(|_| "invalid unicode escape")?;
+        return char::from_u32(code)
+            .ok_or_else(|| format!("\\u{{{:X}}} is not a valid char", code));
+    }
+    Ok(
+        unescape_simple(escape.as_bytes()[0]).expect("the lexer accepts only known escapes")
+            as char,
+    )
+}
+
+/// Value of a byte literal, like `b'a'` or `b'\xFF'`.
+pub fn parse_byte_literal(s: &str) -> Result<u8, String> {
+    let inner = &s[2..s.len() - 1];
+    let Some(escape) = inner.strip_prefix('\\') else {
+        return match inner.as_bytes() {
+            [b] if b.is_ascii() => Ok(*b),
+            _ => Err("byte literal must be an ASCII character".into()),
+        };
+    };
+    if let Some(hex) = escape.strip_prefix('x') {
+        return u8::from_str_radix(hex, 16).map_err(|_| "invalid hex escape".into());
+    }
+    Ok(unescape_simple(escape.as_bytes()[0]).expect("the lexer accepts only known escapes"))
+}
+
+/// Bytes of a byte string literal, like `b"GET\r\n"` or `b"\xFF\x00"`.
+///
+/// Only ASCII characters can be used, other bytes have to be escaped.
+pub fn parse_byte_string(s: &str) -> Result<Vec<u8>, String> {
+    let raw = &s.as_bytes()[2..s.len() - 1];
+    let mut bytes = vec![];
+    let mut iter = raw.iter().copied();
+    while let Some(b) = iter.next() {
+        match b {
+            b'\\' => {
+                let escape = iter
+                    .next()
+                    .expect("the lexer accepts only complete escapes");
+                if escape == b'x' {
+                    let hex: Vec<u8> = iter.by_ref().take(2).collect();
+                    let value = std::str::from_utf8(&hex)
+                        .ok()
+                        .filter(|hex| hex.len() == 2)
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                        .ok_or("hex escape needs two hex digits, like `\\x0A`")?;
+                    bytes.push(value);
+                } else {
+                    let value = unescape_simple(escape)
+                        .ok_or_else(|| format!("invalid escape: \\{}", escape.escape_ascii()))?;
+                    bytes.push(value);
                 }
-                return u8::from_str_radix(hex, 16).map_err(|_| "invalid hex digits".to_string());
             }
-            _ => return Err("unknown escape".into()),
+            b if b.is_ascii() => bytes.push(b),
+            _ => return Err("byte string must contain only ASCII characters".into()),
         }
     }
+    Ok(bytes)
+}
 
-    Err("invalid char literal format".into())
+/// Byte denoted by a single character escape, without the backslash.
+fn unescape_simple(escape: u8) -> Option<u8> {
+    let b = match escape {
+        b'a' => 0x07,
+        b'b' => 0x08,
+        b'f' => 0x0C,
+        b'n' => 0x0A,
+        b'r' => 0x0D,
+        b't' => 0x09,
+        b'v' => 0x0B,
+        b'0' => 0x00,
+        b'\\' | b'\'' | b'"' | b'?' => escape,
+        _ => return None,
+    };
+    Some(b)
 }
@@ -9,6 +9,8 @@ use crate::{
 #[derive(Debug)]
 pub struct Program {
     pub functions: Vec<Func>,
+    /// Constants and statics, their values are computed during compilation.
+    pub consts: Vec<Const>,
     pub sym_table: SymTable,
 }
 
@@ -19,10 +21,21 @@ pub struct Func {
     pub params: Vec<(String, TVar)>,
     pub name: String,
     pub ret_type: Type,
+    /// Position of the return type annotation, if there is one.
+    pub ret_type_pos: Option<Position>,
     pub body: ExprNode,
     pub pos: Position,
 }
 
+#[derive(Debug)]
+pub struct Const {
+    pub id: NodeID,
+    pub name: String,
+    pub tp: Type,
+    pub expr: ExprNode,
+    pub pos: Position,
+}
+
 #[derive(Debug)]
 pub struct FnArg {
     pub is_mut: bool,
@@ -33,72 +46,84 @@ pub struct FnArg {
 
 // ==== Expr ===================================================================
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExprNode {
     pub data: ExprData,
     pub pos: Position,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SymRef {
     Local(String),
     Global(NodeID),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ExprData {
     Var(SymRef),
-    NumLit(usize),
+    /// Number literal, with optional type suffix.
+    NumLit(usize, Option<String>),
+    BoolLit(bool),
     String(String),
+    ByteString(Vec<u8>),
     Tuple(Vec<ExprNode>),
     FunCall(Box<ExprNode>, Vec<ExprNode>),
     MethodCall(Box<ExprNode>, String, Vec<ExprNode>),
     FieldAccess(Box<ExprNode>, String),
+    TupleField(Box<ExprNode>, usize),
     Block(Vec<ExprNode>, Box<ExprNode>),
     Return(Box<ExprNode>),
     Let {
         name: String,
         is_mut: bool,
-        tp: Option<Type>,
+        /// Type annotation and its position.
+        tp: Option<(Type, Position)>,
         expr: Box<ExprNode>,
     },
     Ref(Box<ExprNode>),
     RefMut(Box<ExprNode>),
     Deref(Box<ExprNode>),
-    If(Box<ExprNode>, Box<ExprNode>, Box<ExprNode>),
+    Neg(Box<ExprNode>),
+    /// Condition, then branch and else branch, if there is one.
+    If(Box<ExprNode>, Box<ExprNode>, Option<Box<ExprNode>>),
     StructCons(NodeID, HashMap<String, ExprNode>),
     Assign(Box<ExprNode>, Box<ExprNode>),
     Error,
     IndexAccess(Box<ExprNode>, Box<ExprNode>),
     Match(Box<ExprNode>, Vec<MatchClause>),
     While(Box<ExprNode>, Box<ExprNode>),
+    Loop(Box<ExprNode>),
+    Break(Box<ExprNode>),
+    Continue,
     Cast(Box<ExprNode>, Type),
+    Ascription(Box<ExprNode>, Type),
     ArrayInitExact(Vec<ExprNode>),
     ArrayInitRepeat(Box<ExprNode>, usize),
-    Char(u8),
+    Char(char),
+    Byte(u8),
     Builtin(String, Vec<ExprNode>),
 }
 
 // ==== Pattern matching =======================================================
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MatchClause {
     pub pattern: PatternNode,
     pub expr: ExprNode,
     pub pos: Position,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PatternNode {
     pub data: PatternData,
     pub pos: Position,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PatternData {
     Error,
     Wildcard,
-    Number(usize),
+    Number(i128),
     Var(String),
     Tuple(Vec<PatternNode>),
     TupleCons(NodeID, Vec<PatternNode>),
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeSet, HashMap, HashSet},
     hash::Hash,
 };
 
@@ -13,7 +13,7 @@ use crate::{
     parser::ast::{RTypeData, RTypeNode},
     resolve::{ast::SymRef, error},
     symtable::{SymInfo, SymTable, TypeInfo},
-    tp::{BUILTIN_TYPES, TVar, TVarKind, Type},
+    tp::{self, BUILTIN_TYPES, TVar, TVarKind, Type},
 };
 
 pub struct Env {
@@ -23,6 +23,13 @@ pub struct Env {
     node_map: HashMap<NodeID, SymInfo>,
     tvar_map: HashMap<TVar, TypeInfo>,
     local_scopes: Vec<HashMap<String, LocalBinding>>,
+    aliases: HashMap<NodeID, Alias>,
+}
+
+/// Resolved type alias, expanded wherever it's used.
+struct Alias {
+    params: Vec<TVar>,
+    tp: Type,
 }
 
 enum LocalBinding {
@@ -63,6 +70,9 @@ impl Env {
                             LocalBinding::TypeVar(tvar) => *tvar,
                         }
                     }
+                    SymRef::Global(id) if self.aliases.contains_key(&id) => {
+                        return Ok(self.expand_alias(ctx, id, vec![], &tp.pos));
+                    }
                     SymRef::Global(id) => self.get_tvar(id)?,
                 };
                 let name = path.to_string();
@@ -95,14 +105,8 @@ impl Env {
                 let tp = self.resolve_type(ctx, *tp)?;
                 Type::array(size, tp)
             }
-            RTypeData::Slice(tp) => {
-                let tp = self.resolve_type(ctx, *tp)?;
-                Type::ptr(tp)
-            }
-            RTypeData::MutSlice(tp) => {
-                let tp = self.resolve_type(ctx, *tp)?;
-                Type::mut_ptr(tp)
-            }
+            RTypeData::Slice(tp) => Type::slice(self.resolve_type(ctx, *tp)?),
+            RTypeData::MutSlice(tp) => Type::mut_slice(self.resolve_type(ctx, *tp)?),
             RTypeData::TypeApp(path, tps) => {
                 let tps = tps
                     .into_iter()
@@ -129,6 +133,9 @@ impl Env {
                             LocalBinding::TypeVar(tvar) => *tvar,
                         }
                     }
+                    SymRef::Global(id) if self.aliases.contains_key(&id) => {
+                        return Ok(self.expand_alias(ctx, id, tps, &tp.pos));
+                    }
                     SymRef::Global(id) => self.get_tvar(id)?,
                 };
                 let name = path.to_string();
@@ -143,6 +150,59 @@ impl Env {
         })
     }
 
+    /// Substitute type arguments for the parameters of a type alias.
+    fn expand_alias(&self, ctx: &mut Context, id: NodeID, args: Vec<Type>, pos: &Position) -> Type {
+        let alias = &self.aliases[&id];
+        if alias.params.len() != args.len() {
+            ctx.report(tp::error::type_params_mismatch(
+                pos,
+                alias.params.len(),
+                args.len(),
+            ));
+            return Type::unknown();
+        }
+        let subst = alias.params.iter().copied().zip(args).collect();
+        alias.tp.substitute(&subst)
+    }
+
+    /// Register a type alias, its type may be updated once it's resolved.
+    pub(crate) fn add_alias(&mut self, id: NodeID, params: Vec<TVar>, tp: Type) {
+        self.aliases.insert(id, Alias { params, tp });
+    }
+
+    pub(crate) fn is_alias(&self, id: NodeID) -> bool {
+        self.aliases.contains_key(&id)
+    }
+
+    /// Collect type aliases the type refers to.
+    pub(crate) fn alias_dependencies(&self, tp: &RTypeNode, deps: &mut HashSet<NodeID>) {
+        match &tp.data {
+            RTypeData::Var(path) | RTypeData::TypeApp(path, _) => {
+                if let Ok(SymRef::Global(id)) = self.find_symbol(path.clone())
+                    && self.is_alias(id)
+                {
+                    deps.insert(id);
+                }
+            }
+            _ => (),
+        }
+        match &tp.data {
+            RTypeData::Var(_) => (),
+            RTypeData::TypeApp(_, tps) | RTypeData::Tuple(tps) => {
+                tps.iter().for_each(|tp| self.alias_dependencies(tp, deps))
+            }
+            RTypeData::Fun(args, ret) => {
+                args.iter().for_each(|tp| self.alias_dependencies(tp, deps));
+                self.alias_dependencies(ret, deps)
+            }
+            RTypeData::Ptr(tp)
+            | RTypeData::MutPtr(tp)
+            | RTypeData::Slice(tp)
+            | RTypeData::MutSlice(tp)
+            | RTypeData::Array(_, tp) => self.alias_dependencies(tp, deps),
+        }
+    }
+
     pub(crate) fn leave_scope(&mut self) {
         match self.local_scopes.pop() {
             Some(_) => (),
@@ -168,9 +228,17 @@ impl Env {
                 }
             }
         };
-        let binding = self
-            .scope_info
-            .find_path(self.current_module, path.clone(), &mut true)?;
+        let locals: Vec<&str> = self
+            .local_scopes
+            .iter()
+            .flat_map(|scope| scope.keys().map(String::as_str))
+            .collect();
+        let binding = self.scope_info.find_path_near(
+            self.current_module,
+            path.clone(),
+            &mut true,
+            &locals,
+        )?;
         let id = match binding.sym {
             Symbol::Local(node_id) | Symbol::Imported(node_id) | Symbol::GlobImported(node_id) => {
                 node_id
@@ -209,6 +277,7 @@ impl Env {
             local_scopes: vec![],
             node_map: HashMap::new(),
             tvar_map,
+            aliases: HashMap::new(),
         }
     }
 
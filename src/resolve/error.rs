@@ -1,3 +1,5 @@
+use colored::Color;
+
 use crate::{
     common::Position,
     error::diagnostic::{Diagnostic, Label},
@@ -26,12 +28,74 @@ pub(crate) fn field_duplicate(pos: &Position, name: String) -> Diagnostic {
     })))
 }
 
+pub(crate) fn duplicate_field(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("field `{}` is already defined", name)
+    })))
+}
+
 pub(crate) fn expected_type_got_var(pos: &Position) -> Diagnostic {
     Diagnostic::error(pos)
         .with_label(Label::new(pos).with_msg(Box::new(|| format!("expected type, found variable"))))
 }
 
+pub(crate) fn let_else_outside_block(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(|| {
+        "`let ... else` can only be used as a statement of a block".into()
+    })))
+}
+
 pub(crate) fn local_type(pos: &Position) -> Diagnostic {
     Diagnostic::error(&pos)
         .with_label(Label::new(&pos).with_msg(Box::new(|| format!("this is a local type"))))
 }
+
+pub(crate) fn cyclic_type_alias(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("type alias `{}` refers to itself", name)
+    })))
+}
+
+pub(crate) fn alias_as_value(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos).with_label(
+        Label::new(pos).with_msg(Box::new(|| format!("expected value, found type alias"))),
+    )
+}
+
+pub(crate) fn unknown_attribute(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::warning(pos).with_label(
+        Label::new(pos)
+            .with_msg(Box::new(move || {
+                format!("unknown attribute `@{}` is ignored", name)
+            }))
+            .with_color(Color::Yellow),
+    )
+}
+
+pub(crate) fn attribute_arity(
+    pos: &Position,
+    name: String,
+    expected: usize,
+    found: usize,
+) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        let plural = if expected == 1 { "" } else { "s" };
+        format!("attribute `@{name}` takes {expected} argument{plural}, found {found}")
+    })))
+}
+
+pub(crate) fn link_name_without_extern(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(|| {
+            "`@link_name` can only be used together with `@extern`".into()
+        })))
+        .with_note("symbols that aren't `@extern` are private to the object file".into())
+}
+
+pub(crate) fn variadic_not_extern(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(|| {
+            "only `@extern` functions without a body can be variadic".into()
+        })))
+        .with_note("variadic functions have to be defined in C".into())
+}
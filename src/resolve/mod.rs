@@ -5,11 +5,12 @@ mod error;
 use std::collections::{HashMap, HashSet};
 use std::num::NonZeroUsize;
 
-use crate::common::{Ident, NodeID};
+use crate::common::{Ident, NodeID, Position, RAttribute};
 use crate::error::InternalError;
 use crate::error::context::Context;
 use crate::error::diagnostic::{Diagnostic, Label};
 use crate::resolve::env::Env;
+use crate::symtable::type_sort::topo_sort;
 use crate::symtable::{SymInfo, SymKind, TypeInfo, TypeKind};
 use crate::tp::{TVar, TVarKind, Type};
 
@@ -17,13 +18,19 @@ use crate::mod_tree::ast as in_a;
 use ast as out_a;
 
 pub fn translate(ctx: &mut Context, prog: in_a::Program) -> Result<out_a::Program, InternalError> {
+    let mut ast = prog.ast;
     let mut tvar_map: HashMap<NodeID, TVar> = HashMap::new();
-    generate_tvars(&mut tvar_map, &prog.ast);
+    generate_tvars(&mut tvar_map, &ast);
+    let mut aliases = vec![];
+    take_aliases(&mut ast, &mut aliases);
     let mut env = Env::init(prog.scope_info, tvar_map);
-    let functions = tr_module(ctx, &mut env, prog.ast)?;
+    tr_aliases(ctx, &mut env, aliases)?;
+    let mut consts = vec![];
+    let functions = tr_module(ctx, &mut env, &mut consts, ast)?;
     let sym_table = env.finish(ctx);
     let prog = out_a::Program {
         functions,
+        consts,
         sym_table,
     };
     Ok(prog)
@@ -33,7 +40,10 @@ fn generate_tvars(tvar_map: &mut HashMap<NodeID, TVar>, ast: &in_a::Module) {
     for item in &ast.items {
         match item {
             in_a::ModuleItem::Module(module) => generate_tvars(tvar_map, module),
-            in_a::ModuleItem::Func(_) => continue,
+            in_a::ModuleItem::Func(_)
+            | in_a::ModuleItem::TypeAlias(_)
+            | in_a::ModuleItem::Const(_)
+            | in_a::ModuleItem::Static(_) => continue,
             in_a::ModuleItem::Struct(s) => {
                 let tvar = get_tvar_maybe_builtin(s.type_params.len(), &s.attributes);
                 tvar_map.insert(s.id, tvar);
@@ -46,13 +56,110 @@ fn generate_tvars(tvar_map: &mut HashMap<NodeID, TVar>, ast: &in_a::Module) {
     }
 }
 
-fn get_tvar_maybe_builtin(params: usize, attributes: &Vec<crate::common::RAttribute>) -> TVar {
+/// Move type aliases out of the module tree, with ids of their modules.
+fn take_aliases(module: &mut in_a::Module, aliases: &mut Vec<(NodeID, in_a::TypeAlias)>) {
+    for item in std::mem::take(&mut module.items) {
+        match item {
+            in_a::ModuleItem::TypeAlias(alias) => aliases.push((module.id, alias)),
+            in_a::ModuleItem::Module(mut submodule) => {
+                take_aliases(&mut submodule, aliases);
+                module.items.push(in_a::ModuleItem::Module(submodule));
+            }
+            item => module.items.push(item),
+        }
+    }
+}
+
+/// Resolve type aliases, each after the aliases it refers to,
+/// so that they're fully expanded when used.
+fn tr_aliases(
+    ctx: &mut Context,
+    env: &mut Env,
+    aliases: Vec<(NodeID, in_a::TypeAlias)>,
+) -> Result<(), InternalError> {
+    for (_, alias) in &aliases {
+        env.add_alias(alias.id, vec![], Type::unknown());
+    }
+    let mut deps = HashMap::new();
+    for (module, alias) in &aliases {
+        env.current_module = *module;
+        env.new_scope();
+        for param in &alias.type_params {
+            env.add_local_type_var(param.data.clone(), TVar::new(TVarKind::Parameter));
+        }
+        let mut alias_deps = HashSet::new();
+        env.alias_dependencies(&alias.tp, &mut alias_deps);
+        env.leave_scope();
+        deps.insert(alias.id, alias_deps);
+    }
+    let (order, cyclic) = topo_sort(deps);
+    let mut aliases: HashMap<NodeID, (NodeID, in_a::TypeAlias)> = aliases
+        .into_iter()
+        .map(|(module, alias)| (alias.id, (module, alias)))
+        .collect();
+    for id in order {
+        let (module, alias) = aliases.remove(&id).unwrap();
+        env.current_module = module;
+        env.new_scope();
+        let mut params = vec![];
+        for param in alias.type_params {
+            let tv = TVar::new(TVarKind::Parameter);
+            env.add_local_type_var(param.data, tv);
+            params.push(tv);
+        }
+        let tp = env.resolve_type(ctx, alias.tp)?;
+        env.leave_scope();
+        env.add_alias(id, params, tp);
+    }
+    let mut cyclic: Vec<_> = cyclic.into_iter().map(|id| &aliases[&id].1).collect();
+    cyclic.sort_by_key(|alias| alias.id);
+    for alias in cyclic {
+        ctx.report(error::cyclic_type_alias(
+            &alias.name.pos,
+            alias.name.name_str(),
+        ));
+    }
+    Ok(())
+}
+
+/// Attributes the compiler understands, with the number of their arguments.
+const ATTRIBUTES: &[(&str, usize)] = &[
+    ("builtin", 1),
+    ("extern", 0),
+    ("link_name", 1),
+    ("must_use", 0),
+    ("no_mangle", 0),
+];
+
+/// Report attributes that are unknown, have a wrong number of arguments
+/// or don't make sense together.
+fn check_attributes(ctx: &mut Context, attributes: &[RAttribute]) {
+    for attr in attributes {
+        let name = &attr.name.data;
+        match ATTRIBUTES.iter().find(|(known, _)| known == name) {
+            None => ctx.report(error::unknown_attribute(&attr.pos, name.clone())),
+            Some((_, arity)) if *arity != attr.args.len() => ctx.report(error::attribute_arity(
+                &attr.pos,
+                name.clone(),
+                *arity,
+                attr.args.len(),
+            )),
+            Some(_) => (),
+        }
+    }
+    let find = |name: &str| attributes.iter().find(|attr| attr.name.data == name);
+    if let (Some(link_name), None) = (find("link_name"), find("extern")) {
+        ctx.report(error::link_name_without_extern(&link_name.pos));
+    }
+}
+
+fn get_tvar_maybe_builtin(params: usize, attributes: &Vec<RAttribute>) -> TVar {
     let mut builtin_name = None;
 
     for attribute in attributes {
-        match attribute.name.data.as_str() {
-            "builtin" => {
-                builtin_name = Some(attribute.args[0].clone());
+        match (attribute.name.data.as_str(), attribute.args.first()) {
+            ("builtin", Some(name)) => {
+                builtin_name = Some(name.clone());
             }
             _ => (),
         }
@@ -76,6 +183,7 @@ fn get_tvar_maybe_builtin(params: usize, attributes: &Vec<crate::common::RAttrib
 fn tr_module(
     ctx: &mut Context,
     env: &mut Env,
+    consts: &mut Vec<out_a::Const>,
     ast: in_a::Module,
 ) -> Result<Vec<out_a::Func>, InternalError> {
     let mut functions = vec![];
@@ -83,7 +191,7 @@ fn tr_module(
         env.current_module = ast.id;
         match item {
             in_a::ModuleItem::Module(module) => {
-                let mut mod_functions = tr_module(ctx, env, module)?;
+                let mut mod_functions = tr_module(ctx, env, consts, module)?;
                 functions.append(&mut mod_functions);
             }
             in_a::ModuleItem::Func(func) => {
@@ -94,6 +202,9 @@ fn tr_module(
             }
             in_a::ModuleItem::Struct(s) => tr_struct(ctx, env, &mut functions, s)?,
             in_a::ModuleItem::Enum(e) => tr_enum(ctx, env, &mut functions, e)?,
+            in_a::ModuleItem::TypeAlias(_) => unreachable!("aliases are resolved first"),
+            in_a::ModuleItem::Const(c) => consts.push(tr_const(ctx, env, c)?),
+            in_a::ModuleItem::Static(s) => consts.push(tr_static(ctx, env, s)?),
         }
     }
     Ok(functions)
@@ -107,14 +218,14 @@ fn tr_enum(
 ) -> Result<(), InternalError> {
     let tvar = env.get_tvar(e.id)?;
     let mut constructors = HashMap::new();
-    let mut params = HashMap::new();
+    let mut params = vec![];
     env.new_scope();
     for param in e.type_params {
         let tv = TVar::new(TVarKind::Parameter);
         let name = param.data;
-        env.add_local_type_var(name.clone(), tv);
+        env.add_local_type_var(name, tv);
         // todo: check if duplicate
-        params.insert(name, tv);
+        params.push(tv);
     }
     let mut cons_id = 0;
     for cons in e.constructors {
@@ -134,6 +245,7 @@ fn tr_enum(
                 if let Some(_) = constructors.insert(name.clone(), id) {
                     ctx.report(error::already_bound(&pos, name.clone()));
                 };
+                check_attributes(ctx, &attributes);
                 let sym_info = SymInfo::build(
                     name.clone(),
                     pos,
@@ -158,11 +270,12 @@ fn tr_enum(
         cons_id += 1;
     }
 
+    check_attributes(ctx, &e.attributes);
     let sym_info = SymInfo::build(e.name.data.clone(), e.pos.clone(), SymKind::Enum(tvar))
         .with_attributes(e.attributes);
 
     let kind = TypeKind::Enum {
-        params: params.values().map(|tv| *tv).collect(),
+        params,
         constructors,
     };
 
@@ -186,35 +299,34 @@ fn tr_struct(
 ) -> Result<(), InternalError> {
     let tvar = env.get_tvar(s.id)?;
     let mut fields = HashMap::new();
-    let mut params = HashMap::new();
+    let mut params = vec![];
     env.new_scope();
     for param in s.type_params {
         let tv = TVar::new(TVarKind::Parameter);
         let name = param.data;
-        env.add_local_type_var(name.clone(), tv);
+        env.add_local_type_var(name, tv);
         // todo: check if duplicate
-        params.insert(name, tv);
+        params.push(tv);
     }
 
     let mut field_id = 0;
 
     for (name, tp) in s.fields {
         let tp = env.resolve_type(ctx, tp)?;
-        let name = name.data;
-        match fields.insert(name, (field_id, tp)) {
-            Some(_) => panic!("field already defined"),
-            None => (),
+        if fields.contains_key(&name.data) {
+            // keep the first definition, so that field ids stay consecutive
+            ctx.report(error::duplicate_field(&name.pos, name.data));
+            continue;
         }
+        fields.insert(name.data, (field_id, tp));
         field_id += 1;
     }
 
+    check_attributes(ctx, &s.attributes);
     let sym_info = SymInfo::build(s.name.data.clone(), s.pos.clone(), SymKind::Struct(tvar))
         .with_attributes(s.attributes);
 
-    let kind = TypeKind::Struct {
-        params: params.values().map(|tv| *tv).collect(),
-        fields,
-    };
+    let kind = TypeKind::Struct { params, fields };
 
     env.add_sym_info(s.id, sym_info);
 
@@ -229,6 +341,59 @@ fn tr_struct(
     Ok(())
 }
 
+fn tr_const(
+    ctx: &mut Context,
+    env: &mut Env,
+    c: in_a::Const,
+) -> Result<out_a::Const, InternalError> {
+    let tp = env.resolve_type(ctx, c.tp)?;
+    let kind = SymKind::Const {
+        tp: tp.clone(),
+        value: None,
+    };
+    check_attributes(ctx, &c.attributes);
+    let sym_info =
+        SymInfo::build(c.name.name_str(), c.pos.clone(), kind).with_attributes(c.attributes);
+    env.add_sym_info(c.id, sym_info);
+    env.new_scope();
+    let expr = tr_expr(ctx, env, c.expr)?;
+    env.leave_scope();
+    Ok(out_a::Const {
+        id: c.id,
+        name: c.name.name_str(),
+        tp,
+        expr,
+        pos: c.pos,
+    })
+}
+
+fn tr_static(
+    ctx: &mut Context,
+    env: &mut Env,
+    s: in_a::Static,
+) -> Result<out_a::Const, InternalError> {
+    let tp = env.resolve_type(ctx, s.tp)?;
+    let kind = SymKind::Static {
+        tp: tp.clone(),
+        is_mut: s.is_mut,
+        value: None,
+    };
+    check_attributes(ctx, &s.attributes);
+    let sym_info =
+        SymInfo::build(s.name.name_str(), s.pos.clone(), kind).with_attributes(s.attributes);
+    env.add_sym_info(s.id, sym_info);
+    env.new_scope();
+    let expr = tr_expr(ctx, env, s.expr)?;
+    env.leave_scope();
+    Ok(out_a::Const {
+        id: s.id,
+        name: s.name.name_str(),
+        tp,
+        expr,
+        pos: s.pos,
+    })
+}
+
 fn tr_func(
     ctx: &mut Context,
     env: &mut Env,
@@ -236,15 +401,16 @@ fn tr_func(
     parent: Option<(TVar, String)>,
 ) -> Result<Option<ast::Func>, InternalError> {
     env.new_scope();
-    let mut params = HashSet::new();
+    let mut params = vec![];
     let mut named_params = vec![];
     for param in func.type_params {
         let tv = TVar::new(TVarKind::Parameter);
         let name = param.data;
         env.add_local_type_var(name.clone(), tv);
-        params.insert(tv);
+        params.push(tv);
         named_params.push((name.clone(), tv));
     }
+    let ret_type_pos = func.ret_type.as_ref().map(|tp| tp.pos.clone());
     let ret_type = match func.ret_type {
         Some(tp) => env.resolve_type(ctx, tp)?,
         None => Type::unit(),
@@ -353,16 +519,26 @@ fn tr_func(
         ret: ret_type.clone(),
     };
 
-    let sym_info = SymInfo::build(func.name.name_str(), func.pos.clone(), sym_kind)
+    check_attributes(ctx, &func.attributes);
+    let mut sym_info = SymInfo::build(func.name.name_str(), func.pos.clone(), sym_kind)
         .with_attributes(func.attributes);
 
     let is_extern = sym_info.is_extern;
 
+    if let Some(pos) = &func.variadic {
+        if !is_extern || func.body.is_some() {
+            ctx.report(error::variadic_not_extern(pos));
+        }
+        sym_info.variadic = Some(pos.clone());
+    }
+
     env.add_sym_info(func.id, sym_info);
 
     let body = match func.body {
         Some(body) => tr_expr(ctx, env, body)?,
         None => {
+            // The symbol stays registered so that calls still typecheck,
+            // the error stops compilation before anything is emitted.
             if !is_extern {
                 ctx.report(error::function_with_no_body(&func.pos));
             }
@@ -377,6 +553,7 @@ fn tr_func(
         params: named_params,
         args,
         ret_type,
+        ret_type_pos,
         body,
         pos: func.pos,
     };
@@ -396,6 +573,10 @@ fn tr_expr(
     });
     let data = match expr.data {
         in_a::ExprData::Var(path) => match env.find_symbol(path) {
+            Ok(out_a::SymRef::Global(id)) if env.is_alias(id) => {
+                ctx.report(error::alias_as_value(&pos));
+                out_a::ExprData::Error
+            }
             Ok(sym) => out_a::ExprData::Var(sym),
             Err(diag) => {
                 ctx.report(diag);
@@ -406,28 +587,17 @@ fn tr_expr(
             let expr_node = tr_expr(ctx, env, *expr_node)?;
             out_a::ExprData::FieldAccess(Box::new(expr_node), ident.name_str())
         }
-        in_a::ExprData::ClosedBlock(expr_nodes) => {
-            env.new_scope();
-            let expr_nodes = expr_nodes
-                .into_iter()
-                .map(|expr| tr_expr(ctx, env, expr))
-                .collect::<Result<_, _>>()?;
-            env.leave_scope();
-            let last = out_a::ExprNode {
-                data: out_a::ExprData::Tuple(vec![]),
-                pos: pos.clone(),
-            };
-            out_a::ExprData::Block(expr_nodes, Box::new(last))
+        in_a::ExprData::TupleField(expr_node, n) => {
+            let expr_node = tr_expr(ctx, env, *expr_node)?;
+            out_a::ExprData::TupleField(Box::new(expr_node), n)
         }
+        in_a::ExprData::ClosedBlock(expr_nodes) => tr_block(ctx, env, expr_nodes, None, &pos)?,
         in_a::ExprData::OpenBlock(expr_nodes, expr_node) => {
-            env.new_scope();
-            let expr_nodes = expr_nodes
-                .into_iter()
-                .map(|expr| tr_expr(ctx, env, expr))
-                .collect::<Result<_, _>>()?;
-            let expr_node = tr_expr(ctx, env, *expr_node)?;
-            env.leave_scope();
-            out_a::ExprData::Block(expr_nodes, Box::new(expr_node))
+            tr_block(ctx, env, expr_nodes, Some(*expr_node), &pos)?
+        }
+        in_a::ExprData::LetElse { .. } => {
+            ctx.report(error::let_else_outside_block(&pos));
+            out_a::ExprData::Error
         }
         in_a::ExprData::Return(expr_node) => {
             let expr_node = match expr_node {
@@ -447,7 +617,10 @@ fn tr_expr(
         } => {
             env.add_local_var(name.name_str());
             let tp = match tp {
-                Some(tp) => Some(env.resolve_type(ctx, tp)?),
+                Some(tp) => {
+                    let pos = tp.pos.clone();
+                    Some((env.resolve_type(ctx, tp)?, pos))
+                }
                 None => None,
             };
             let expr = Box::new(tr_expr(ctx, env, *expr)?);
@@ -462,13 +635,10 @@ fn tr_expr(
             let pr = tr_expr(ctx, env, *pr)?;
             let th = tr_expr(ctx, env, *th)?;
             let el = match el {
-                Some(expr_node) => tr_expr(ctx, env, *expr_node)?,
-                None => out_a::ExprNode {
-                    data: out_a::ExprData::Tuple(vec![]),
-                    pos: pos.clone(),
-                },
+                Some(expr_node) => Some(Box::new(tr_expr(ctx, env, *expr_node)?)),
+                None => None,
             };
-            out_a::ExprData::If(Box::new(pr), Box::new(th), Box::new(el))
+            out_a::ExprData::If(Box::new(pr), Box::new(th), el)
         }
         in_a::ExprData::StructCons(path, items) => {
             let sym_ref = match env.find_symbol(path) {
@@ -480,6 +650,10 @@ fn tr_expr(
             };
             let id = match sym_ref {
                 out_a::SymRef::Local(_) => panic!("local type definitons not supported"),
+                out_a::SymRef::Global(id) if env.is_alias(id) => {
+                    ctx.report(error::alias_as_value(&pos));
+                    return err_node;
+                }
                 out_a::SymRef::Global(id) => id,
             };
             let mut tr_items = HashMap::new();
@@ -496,6 +670,20 @@ fn tr_expr(
             let rexpr = tr_expr(ctx, env, *rexpr)?;
             out_a::ExprData::Assign(Box::new(lexpr), Box::new(rexpr))
         }
+        // `x op= e` is `x = x op e`, so the place is evaluated twice.
+        // It's fine as long as places don't have side effects.
+        in_a::ExprData::OpAssign(op, lexpr, rexpr) => {
+            let lexpr = tr_expr(ctx, env, *lexpr)?;
+            let rexpr = tr_expr(ctx, env, *rexpr)?;
+            let value = out_a::ExprNode {
+                data: out_a::ExprData::Builtin(
+                    binop_builtin(op).into(),
+                    vec![lexpr.clone(), rexpr],
+                ),
+                pos: pos.clone(),
+            };
+            out_a::ExprData::Assign(Box::new(lexpr), Box::new(value))
+        }
         in_a::ExprData::FunCall(expr_node, expr_nodes) => {
             let expr_node = tr_expr(ctx, env, *expr_node)?;
             let expr_nodes = expr_nodes
@@ -516,10 +704,16 @@ fn tr_expr(
             let expr_node = tr_expr(ctx, env, *expr_node)?;
             out_a::ExprData::Deref(Box::new(expr_node))
         }
-        in_a::ExprData::Number(num) => out_a::ExprData::NumLit(num),
+        in_a::ExprData::Neg(expr_node) => {
+            let expr_node = tr_expr(ctx, env, *expr_node)?;
+            out_a::ExprData::Neg(Box::new(expr_node))
+        }
+        in_a::ExprData::Number(num, suffix) => out_a::ExprData::NumLit(num, suffix),
         in_a::ExprData::Error => out_a::ExprData::Error,
         in_a::ExprData::Char(c) => out_a::ExprData::Char(c),
+        in_a::ExprData::Byte(b) => out_a::ExprData::Byte(b),
         in_a::ExprData::String(s) => out_a::ExprData::String(s),
+        in_a::ExprData::ByteString(bytes) => out_a::ExprData::ByteString(bytes),
         in_a::ExprData::Tuple(expr_nodes) => {
             let expr_nodes = expr_nodes
                 .into_iter()
@@ -540,6 +734,21 @@ fn tr_expr(
             let block = tr_expr(ctx, env, *block)?;
             out_a::ExprData::While(Box::new(expr), Box::new(block))
         }
+        in_a::ExprData::Loop(block) => {
+            let block = tr_expr(ctx, env, *block)?;
+            out_a::ExprData::Loop(Box::new(block))
+        }
+        in_a::ExprData::Break(expr_node) => {
+            let expr_node = match expr_node {
+                Some(expr_node) => tr_expr(ctx, env, *expr_node)?,
+                None => out_a::ExprNode {
+                    data: out_a::ExprData::Tuple(vec![]),
+                    pos: pos.clone(),
+                },
+            };
+            out_a::ExprData::Break(Box::new(expr_node))
+        }
+        in_a::ExprData::Continue => out_a::ExprData::Continue,
         in_a::ExprData::MethodCall(expr_node, ident, expr_nodes) => {
             let expr_node = tr_expr(ctx, env, *expr_node)?;
             let expr_nodes = expr_nodes
@@ -569,6 +778,11 @@ fn tr_expr(
             let tp = env.resolve_type(ctx, rtype_node)?;
             out_a::ExprData::Cast(Box::new(expr_node), tp)
         }
+        in_a::ExprData::Ascription(expr_node, rtype_node) => {
+            let expr_node = tr_expr(ctx, env, *expr_node)?;
+            let tp = env.resolve_type(ctx, rtype_node)?;
+            out_a::ExprData::Ascription(Box::new(expr_node), tp)
+        }
         in_a::ExprData::Builtin(ident, expr_nodes) => {
             let expr_nodes = expr_nodes
                 .into_iter()
@@ -576,11 +790,146 @@ fn tr_expr(
                 .collect::<Result<_, _>>()?;
             out_a::ExprData::Builtin(ident.data, expr_nodes)
         }
+        in_a::ExprData::BinOp(op, e1, e2) => {
+            let e1 = tr_expr(ctx, env, *e1)?;
+            let e2 = tr_expr(ctx, env, *e2)?;
+            out_a::ExprData::Builtin(binop_builtin(op).into(), vec![e1, e2])
+        }
+        // Logical operators are desugared into conditionals:
+        // `a && b` to `if a { b } else { false }`,
+        // `a || b` to `if a { true } else { b }`.
+        in_a::ExprData::And(e1, e2) => {
+            let e1 = tr_expr(ctx, env, *e1)?;
+            let e2 = tr_expr(ctx, env, *e2)?;
+            let el = out_a::ExprNode {
+                data: out_a::ExprData::BoolLit(false),
+                pos: pos.clone(),
+            };
+            out_a::ExprData::If(Box::new(e1), Box::new(e2), Some(Box::new(el)))
+        }
+        in_a::ExprData::Or(e1, e2) => {
+            let e1 = tr_expr(ctx, env, *e1)?;
+            let e2 = tr_expr(ctx, env, *e2)?;
+            let th = out_a::ExprNode {
+                data: out_a::ExprData::BoolLit(true),
+                pos: pos.clone(),
+            };
+            out_a::ExprData::If(Box::new(e1), Box::new(th), Some(Box::new(e2)))
+        }
     };
     let expr = out_a::ExprNode { data, pos };
     Ok(expr)
 }
 
+/// Name of the builtin a binary operator is desugared to.
+fn binop_builtin(op: in_a::BinOp) -> &'static str {
+    match op {
+        in_a::BinOp::Add => "iadd",
+        in_a::BinOp::Sub => "isub",
+        in_a::BinOp::Mul => "imul",
+        in_a::BinOp::Div => "idiv",
+        in_a::BinOp::Lt => "lt",
+        in_a::BinOp::Le => "le",
+        in_a::BinOp::Eq => "eq",
+        in_a::BinOp::Ne => "ne",
+        in_a::BinOp::Gt => "gt",
+        in_a::BinOp::Ge => "ge",
+    }
+}
+
+/// Translate statements of a block and its trailing expression, if any.
+///
+/// `let P = e else { ... };` is lowered to a match whose first arm holds
+/// the rest of the block, so bindings of `P` are visible there:
+///
+/// ```mst
+/// match e {
+///     P => { rest },
+///     _ => ({ ... } : never),
+/// }
+/// ```
+fn tr_block(
+    ctx: &mut Context,
+    env: &mut Env,
+    mut stmts: Vec<in_a::ExprNode>,
+    mut last: Option<in_a::ExprNode>,
+    pos: &Position,
+) -> Result<out_a::ExprData, InternalError> {
+    if let Some(
+        expr @ in_a::ExprNode {
+            data: in_a::ExprData::LetElse { .. },
+            ..
+        },
+    ) = last
+    {
+        stmts.push(expr);
+        last = None;
+    }
+    env.new_scope();
+    let mut expr_nodes = vec![];
+    let mut let_else = None;
+    let mut stmts = stmts.into_iter();
+    while let Some(stmt) = stmts.next() {
+        let in_a::ExprData::LetElse { pattern, expr, els } = stmt.data else {
+            expr_nodes.push(tr_expr(ctx, env, stmt)?);
+            continue;
+        };
+        let expr = tr_expr(ctx, env, *expr)?;
+        let els = tr_expr(ctx, env, *els)?;
+        let els_pos = els.pos.clone();
+        let rest_pos = Position::new(pos.filename.clone(), stmt.pos.end, pos.end);
+        env.new_scope();
+        let pattern = tr_pattern(ctx, env, pattern)?;
+        let rest = tr_block(ctx, env, stmts.collect(), last.take(), &rest_pos)?;
+        env.leave_scope();
+        let clauses = vec![
+            out_a::MatchClause {
+                pattern,
+                expr: out_a::ExprNode {
+                    data: rest,
+                    pos: rest_pos.clone(),
+                },
+                pos: rest_pos,
+            },
+            out_a::MatchClause {
+                pattern: out_a::PatternNode {
+                    data: out_a::PatternData::Wildcard,
+                    pos: els_pos.clone(),
+                },
+                expr: out_a::ExprNode {
+                    data: out_a::ExprData::Ascription(Box::new(els), Type::builtin("never")),
+                    pos: els_pos.clone(),
+                },
+                pos: els_pos,
+            },
+        ];
+        let_else = Some(out_a::ExprNode {
+            data: out_a::ExprData::Match(Box::new(expr), clauses),
+            pos: stmt.pos,
+        });
+        break;
+    }
+    let last = match (let_else, last) {
+        (Some(let_else), _) => let_else,
+        (None, Some(last)) => tr_expr(ctx, env, last)?,
+        // Block ending with `return`, `break` or `continue` never yields
+        // a value, so it can stand in for any type.
+        (None, None) => match expr_nodes.last() {
+            Some(out_a::ExprNode {
+                data:
+                    out_a::ExprData::Return(_) | out_a::ExprData::Break(_) | out_a::ExprData::Continue,
+                ..
+            }) => expr_nodes.pop().unwrap(),
+            _ => out_a::ExprNode {
+                data: out_a::ExprData::Tuple(vec![]),
+                pos: pos.clone(),
+            },
+        },
+    };
+    env.leave_scope();
+    Ok(out_a::ExprData::Block(expr_nodes, Box::new(last)))
+}
+
 fn tr_clause(
     ctx: &mut Context,
     env: &mut Env,
@@ -628,6 +977,10 @@ fn tr_pattern(
                     ctx.report(error::local_type(&pos));
                     out_a::PatternData::Error
                 }
+                ast::SymRef::Global(id) if env.is_alias(id) => {
+                    ctx.report(error::alias_as_value(&pos));
+                    out_a::PatternData::Error
+                }
                 ast::SymRef::Global(id) => {
                     let pattern_nodes = pattern_nodes
                         .into_iter()
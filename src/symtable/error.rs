@@ -1,11 +1,29 @@
+use colored::Color;
+
 use crate::{
     common::Position,
     error::diagnostic::{Diagnostic, Label},
 };
 
-pub fn recursive_types(pos: &Position) -> Diagnostic {
+/// Report a cycle of types containing each other, starting with the reported one.
+pub fn recursive_types(pos: &Position, cycle: Vec<String>) -> Diagnostic {
+    let name = cycle[0].clone();
+    let chain = cycle
+        .iter()
+        .chain(cycle.first())
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>()
+        .join(" contains ");
+    let next = cycle.get(1).unwrap_or(&cycle[0]).clone();
     Diagnostic::error(pos)
-        .with_label(Label::new(pos).with_msg(Box::new(move || format!("recursive type"))))
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("recursive type `{}` has infinite size", name)
+        })))
+        .with_note(chain)
+        .with_note(format!(
+            "insert a pointer to break the cycle, for example `*{}`",
+            next
+        ))
 }
 
 pub(crate) fn unsized_type(pos: &Position) -> Diagnostic {
@@ -13,3 +31,27 @@ pub(crate) fn unsized_type(pos: &Position) -> Diagnostic {
         Label::new(pos).with_msg(Box::new(move || format!("this type has infinite size"))),
     )
 }
+
+pub(crate) fn large_enum_variant(
+    pos: &Position,
+    cons_pos: &Position,
+    size: u32,
+    largest: u32,
+    second: u32,
+) -> Diagnostic {
+    Diagnostic::warning(pos)
+        .with_label(
+            Label::new(pos)
+                .with_msg(Box::new(move || format!("this enum is {size} bytes big")))
+                .with_color(Color::Yellow),
+        )
+        .with_label(
+            Label::new(cons_pos)
+                .with_msg(Box::new(move || {
+                    format!("this variant has {largest} bytes, the second largest has {second}")
+                }))
+                .with_color(Color::Yellow),
+        )
+        .with_note("consider putting the payload of this variant behind a pointer".into())
+        .with_note("enabled by `-W large_enum_variant`".into())
+}
@@ -1,11 +1,12 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Layout {
     pub size: u32,
+    /// Alignment in bytes, always a power of two.
     pub align: u32,
     pub kind: LayoutKind,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
     Tu8,
     Tu16,
@@ -19,13 +20,32 @@ pub enum Type {
     Tisize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LayoutKind {
     Primitive(Type),
     Struct(Vec<(Layout, i32)>),
     Union(Vec<Layout>),
 }
 
+impl Type {
+    /// Size in bytes.
+    pub(crate) fn size(&self) -> u32 {
+        match self {
+            Type::Tu8 | Type::Ti8 => 1,
+            Type::Tu16 | Type::Ti16 => 2,
+            Type::Tu32 | Type::Ti32 => 4,
+            Type::Tu64 | Type::Ti64 | Type::Tusize | Type::Tisize => 8,
+        }
+    }
+
+    pub(crate) fn is_signed(&self) -> bool {
+        match self {
+            Type::Tu8 | Type::Tu16 | Type::Tu32 | Type::Tu64 | Type::Tusize => false,
+            Type::Ti8 | Type::Ti16 | Type::Ti32 | Type::Ti64 | Type::Tisize => true,
+        }
+    }
+}
+
 impl Layout {
     pub(crate) fn require_stack(&self) -> bool {
         match &self.kind {
@@ -34,4 +54,71 @@ impl Layout {
             LayoutKind::Union(layouts) => true,
         }
     }
+
+    /// Layout of the tag of an enum, which holds index of the constructor.
+    ///
+    /// An enum is laid out as a struct of the tag and a union of
+    /// its constructors' fields.
+    pub(crate) fn enum_tag() -> Layout {
+        Layout {
+            size: 4,
+            align: 4,
+            kind: LayoutKind::Primitive(Type::Tu32),
+        }
+    }
+
+    /// Layouts of the fields of each constructor of an enum,
+    /// indexed by constructor.
+    pub(crate) fn enum_payloads(&self) -> &[Layout] {
+        let LayoutKind::Struct(items) = &self.kind else {
+            unreachable!("enum is a struct of the tag and the payload")
+        };
+        let [_, (payload, _)] = &items[..] else {
+            unreachable!("enum is a struct of the tag and the payload")
+        };
+        let LayoutKind::Union(variants) = &payload.kind else {
+            unreachable!("payload of an enum is a union")
+        };
+        variants
+    }
+
+    /// Layout of an enum value built with given constructor,
+    /// which is the tag followed by the fields of the constructor.
+    pub(crate) fn enum_variant(&self, cons: usize) -> Layout {
+        let LayoutKind::Struct(items) = &self.kind else {
+            unreachable!("enum is a struct of the tag and the payload")
+        };
+        let [(tag, _), (payload, offset)] = &items[..] else {
+            unreachable!("enum is a struct of the tag and the payload")
+        };
+        let LayoutKind::Union(variants) = &payload.kind else {
+            unreachable!("payload of an enum is a union")
+        };
+        let LayoutKind::Struct(fields) = &variants[cons].kind else {
+            unreachable!("constructor fields are laid out as a struct")
+        };
+        let mut items = vec![(tag.clone(), 0)];
+        items.extend(
+            fields
+                .iter()
+                .map(|(layout, field_offset)| (layout.clone(), offset + field_offset)),
+        );
+        Layout {
+            size: self.size,
+            align: self.align,
+            kind: LayoutKind::Struct(items),
+        }
+    }
+}
+
+/// Layout of a named type, as seen from outside of the compiler.
+///
+/// Unlike [Layout] it doesn't expose the backend representation,
+/// all values are in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicLayout {
+    pub size: u32,
+    pub align: u32,
+    /// Field names and their offsets, ordered by offset.
+    pub fields: Vec<(String, u32)>,
 }
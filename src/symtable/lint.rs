@@ -0,0 +1,54 @@
+use crate::{
+    error::{Lint, context::Context},
+    symtable::{SymKind, SymTable, TypeKind, error},
+};
+
+/// Minimal difference (in bytes) between the largest and the second largest
+/// variant of an enum, for which a warning is reported.
+const LARGE_VARIANT_DIFF: u32 = 128;
+
+impl SymTable {
+    /// Warn about enums whose size is dominated by a single large variant.
+    ///
+    /// Every value of an enum is as big as its largest variant, so one big
+    /// payload makes all the other variants waste space.
+    pub(crate) fn check_enum_sizes(&self, ctx: &mut Context) {
+        if !ctx.lint_enabled(Lint::LargeEnumVariant) {
+            return;
+        }
+        for (tvar, info) in &self.tvar_map {
+            let TypeKind::Enum {
+                params,
+                constructors,
+            } = &info.kind
+            else {
+                continue;
+            };
+            if !params.is_empty() {
+                continue;
+            }
+            let layout = self.named_layout(*tvar, &[]);
+            let payloads = layout.enum_payloads();
+            let mut sizes = vec![];
+            for id in constructors.values() {
+                let cons = self.find_sym_info(*id);
+                let SymKind::EnumCons { id, .. } = &cons.kind else {
+                    unreachable!()
+                };
+                sizes.push((payloads[*id].size, &cons.pos));
+            }
+            sizes.sort_by_key(|(size, _)| *size);
+            if let [.., (second, _), (largest, pos)] = sizes[..]
+                && largest - second >= LARGE_VARIANT_DIFF
+            {
+                ctx.report(error::large_enum_variant(
+                    &info.pos,
+                    pos,
+                    layout.size,
+                    largest,
+                    second,
+                ));
+            }
+        }
+    }
+}
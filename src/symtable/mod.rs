@@ -1,15 +1,19 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
 
 mod error;
 pub mod layout;
-mod type_sort;
+mod lint;
+pub(crate) mod type_sort;
 
 use crate::{
     common::{NodeID, Position, RAttribute},
     error::context::Context,
     symtable::{
-        layout::{Layout, LayoutKind},
-        type_sort::{make_dep_tree, topo_sort},
+        layout::{Layout, LayoutKind, PublicLayout},
+        type_sort::{find_cycle, make_dep_tree, topo_sort},
     },
     tp::{TVar, Type, TypeView},
 };
@@ -18,6 +22,11 @@ use crate::{
 pub struct SymTable {
     node_map: HashMap<NodeID, SymInfo>,
     tvar_map: HashMap<TVar, TypeInfo>,
+    /// Layouts of named types already computed, by layouts of type arguments.
+    ///
+    /// Layout of a named type only depends on layouts of its arguments,
+    /// so different instantiations that are laid out the same share an entry.
+    layouts: RefCell<HashMap<(TVar, Vec<Layout>), Layout>>,
 }
 
 impl SymTable {
@@ -27,12 +36,47 @@ impl SymTable {
         tvar_map: HashMap<TVar, TypeInfo>,
     ) -> SymTable {
         let dep_tree: HashMap<TVar, HashSet<TVar>> = make_dep_tree(&tvar_map, &node_map);
-        let (_, cyclic) = topo_sort(dep_tree);
+        let (_, cyclic) = topo_sort(dep_tree.clone());
+        let st = Self {
+            node_map,
+            tvar_map,
+            layouts: RefCell::new(HashMap::new()),
+        };
+        if cyclic.is_empty() {
+            st.check_enum_sizes(ctx);
+        }
+        st.report_cycles(ctx, &dep_tree, cyclic);
+        st
+    }
+
+    /// Report cycles of types that contain each other.
+    ///
+    /// Every type is reported once, as a part of the first cycle found through it.
+    fn report_cycles(
+        &self,
+        ctx: &mut Context,
+        dep_tree: &HashMap<TVar, HashSet<TVar>>,
+        mut cyclic: Vec<TVar>,
+    ) {
+        cyclic.sort();
+        let nodes: HashSet<TVar> = cyclic.iter().copied().collect();
+        let mut reported = HashSet::new();
         for tv in cyclic {
-            let info = tvar_map.get(&tv).unwrap();
-            ctx.report(error::recursive_types(&info.pos));
+            if reported.contains(&tv) {
+                continue;
+            }
+            // types depending on a cycle without being on one are left too
+            let Some(cycle) = find_cycle(dep_tree, &nodes, tv) else {
+                continue;
+            };
+            reported.extend(cycle.iter().copied());
+            let names = cycle
+                .iter()
+                .map(|tv| self.find_type_info(*tv).name.clone())
+                .collect();
+            let info = self.find_type_info(tv);
+            ctx.report(error::recursive_types(&info.pos, names));
         }
-        Self { node_map, tvar_map }
     }
 
     pub fn get_items(&self) -> &HashMap<NodeID, SymInfo> {
@@ -47,6 +91,46 @@ impl SymTable {
         self.node_map.get(&node_id).unwrap()
     }
 
+    /// Record the evaluated value of a constant, or initial value of a static.
+    pub(crate) fn set_const_value(&mut self, node_id: NodeID, bits: usize) {
+        if let Some(SymInfo {
+            kind: SymKind::Const { value, .. } | SymKind::Static { value, .. },
+            ..
+        }) = self.node_map.get_mut(&node_id)
+        {
+            *value = Some(bits);
+        }
+    }
+
+    /// Register a specialization of generic function `id`
+    /// for given type arguments, and return its fresh node id.
+    ///
+    /// Specializations are private to the object file,
+    /// so their names are always mangled.
+    pub(crate) fn add_specialization(&mut self, id: NodeID, type_args: &[Type]) -> NodeID {
+        let info = self.find_sym_info(id);
+        let SymKind::Func { params, args, ret } = &info.kind else {
+            panic!("only functions can be specialized")
+        };
+        let subst: HashMap<TVar, Type> = params
+            .iter()
+            .copied()
+            .zip(type_args.iter().cloned())
+            .collect();
+        let kind = SymKind::Func {
+            params: vec![],
+            args: args.iter().map(|tp| tp.substitute(&subst)).collect(),
+            ret: ret.substitute(&subst),
+        };
+        let type_args: Vec<String> = type_args.iter().map(|tp| tp.to_string()).collect();
+        let name = format!("{}<{}>", info.name, type_args.join(", "));
+        let mut new_info = SymInfo::build(name, info.pos.clone(), kind);
+        new_info.must_use = info.must_use;
+        let new_id = NodeID::new_global();
+        self.node_map.insert(new_id, new_info);
+        new_id
+    }
+
     pub(crate) fn find_type_info(&self, tvar: TVar) -> &TypeInfo {
         self.tvar_map.get(&tvar).unwrap()
     }
@@ -62,64 +146,181 @@ impl SymTable {
         None
     }
 
+    /// Returns layout of a type declared with given node id.
+    ///
+    /// Returns `None` if the node isn't a type or the type has parameters.
+    pub fn type_layout(&self, id: NodeID) -> Option<PublicLayout> {
+        let tvar = match self.node_map.get(&id)?.kind {
+            SymKind::Struct(tvar) | SymKind::Enum(tvar) => tvar,
+            SymKind::Func { .. }
+            | SymKind::EnumCons { .. }
+            | SymKind::Const { .. }
+            | SymKind::Static { .. } => return None,
+        };
+        let info = self.find_type_info(tvar);
+        let tp = Type::named_var(tvar, &info.name, &info.pos).ok()?;
+        let layout = self.get_layout(&tp);
+        let mut fields = match (&info.kind, &layout.kind) {
+            (TypeKind::Struct { fields, .. }, LayoutKind::Struct(items)) => fields
+                .iter()
+                .map(|(name, (id, _))| (name.clone(), items[*id].1 as u32))
+                .collect(),
+            _ => vec![],
+        };
+        fields.sort_by_key(|(_, offset)| *offset);
+        Some(PublicLayout {
+            size: layout.size,
+            align: layout.align,
+            fields,
+        })
+    }
+
+    /// Returns layout of a type with given name.
+    ///
+    /// If more than one type has this name, any of them may be returned.
+    pub fn type_layout_by_name(&self, name: &str) -> Option<PublicLayout> {
+        let (id, _) = self.node_map.iter().find(|(_, info)| {
+            info.name == name && matches!(info.kind, SymKind::Struct(_) | SymKind::Enum(_))
+        })?;
+        self.type_layout(*id)
+    }
+
+    /// Lay out consecutive fields of given types, as in a struct.
+    fn fields_layout<'a>(&self, tps: impl Iterator<Item = &'a Type>) -> Layout {
+        struct_layout(tps.map(|tp| self.get_layout(tp)))
+    }
+
     pub(crate) fn get_layout(&self, tp: &Type) -> Layout {
         match tp.view() {
             TypeView::Unknown => todo!(),
             TypeView::UVar(uvar) => todo!(),
             TypeView::NumericUVar(uvar) => todo!(),
-            TypeView::NamedVar(tvar, _) | TypeView::Var(tvar) => {
-                let t_info = self.find_type_info(tvar);
-                match &t_info.kind {
-                    TypeKind::Builtin => {
-                        let size = tvar.builtin_size().unwrap();
-                        let tp = tvar.builtin_as_primitive().unwrap();
-                        Layout {
-                            size,
-                            align: 3,
-                            kind: LayoutKind::Primitive(tp),
-                        }
-                    }
-                    TypeKind::Struct { params, fields } => {
-                        let mut v: Vec<_> = fields.into_iter().map(|(_, v)| v).collect();
-                        v.sort_by_key(|(k, _)| k);
-                        let mut layouts = vec![];
-                        let mut curr_offset = 0;
-                        for (_, tp) in v {
-                            let layout = self.get_layout(tp);
-                            // Align curr_offset to layout.align
-                            let align_mask = (1 << layout.align) - 1;
-                            curr_offset = (curr_offset + align_mask) & !align_mask;
-                            let total_size = layout.size;
-                            layouts.push((layout, curr_offset as i32));
-                            curr_offset += total_size;
-                        }
-                        let struct_align = layouts
-                            .iter()
-                            .map(|(layout, _)| layout.align)
-                            .max()
-                            .unwrap_or(1);
-                        Layout {
-                            size: curr_offset,
-                            align: struct_align,
-                            kind: LayoutKind::Struct(layouts),
-                        }
-                    }
-                    TypeKind::Enum {
-                        params,
-                        constructors,
-                    } => todo!(),
+            TypeView::NamedVar(tvar, _) | TypeView::Var(tvar) => self.named_layout(tvar, &[]),
+            TypeView::Tuple(items) => self.fields_layout(items.iter()),
+            TypeView::Array(size, tp) => {
+                let layout = self.get_layout(&tp);
+                let stride = layout.size;
+                let items = (0..size)
+                    .map(|id| (layout.clone(), (id as u32 * stride) as i32))
+                    .collect();
+                Layout {
+                    size: stride * size as u32,
+                    align: layout.align,
+                    kind: LayoutKind::Struct(items),
                 }
             }
-            TypeView::Tuple(items) => todo!(),
-            TypeView::Array(_, _) => todo!(),
             TypeView::Fun(_, _) | TypeView::Ptr(_) | TypeView::MutPtr(_) => Layout {
                 size: 8,
-                align: 3,
+                align: 8,
                 kind: LayoutKind::Primitive(layout::Type::Tusize),
             },
-            TypeView::TypeApp(tvar, _, items) => todo!(),
+            // pointer to the first element and the length
+            TypeView::Slice(_) | TypeView::MutSlice(_) => {
+                let word = Layout {
+                    size: 8,
+                    align: 8,
+                    kind: LayoutKind::Primitive(layout::Type::Tusize),
+                };
+                struct_layout([word.clone(), word].into_iter())
+            }
+            TypeView::TypeApp(tvar, _, items) => self.named_layout(tvar, &items),
         }
     }
+
+    /// Layout of a named type applied to given type arguments, computed once.
+    fn named_layout(&self, tvar: TVar, args: &[Type]) -> Layout {
+        let key = (tvar, args.iter().map(|tp| self.get_layout(tp)).collect());
+        if let Some(layout) = self.layouts.borrow().get(&key) {
+            return layout.clone();
+        }
+        let layout = self.compute_named_layout(tvar, args);
+        self.layouts.borrow_mut().insert(key, layout.clone());
+        layout
+    }
+
+    fn compute_named_layout(&self, tvar: TVar, args: &[Type]) -> Layout {
+        let t_info = self.find_type_info(tvar);
+        match &t_info.kind {
+            // nothing is ever stored, so it takes no space and no loads
+            // or stores are emitted for it, as for the unit type
+            TypeKind::Builtin if tvar.is_never() => struct_layout(std::iter::empty()),
+            TypeKind::Builtin => {
+                let size = tvar.builtin_size().unwrap();
+                let tp = tvar.builtin_as_primitive().unwrap();
+                Layout {
+                    size,
+                    align: size,
+                    kind: LayoutKind::Primitive(tp),
+                }
+            }
+            TypeKind::Struct { params, fields } => {
+                let subst: HashMap<TVar, Type> =
+                    params.iter().copied().zip(args.iter().cloned()).collect();
+                let mut v: Vec<_> = fields.values().collect();
+                v.sort_by_key(|(k, _)| k);
+                let tps: Vec<_> = v.into_iter().map(|(_, tp)| tp.substitute(&subst)).collect();
+                self.fields_layout(tps.iter())
+            }
+            TypeKind::Enum {
+                params,
+                constructors,
+            } => {
+                let subst: HashMap<TVar, Type> =
+                    params.iter().copied().zip(args.iter().cloned()).collect();
+                let mut v: Vec<_> = constructors
+                    .values()
+                    .map(|id| match &self.find_sym_info(*id).kind {
+                        SymKind::EnumCons { id, args, .. } => (*id, args),
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                v.sort_by_key(|(k, _)| *k);
+                let variants: Vec<_> = v
+                    .into_iter()
+                    .map(|(_, args)| {
+                        let tps: Vec<_> = args.iter().map(|tp| tp.substitute(&subst)).collect();
+                        self.fields_layout(tps.iter())
+                    })
+                    .collect();
+                let align = variants.iter().map(|l| l.align).max().unwrap_or(1);
+                let size = variants.iter().map(|l| l.size).max().unwrap_or(0);
+                let payload = Layout {
+                    size: align_to(size, align),
+                    align,
+                    kind: LayoutKind::Union(variants),
+                };
+                struct_layout([Layout::enum_tag(), payload].into_iter())
+            }
+        }
+    }
+}
+
+/// Lay out consecutive fields, as in a struct.
+fn struct_layout(fields: impl Iterator<Item = Layout>) -> Layout {
+    let mut layouts = vec![];
+    let mut curr_offset = 0;
+    for layout in fields {
+        curr_offset = align_to(curr_offset, layout.align);
+        let total_size = layout.size;
+        layouts.push((layout, curr_offset as i32));
+        curr_offset += total_size;
+    }
+    let struct_align = layouts
+        .iter()
+        .map(|(layout, _)| layout.align)
+        .max()
+        .unwrap_or(1);
+    Layout {
+        // pad the end, so that the stride of an array is the size
+        size: align_to(curr_offset, struct_align),
+        align: struct_align,
+        kind: LayoutKind::Struct(layouts),
+    }
+}
+
+/// Round `offset` up to a multiple of `align`.
+fn align_to(offset: u32, align: u32) -> u32 {
+    (offset + align - 1) & !(align - 1)
 }
 
 #[derive(Debug)]
@@ -129,7 +330,14 @@ pub struct SymInfo {
     pub kind: SymKind,
     pub builtin_name: Option<String>,
     pub is_extern: bool,
+    /// Position of `...` of a function accepting arguments
+    /// past the declared ones, like C `printf`.
+    pub variadic: Option<Position>,
     pub mangle: bool,
+    /// Name of the symbol in the object file, given by `@link_name`.
+    pub link_name: Option<String>,
+    /// Results of calls to this function shouldn't be discarded.
+    pub must_use: bool,
 }
 
 impl SymInfo {
@@ -140,7 +348,10 @@ impl SymInfo {
             kind,
             builtin_name: None,
             is_extern: false,
+            variadic: None,
             mangle: true,
+            link_name: None,
+            must_use: false,
         }
     }
 
@@ -150,6 +361,8 @@ impl SymInfo {
             match attr.name.data.as_str() {
                 "extern" => self.is_extern = true,
                 "no_mangle" => self.mangle = false,
+                "link_name" => self.link_name = attr.args.into_iter().next(),
+                "must_use" => self.must_use = true,
                 _ => continue,
             }
         }
@@ -160,7 +373,8 @@ impl SymInfo {
 #[derive(Debug)]
 pub enum SymKind {
     Func {
-        params: HashSet<TVar>,
+        /// Type parameters, in order of declaration.
+        params: Vec<TVar>,
         args: Vec<Type>,
         ret: Type,
     },
@@ -171,6 +385,17 @@ pub enum SymKind {
         args: Vec<Type>,
         parent: NodeID,
     },
+    Const {
+        tp: Type,
+        /// Bits of the value, known once the typechecker evaluates it.
+        value: Option<usize>,
+    },
+    Static {
+        tp: Type,
+        is_mut: bool,
+        /// Bits of the initial value, known once the typechecker evaluates it.
+        value: Option<usize>,
+    },
 }
 
 #[derive(Debug)]
@@ -184,11 +409,13 @@ pub struct TypeInfo {
 pub enum TypeKind {
     Builtin,
     Struct {
-        params: HashSet<TVar>,
+        /// Type parameters, in order of declaration.
+        params: Vec<TVar>,
         fields: HashMap<String, (usize, Type)>,
     },
     Enum {
-        params: HashSet<TVar>,
+        /// Type parameters, in order of declaration.
+        params: Vec<TVar>,
         constructors: HashMap<String, NodeID>,
     },
 }
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
 
 use crate::{
     common::{NodeID, Position},
@@ -7,8 +10,10 @@ use crate::{
     tp::{TVar, Type, TypeView},
 };
 
-pub fn reverse_graph(graph: &HashMap<TVar, HashSet<TVar>>) -> HashMap<TVar, HashSet<TVar>> {
-    let mut rev: HashMap<TVar, HashSet<TVar>> = HashMap::new();
+pub fn reverse_graph<T: Copy + Eq + Hash>(
+    graph: &HashMap<T, HashSet<T>>,
+) -> HashMap<T, HashSet<T>> {
+    let mut rev: HashMap<T, HashSet<T>> = HashMap::new();
 
     for node in graph.keys() {
         rev.entry(*node).or_default();
@@ -23,12 +28,16 @@ pub fn reverse_graph(graph: &HashMap<TVar, HashSet<TVar>>) -> HashMap<TVar, Hash
     rev
 }
 
-pub fn topo_sort(dep_tree: HashMap<TVar, HashSet<TVar>>) -> (Vec<TVar>, Vec<TVar>) {
+/// Sort nodes of a dependency graph, so that each node comes after
+/// its dependencies.
+///
+/// Returns the sorted nodes and the nodes on cycles, which are left out.
+pub fn topo_sort<T: Copy + Eq + Hash>(dep_tree: HashMap<T, HashSet<T>>) -> (Vec<T>, Vec<T>) {
     let n = dep_tree.len();
-    let mut indeg = HashMap::<TVar, usize>::new();
+    let mut indeg = HashMap::<T, usize>::new();
 
-    for (tvar, set) in reverse_graph(&dep_tree) {
-        indeg.insert(tvar, set.len());
+    for (node, set) in reverse_graph(&dep_tree) {
+        indeg.insert(node, set.len());
     }
 
     let mut q = VecDeque::new();
@@ -54,16 +63,65 @@ pub fn topo_sort(dep_tree: HashMap<TVar, HashSet<TVar>>) -> (Vec<TVar>, Vec<TVar
     order.reverse();
 
     if order.len() != n {
-        let left: Vec<_> = indeg
+        let mut left: HashSet<T> = indeg
             .into_iter()
             .filter_map(|(k, v)| if v > 0 { Some(k) } else { None })
             .collect();
-        return (order, left);
+        // Nodes only depended on by a cycle are left too, but aren't on it:
+        // peel off the ones whose dependencies are all sorted.
+        let mut deps_first = vec![];
+        while let Some(node) = left
+            .iter()
+            .copied()
+            .find(|node| dep_tree[node].iter().all(|dep| !left.contains(dep)))
+        {
+            left.remove(&node);
+            deps_first.push(node);
+        }
+        deps_first.extend(order);
+        return (deps_first, left.into_iter().collect());
     }
 
     (order, vec![])
 }
 
+/// Find the shortest cycle through `start` that stays within `nodes`.
+///
+/// Returns nodes of the cycle starting with `start`, each depending on the
+/// next one and the last on `start`, or `None` if `start` isn't on a cycle.
+pub fn find_cycle<T: Copy + Eq + Hash + Ord>(
+    dep_tree: &HashMap<T, HashSet<T>>,
+    nodes: &HashSet<T>,
+    start: T,
+) -> Option<Vec<T>> {
+    let mut prev: HashMap<T, T> = HashMap::new();
+    let mut q = VecDeque::from([start]);
+    while let Some(node) = q.pop_front() {
+        // visit dependencies in a fixed order, so the cycle is deterministic
+        let mut deps: Vec<T> = dep_tree[&node]
+            .iter()
+            .copied()
+            .filter(|dep| nodes.contains(dep))
+            .collect();
+        deps.sort();
+        for dep in deps {
+            if dep == start {
+                let mut cycle = vec![node];
+                while let Some(p) = prev.get(cycle.last().unwrap()) {
+                    cycle.push(*p);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if !prev.contains_key(&dep) {
+                prev.insert(dep, node);
+                q.push_back(dep);
+            }
+        }
+    }
+    None
+}
+
 pub fn make_dep_tree(
     tvar_map: &HashMap<TVar, TypeInfo>,
     node_map: &HashMap<NodeID, SymInfo>,
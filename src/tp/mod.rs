@@ -1,4 +1,4 @@
-mod error;
+pub(crate) mod error;
 mod tvar;
 mod uvar;
 
@@ -15,11 +15,40 @@ use crate::{
     error::diagnostic::{Diagnostic, Label},
 };
 
-pub const BUILTIN_TYPES: [&'static str; 13] = [
+pub const BUILTIN_TYPES: [&'static str; 14] = [
     "never", "bool", "order", "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64",
-    "isize",
+    "isize", "char",
 ];
 
+/// Allocator of unification variables.
+///
+/// Variables are told apart by identity, ids only name them in messages,
+/// so every function is checked with its own context and unification
+/// doesn't depend on any global state.
+#[derive(Debug, Default)]
+pub struct TypeCtx {
+    last_uvar: usize,
+}
+
+impl TypeCtx {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fresh_uvar(&mut self) -> Type {
+        Type(TypeView::UVar(UVar::new(self.next_id())))
+    }
+
+    pub fn numeric_uvar(&mut self) -> Type {
+        Type(TypeView::NumericUVar(UVar::new(self.next_id())))
+    }
+
+    fn next_id(&mut self) -> usize {
+        self.last_uvar += 1;
+        self.last_uvar
+    }
+}
+
 /// The abstract type representation.
 ///
 /// Use [Type::view] to see the actual type.
@@ -38,6 +67,9 @@ pub enum TypeView {
     Fun(Vec<Type>, Box<Type>),
     Ptr(Box<Type>),
     MutPtr(Box<Type>),
+    /// Pointer to the first element and the number of elements.
+    Slice(Box<Type>),
+    MutSlice(Box<Type>),
     TypeApp(TVar, String, Vec<Type>),
 }
 
@@ -85,6 +117,14 @@ impl Type {
         Type(TypeView::MutPtr(Box::new(tp)))
     }
 
+    pub(crate) fn slice(tp: Type) -> Type {
+        Type(TypeView::Slice(Box::new(tp)))
+    }
+
+    pub(crate) fn mut_slice(tp: Type) -> Type {
+        Type(TypeView::MutSlice(Box::new(tp)))
+    }
+
     pub(crate) fn named_var(tvar: TVar, name: &str, pos: &Position) -> Result<Type, Diagnostic> {
         if let TVarKind::TypeCons(n) = tvar.kind() {
             return Err(error::type_params_mismatch(pos, n.into(), 0));
@@ -100,14 +140,6 @@ impl Type {
         Type(TypeView::Unknown)
     }
 
-    pub fn fresh_uvar() -> Type {
-        Type(TypeView::UVar(UVar::new()))
-    }
-
-    pub fn numeric_uvar() -> Type {
-        Type(TypeView::NumericUVar(UVar::new()))
-    }
-
     pub(crate) fn array(size: usize, tp: Type) -> Type {
         Type(TypeView::Array(size, Box::new(tp)))
     }
@@ -161,6 +193,14 @@ impl Type {
                 let tp = tp.substitute(subst);
                 Type::mut_ptr(tp)
             }
+            TypeView::Slice(tp) => {
+                let tp = tp.substitute(subst);
+                Type::slice(tp)
+            }
+            TypeView::MutSlice(tp) => {
+                let tp = tp.substitute(subst);
+                Type::mut_slice(tp)
+            }
             TypeView::TypeApp(tvar, name, items) => {
                 let tps = items.iter().map(|tp| tp.substitute(subst)).collect();
                 unsafe { Type::type_app(tvar, &name, tps, &Position::nowhere()).unwrap_unchecked() }
@@ -172,8 +212,9 @@ impl Type {
     /// that this type's size depends on.
     pub fn get_size_dependencies(&self) -> HashSet<TVar> {
         match &self.view() {
-            TypeView::Unknown => HashSet::new(),
-            TypeView::UVar(uvar) | TypeView::NumericUVar(uvar) => panic!(),
+            // Nothing is known about the type, so it can't make a cycle.
+            // Types left uninferred are reported by the typechecker.
+            TypeView::Unknown | TypeView::UVar(_) | TypeView::NumericUVar(_) => HashSet::new(),
             TypeView::TypeApp(tvar, _, _) | TypeView::Var(tvar) | TypeView::NamedVar(tvar, _) => {
                 match tvar.kind() {
                     // don't return parameters, they will get the unsized treatment
@@ -194,7 +235,11 @@ impl Type {
             }
             TypeView::Array(_, tp) => tp.get_size_dependencies(),
             // pointer types break the dependency
-            TypeView::Fun(_, _) | TypeView::Ptr(_) | TypeView::MutPtr(_) => HashSet::new(),
+            TypeView::Fun(_, _)
+            | TypeView::Ptr(_)
+            | TypeView::MutPtr(_)
+            | TypeView::Slice(_)
+            | TypeView::MutSlice(_) => HashSet::new(),
         }
     }
 }
@@ -215,6 +260,8 @@ impl Display for Type {
             TypeView::UVar(uvar) => write!(f, "U?#{}", uvar.id().unwrap()),
             TypeView::Ptr(tp) => write!(f, "*{}", tp),
             TypeView::MutPtr(tp) => write!(f, "*mut {}", tp),
+            TypeView::Slice(tp) => write!(f, "[]{}", tp),
+            TypeView::MutSlice(tp) => write!(f, "[]mut {}", tp),
             TypeView::Unknown => write!(f, "{{unknown}}"),
             TypeView::NumericUVar(uvar) => write!(f, "NU?#{}", uvar.id().unwrap()),
             TypeView::Tuple(items) => {
@@ -238,6 +285,36 @@ impl Display for Type {
     }
 }
 
+/// Structural equality of types, following resolved unification variables.
+///
+/// Unresolved unification variables are only equal to themselves.
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.view(), other.view()) {
+            (TypeView::Unknown, TypeView::Unknown) => true,
+            (TypeView::UVar(uv1), TypeView::UVar(uv2))
+            | (TypeView::NumericUVar(uv1), TypeView::NumericUVar(uv2)) => uv1.same(&uv2),
+            (
+                TypeView::Var(tv1) | TypeView::NamedVar(tv1, _),
+                TypeView::Var(tv2) | TypeView::NamedVar(tv2, _),
+            ) => tv1 == tv2,
+            (TypeView::Tuple(items1), TypeView::Tuple(items2)) => items1 == items2,
+            (TypeView::Array(s1, tp1), TypeView::Array(s2, tp2)) => s1 == s2 && tp1 == tp2,
+            (TypeView::Fun(items1, ret1), TypeView::Fun(items2, ret2)) => {
+                items1 == items2 && ret1 == ret2
+            }
+            (TypeView::Ptr(tp1), TypeView::Ptr(tp2))
+            | (TypeView::MutPtr(tp1), TypeView::MutPtr(tp2))
+            | (TypeView::Slice(tp1), TypeView::Slice(tp2))
+            | (TypeView::MutSlice(tp1), TypeView::MutSlice(tp2)) => tp1 == tp2,
+            (TypeView::TypeApp(tv1, _, items1), TypeView::TypeApp(tv2, _, items2)) => {
+                tv1 == tv2 && items1 == items2
+            }
+            _ => false,
+        }
+    }
+}
+
 /// Unify two types, coercing `act_tp` to `exp_tp` if needed.
 ///
 /// In terms of subtyping relation, `act_tp <: exp_tp` must be satisfied.
@@ -282,10 +359,13 @@ pub fn unify(exp_tp: &Type, act_tp: &Type) -> bool {
 
         (TypeView::Array(s1, tp1), TypeView::Array(s2, tp2)) => s1 == s2 && unify(&tp1, &tp2),
 
-        (TypeView::Tuple(items1), TypeView::Tuple(items2)) => items1
-            .iter()
-            .zip(items2.iter())
-            .all(|(it1, it2)| unify(it1, it2)),
+        (TypeView::Tuple(items1), TypeView::Tuple(items2)) => {
+            items1.len() == items2.len()
+                && items1
+                    .iter()
+                    .zip(items2.iter())
+                    .all(|(it1, it2)| unify(it1, it2))
+        }
 
         (TypeView::NumericUVar(uvar), TypeView::Var(tv) | TypeView::NamedVar(tv, _)) => {
             if !uvar.occurs(&act_tp) && tv.is_numeric() {
@@ -310,9 +390,14 @@ pub fn unify(exp_tp: &Type, act_tp: &Type) -> bool {
         | (TypeView::Ptr(tp1), TypeView::MutPtr(tp2))
         | (TypeView::MutPtr(tp1), TypeView::MutPtr(tp2)) => unify(&*tp1, &*tp2),
 
+        // same for slices
+        (TypeView::Slice(tp1), TypeView::Slice(tp2))
+        | (TypeView::Slice(tp1), TypeView::MutSlice(tp2))
+        | (TypeView::MutSlice(tp1), TypeView::MutSlice(tp2)) => unify(&*tp1, &*tp2),
+
         (TypeView::Fun(items1, ret1), TypeView::Fun(items2, ret2)) => {
             // use mutable ret here to unify as much as possible
-            let mut ret = items1.len() != items2.len();
+            let mut ret = items1.len() == items2.len();
             if !unify(&ret1, &ret2) {
                 ret = false;
             };
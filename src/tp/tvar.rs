@@ -1,8 +1,13 @@
-use std::{hash::Hash, num::NonZeroUsize};
+use std::{
+    hash::Hash,
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use crate::{mir, symtable::layout::Type, tp::BUILTIN_TYPES};
 
-static mut COUNTER: usize = 64;
+/// Last used id, smaller ids are reserved.
+static COUNTER: AtomicUsize = AtomicUsize::new(64);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TVarKind {
@@ -33,10 +38,8 @@ impl Hash for TVar {
 impl TVar {
     /// Create a fresh type variable.
     pub(crate) fn new(kind: TVarKind) -> Self {
-        unsafe {
-            COUNTER += 1;
-            Self { id: COUNTER, kind }
-        }
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+        Self { id, kind }
     }
 
     /// Returns the kind of type variable.
@@ -50,8 +53,11 @@ impl TVar {
     }
 
     /// Check if type variable represents numeric type.
+    ///
+    /// Only integers are numeric, `char` isn't one even though
+    /// it's represented as a number.
     pub(crate) fn is_numeric(&self) -> bool {
-        self.id > 2 && self.id < 32
+        self.id > 2 && self.id < 13
     }
 
     /// Check if type variable represents the never type.
@@ -74,6 +80,7 @@ impl TVar {
             "i32" => 10,
             "i64" => 11,
             "isize" => 12,
+            "char" => 13,
             _ => panic!("not a builtin name: {}", name),
         };
         TVar {
@@ -87,9 +94,10 @@ impl TVar {
     }
 
     pub(crate) fn builtin_size(&self) -> Option<u32> {
-        let size = if self.id < 13 {
+        let size = if self.id < BUILTIN_TYPES.len() {
             match BUILTIN_TYPES[self.id] {
-                "never" => 42,
+                // there are no values of it
+                "never" => 0,
                 "bool" => 1,
                 "order" => 1,
                 "u8" => 1,
@@ -102,6 +110,8 @@ impl TVar {
                 "i32" => 4,
                 "i64" => 8,
                 "isize" => 8,
+                // a unicode scalar value
+                "char" => 4,
                 _ => return None,
             }
         } else {
@@ -110,12 +120,25 @@ impl TVar {
         Some(size)
     }
 
+    /// Returns the range of values of a builtin integer type.
+    pub(crate) fn int_range(&self) -> Option<(i128, i128)> {
+        if !self.is_numeric() {
+            return None;
+        }
+        let bits = self.builtin_size()? * 8;
+        if BUILTIN_TYPES[self.id].starts_with('i') {
+            Some((-(1 << (bits - 1)), (1 << (bits - 1)) - 1))
+        } else {
+            Some((0, (1 << bits) - 1))
+        }
+    }
+
     pub fn builtin_as_primitive(&self) -> Option<Type> {
-        let tp = if self.id < 13 {
+        let tp = if self.id < BUILTIN_TYPES.len() {
             match BUILTIN_TYPES[self.id] {
-                "never" => todo!(),
-                "bool" => todo!(),
-                "order" => todo!(),
+                "never" => Type::Tu8,
+                "bool" => Type::Tu8,
+                "order" => Type::Tu8,
                 "u8" => Type::Tu8,
                 "u16" => Type::Tu16,
                 "u32" => Type::Tu32,
@@ -126,6 +149,7 @@ impl TVar {
                 "i32" => Type::Ti32,
                 "i64" => Type::Ti64,
                 "isize" => Type::Tisize,
+                "char" => Type::Tu32,
                 _ => return None,
             }
         } else {
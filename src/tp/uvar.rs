@@ -14,16 +14,13 @@ enum UVarData {
     Resolved(Type),
 }
 
-static mut COUNTER: usize = 64;
-
 impl UVar {
     /// Create a fresh unification variable.
-    pub fn new() -> Self {
-        unsafe {
-            COUNTER += 1;
-            let uvar = Rc::new(RefCell::new(UVarData::Unresolved(COUNTER)));
-            Self(uvar)
-        }
+    ///
+    /// Use [TypeCtx](super::TypeCtx) to get unique ids.
+    pub(super) fn new(id: usize) -> Self {
+        let uvar = Rc::new(RefCell::new(UVarData::Unresolved(id)));
+        Self(uvar)
     }
 
     /// Returns id of unresolved unification variable.
@@ -59,6 +56,11 @@ impl UVar {
         }
     }
 
+    /// Check if both unification variables have the same representative.
+    pub fn same(&self, other: &UVar) -> bool {
+        Rc::ptr_eq(&self.find().0, &other.find().0)
+    }
+
     /// Resolve unification variable to a given type.
     ///
     /// Panics if variable was already resolved or isn't a representative.
@@ -88,7 +90,11 @@ impl UVar {
             TypeView::Fun(args, ret) => {
                 args.iter().any(|arg| self.occurs(arg)) || self.occurs(&ret)
             }
-            TypeView::Ptr(tp) | TypeView::MutPtr(tp) | TypeView::Array(_, tp) => self.occurs(&tp),
+            TypeView::Ptr(tp)
+            | TypeView::MutPtr(tp)
+            | TypeView::Slice(tp)
+            | TypeView::MutSlice(tp)
+            | TypeView::Array(_, tp) => self.occurs(&tp),
             TypeView::Tuple(items) => items.iter().any(|item| self.occurs(item)),
             TypeView::Unknown => false,
             TypeView::TypeApp(_, _, items) => items.iter().any(|item| self.occurs(item)),
@@ -8,7 +8,7 @@ pub struct Program {
     pub sym_table: SymTable,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Func {
     pub id: NodeID,
     pub name: String,
@@ -19,10 +19,10 @@ pub struct Func {
 
 // ==== Expr ===================================================================
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Expr {
     NumLit(usize, Type),
-    StringLit(String, Type),
+    StringLit(Vec<u8>, Type),
     LocalVar {
         name: String,
         tp: Type,
@@ -30,6 +30,8 @@ pub enum Expr {
     GlobalVar {
         id: NodeID,
         tp: Type,
+        /// Type arguments of a generic function, in order of its parameters.
+        type_args: Vec<Type>,
     },
     Tuple(Vec<Expr>, Type),
     FunCall {
@@ -44,6 +46,19 @@ pub enum Expr {
         struct_tp: Type,
         field_tp: Type,
     },
+    /// Tag of an enum value, which is the index of its constructor.
+    EnumTag {
+        object: Box<Expr>,
+        enum_tp: Type,
+    },
+    /// Field of an enum value built with given constructor.
+    EnumField {
+        object: Box<Expr>,
+        enum_tp: Type,
+        cons: usize,
+        field_id: usize,
+        field_tp: Type,
+    },
     Block {
         exprs: Vec<Expr>,
         last_expr: Box<Expr>,
@@ -87,18 +102,37 @@ pub enum Expr {
         expr: Box<Expr>,
         in_tp: Type,
     },
+    /// Conversion of a scalar value to another scalar type.
+    Cast {
+        expr: Box<Expr>,
+        from: Type,
+        to: Type,
+    },
     Error,
-    Char(u8),
     ArrayInitRepeat(Box<Expr>, usize, Type),
     ArrayInitExact(Vec<Expr>, Type),
+    /// Slice of the array behind a pointer, with the length of the array.
+    ArrayToSlice {
+        ptr: Box<Expr>,
+        len: usize,
+        tp: Type,
+    },
     While {
         pred: Box<Expr>,
         block: Box<Expr>,
     },
+    Loop {
+        body: Box<Expr>,
+        tp: Type,
+    },
+    Break(Box<Expr>),
+    Continue,
     IndexAccess {
         arr: Box<Expr>,
         index: Box<Expr>,
+        arr_tp: Type,
         tp: Type,
     },
-    Builtin(String, Vec<Expr>),
+    /// Builtin applied to arguments of given types.
+    Builtin(String, Vec<Expr>, Vec<Type>),
 }
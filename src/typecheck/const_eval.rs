@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    common::NodeID,
+    error::context::Context,
+    resolve::ast::{Const, ExprData, ExprNode, SymRef},
+    symtable::{SymKind, SymTable},
+    tp::{TVar, Type, TypeView},
+    typecheck::error,
+};
+
+/// Evaluate constants and initializers of statics,
+/// and record their values in the symbol table.
+///
+/// Statics can't be read by the initializers, their values may change.
+/// Only constants whose initializers typechecked are given,
+/// references to the other ones evaluate to nothing without further errors.
+pub(crate) fn eval_consts(ctx: &mut Context, sym_table: &mut SymTable, consts: Vec<Const>) {
+    let consts: HashMap<NodeID, Const> = consts.into_iter().map(|c| (c.id, c)).collect();
+    let mut ids: Vec<NodeID> = consts.keys().copied().collect();
+    ids.sort();
+    let mut eval = Eval {
+        ctx,
+        sym_table,
+        consts: &consts,
+        values: HashMap::new(),
+        active: HashSet::new(),
+    };
+    for id in ids {
+        eval.eval_const(id);
+    }
+    let values = eval.values;
+    for (id, value) in values {
+        let Some(value) = value else {
+            continue;
+        };
+        let (min, max) = const_range(&consts[&id].tp).unwrap();
+        let mask = (max - min) as u64;
+        sym_table.set_const_value(id, (value as u64 & mask) as usize);
+    }
+}
+
+/// Returns the range of values a constant of given type can hold,
+/// or `None` if constants can't have this type.
+pub(crate) fn const_range(tp: &Type) -> Option<(i128, i128)> {
+    match tp.view() {
+        TypeView::NamedVar(tvar, _) | TypeView::Var(tvar) => {
+            if tvar == TVar::of_builtin("bool") {
+                Some((0, 1))
+            } else {
+                tvar.int_range()
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Name of the kind of an item with a value computed during compilation.
+pub(crate) fn item_kind(sym_table: &SymTable, id: NodeID) -> &'static str {
+    match sym_table.find_sym_info(id).kind {
+        SymKind::Static { .. } => "static",
+        _ => "constant",
+    }
+}
+
+struct Eval<'a> {
+    ctx: &'a mut Context,
+    sym_table: &'a SymTable,
+    consts: &'a HashMap<NodeID, Const>,
+    values: HashMap<NodeID, Option<i128>>,
+    /// Constants being evaluated, to detect cycles.
+    active: HashSet<NodeID>,
+}
+
+impl Eval<'_> {
+    fn eval_const(&mut self, id: NodeID) -> Option<i128> {
+        if let Some(value) = self.values.get(&id) {
+            return *value;
+        }
+        let c = self.consts.get(&id)?;
+        if !self.active.insert(id) {
+            self.ctx.report(error::cyclic_const(&c.pos, c.name.clone()));
+            return None;
+        }
+        let value = self.eval(&c.expr).filter(|value| {
+            let (min, max) = const_range(&c.tp).unwrap();
+            let fits = min <= *value && *value <= max;
+            if !fits {
+                let what = item_kind(self.sym_table, c.id);
+                self.ctx.report(error::const_overflow(
+                    &c.expr.pos,
+                    what,
+                    *value,
+                    c.tp.clone(),
+                ));
+            }
+            fits
+        });
+        self.active.remove(&id);
+        self.values.insert(id, value);
+        value
+    }
+
+    /// Evaluate an expression using exact arithmetic.
+    ///
+    /// Like at runtime, only the taken branch of a conditional is evaluated.
+    fn eval(&mut self, expr: &ExprNode) -> Option<i128> {
+        match &expr.data {
+            ExprData::NumLit(n, _) => Some(*n as i128),
+            ExprData::BoolLit(b) => Some(*b as i128),
+            ExprData::Char(c) => Some(*c as i128),
+            ExprData::Byte(b) => Some(*b as i128),
+            ExprData::Var(SymRef::Global(id))
+                if matches!(
+                    self.sym_table.find_sym_info(*id).kind,
+                    SymKind::Const { .. }
+                ) =>
+            {
+                self.eval_const(*id)
+            }
+            ExprData::Neg(expr) => Some(-self.eval(expr)?),
+            ExprData::Ascription(expr, _) => self.eval(expr),
+            ExprData::Block(exprs, last) if exprs.is_empty() => self.eval(last),
+            ExprData::If(pred, th, el) => match self.eval(pred)? {
+                0 => self.eval(el.as_ref()?),
+                _ => self.eval(th),
+            },
+            ExprData::Builtin(name, args) if args.len() == 2 => {
+                let lhs = self.eval(&args[0])?;
+                let rhs = self.eval(&args[1])?;
+                let value = match name.as_str() {
+                    "iadd" => lhs.checked_add(rhs),
+                    "isub" => lhs.checked_sub(rhs),
+                    "imul" => lhs.checked_mul(rhs),
+                    "idiv" if rhs == 0 => {
+                        self.ctx.report(error::const_div_by_zero(&expr.pos));
+                        return None;
+                    }
+                    "idiv" => lhs.checked_div(rhs),
+                    "lt" => Some((lhs < rhs) as i128),
+                    "le" => Some((lhs <= rhs) as i128),
+                    "eq" => Some((lhs == rhs) as i128),
+                    "ne" => Some((lhs != rhs) as i128),
+                    "gt" => Some((lhs > rhs) as i128),
+                    "ge" => Some((lhs >= rhs) as i128),
+                    _ => {
+                        self.ctx.report(error::not_constant(&expr.pos));
+                        return None;
+                    }
+                };
+                if value.is_none() {
+                    self.ctx.report(error::const_arith_overflow(&expr.pos));
+                }
+                value
+            }
+            _ => {
+                self.ctx.report(error::not_constant(&expr.pos));
+                None
+            }
+        }
+    }
+}
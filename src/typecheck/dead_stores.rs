@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use crate::{
+    common::Position,
+    error::context::Context,
+    resolve::ast::{ExprData, ExprNode, Func, PatternData, PatternNode, SymRef},
+    typecheck::error,
+};
+
+/// Warn about values assigned to mutable locals that are never read.
+///
+/// The analysis follows the evaluation order and is deliberately
+/// conservative, it can miss dead stores but never reports a live one:
+/// - a write is live if any path from it reaches a read,
+/// - borrowing a variable makes all its writes live,
+/// - inside a loop, writes to variables declared outside of it are live.
+pub(crate) fn check_func(ctx: &mut Context, func: &Func) {
+    let mut env = Env::default();
+    env.scopes.push(HashMap::new());
+    for arg in &func.args {
+        env.add_var(arg.name.clone());
+    }
+    env.visit(&func.body);
+    for store in env.stores {
+        if !store.is_read && !store.name.starts_with('_') {
+            ctx.report(error::dead_store(&store.pos, store.name));
+        }
+    }
+}
+
+#[derive(Default)]
+struct Env {
+    scopes: Vec<HashMap<String, usize>>,
+    vars: Vec<Var>,
+    stores: Vec<Store>,
+    /// Stores that may be the last write to each variable.
+    pending: HashMap<usize, Vec<usize>>,
+    loop_depth: usize,
+}
+
+struct Var {
+    /// Number of loops the variable is declared in.
+    loop_depth: usize,
+    is_borrowed: bool,
+}
+
+struct Store {
+    name: String,
+    pos: Position,
+    is_read: bool,
+}
+
+impl Env {
+    fn add_var(&mut self, name: String) -> usize {
+        let id = self.vars.len();
+        self.vars.push(Var {
+            loop_depth: self.loop_depth,
+            is_borrowed: false,
+        });
+        self.scopes.last_mut().unwrap().insert(name, id);
+        id
+    }
+
+    fn find_var(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn read(&mut self, id: usize) {
+        for store in self.pending.remove(&id).unwrap_or_default() {
+            self.stores[store].is_read = true;
+        }
+    }
+
+    /// Record a store, replacing the pending ones.
+    fn write(&mut self, id: usize, name: String, pos: &Position) {
+        let var = &self.vars[id];
+        // The value may be read in a later iteration or through a pointer.
+        let is_read = var.is_borrowed || var.loop_depth < self.loop_depth;
+        self.pending.insert(id, vec![self.stores.len()]);
+        self.stores.push(Store {
+            name,
+            pos: pos.clone(),
+            is_read,
+        });
+    }
+
+    /// Mark all the pending stores as read.
+    fn read_all(&mut self) {
+        for (_, stores) in self.pending.drain() {
+            for store in stores {
+                self.stores[store].is_read = true;
+            }
+        }
+    }
+
+    fn borrow(&mut self, place: &ExprNode) {
+        match &place.data {
+            ExprData::Var(SymRef::Local(name)) => {
+                if let Some(id) = self.find_var(name) {
+                    self.read(id);
+                    self.vars[id].is_borrowed = true;
+                }
+            }
+            ExprData::FieldAccess(expr, _)
+            | ExprData::TupleField(expr, _)
+            | ExprData::IndexAccess(expr, _) => self.borrow(expr),
+            _ => (),
+        }
+    }
+
+    /// Visit alternative branches starting from the same state,
+    /// a store is pending after them if it's pending after any of them.
+    fn visit_branches<'a>(
+        &mut self,
+        branches: impl Iterator<Item = (Option<&'a PatternNode>, &'a ExprNode)>,
+    ) {
+        let start = self.pending.clone();
+        let mut merged: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (pattern, expr) in branches {
+            self.pending = start.clone();
+            self.scopes.push(HashMap::new());
+            if let Some(pattern) = pattern {
+                self.bind(pattern);
+            }
+            self.visit(expr);
+            self.scopes.pop();
+            for (id, stores) in self.pending.drain() {
+                let entry = merged.entry(id).or_default();
+                for store in stores {
+                    if !entry.contains(&store) {
+                        entry.push(store);
+                    }
+                }
+            }
+        }
+        self.pending = merged;
+    }
+
+    fn bind(&mut self, pattern: &PatternNode) {
+        match &pattern.data {
+            PatternData::Var(name) => {
+                self.add_var(name.clone());
+            }
+            PatternData::Tuple(pats) | PatternData::TupleCons(_, pats) => {
+                pats.iter().for_each(|pat| self.bind(pat))
+            }
+            PatternData::Error | PatternData::Wildcard | PatternData::Number(_) => (),
+        }
+    }
+
+    fn visit(&mut self, expr: &ExprNode) {
+        match &expr.data {
+            ExprData::Var(SymRef::Local(name)) => {
+                if let Some(id) = self.find_var(name) {
+                    self.read(id);
+                }
+            }
+            ExprData::Var(SymRef::Global(_))
+            | ExprData::NumLit(_, _)
+            | ExprData::BoolLit(_)
+            | ExprData::String(_)
+            | ExprData::ByteString(_)
+            | ExprData::Char(_)
+            | ExprData::Byte(_)
+            | ExprData::Error
+            | ExprData::Continue => (),
+            ExprData::Tuple(exprs)
+            | ExprData::ArrayInitExact(exprs)
+            | ExprData::Builtin(_, exprs) => exprs.iter().for_each(|e| self.visit(e)),
+            ExprData::FunCall(expr, args) | ExprData::MethodCall(expr, _, args) => {
+                self.visit(expr);
+                args.iter().for_each(|e| self.visit(e));
+            }
+            ExprData::StructCons(_, fields) => fields.values().for_each(|e| self.visit(e)),
+            ExprData::FieldAccess(expr, _)
+            | ExprData::TupleField(expr, _)
+            | ExprData::Return(expr)
+            | ExprData::Break(expr)
+            | ExprData::Deref(expr)
+            | ExprData::Neg(expr)
+            | ExprData::Cast(expr, _)
+            | ExprData::Ascription(expr, _)
+            | ExprData::ArrayInitRepeat(expr, _) => self.visit(expr),
+            ExprData::IndexAccess(e1, e2) => {
+                self.visit(e1);
+                self.visit(e2);
+            }
+            ExprData::Ref(expr) | ExprData::RefMut(expr) => {
+                self.visit(expr);
+                self.borrow(expr);
+            }
+            ExprData::Block(exprs, last) => {
+                self.scopes.push(HashMap::new());
+                exprs.iter().for_each(|e| self.visit(e));
+                self.visit(last);
+                self.scopes.pop();
+            }
+            ExprData::Let {
+                name, is_mut, expr, ..
+            } => {
+                self.visit(expr);
+                let id = self.add_var(name.clone());
+                if *is_mut {
+                    self.write(id, name.clone(), &expr.pos);
+                }
+            }
+            ExprData::Assign(lval, rval) => {
+                self.visit(rval);
+                match &lval.data {
+                    ExprData::Var(SymRef::Local(name)) => match self.find_var(name) {
+                        Some(id) => self.write(id, name.clone(), &expr.pos),
+                        None => (),
+                    },
+                    // Assignment to a part of a variable keeps the rest of it.
+                    _ => self.visit(lval),
+                }
+            }
+            ExprData::If(pred, th, el) => {
+                self.visit(pred);
+                // a missing else branch is a branch that does nothing
+                let nothing = ExprNode {
+                    data: ExprData::Tuple(vec![]),
+                    pos: pred.pos.clone(),
+                };
+                let el = el.as_deref().unwrap_or(&nothing);
+                self.visit_branches([(None, &**th), (None, el)].into_iter());
+            }
+            ExprData::Match(expr, clauses) => {
+                self.visit(expr);
+                self.visit_branches(clauses.iter().map(|cl| (Some(&cl.pattern), &cl.expr)));
+            }
+            ExprData::While(pred, block) => {
+                self.read_all();
+                self.loop_depth += 1;
+                self.visit(pred);
+                self.visit(block);
+                self.loop_depth -= 1;
+            }
+            ExprData::Loop(body) => {
+                self.read_all();
+                self.loop_depth += 1;
+                self.visit(body);
+                self.loop_depth -= 1;
+            }
+        }
+    }
+}
@@ -2,51 +2,167 @@ use std::{collections::BTreeMap, ops::ControlFlow};
 
 use crate::{
     common::Position,
-    error::{InternalError, context::Context},
-    tp::{Type, TypeView},
+    error::{InternalError, Lint, context::Context},
+    tp::{TVar, Type, TypeCtx, TypeView},
     typecheck::error,
 };
 
+/// Local variable in scope.
+#[derive(Debug)]
+struct Local {
+    is_mut: bool,
+    tp: Type,
+    /// Where the variable is declared, `None` if it doesn't have to be read.
+    pos: Option<Position>,
+    is_read: bool,
+}
+
 #[derive(Debug)]
 pub struct Env {
+    tcx: TypeCtx,
     expected_ret: Type,
-    scopes: Vec<BTreeMap<String, (bool, Type)>>,
+    /// Position of the return type annotation.
+    ret_pos: Option<Position>,
+    /// Source of the type expected of the next checked expression, set
+    /// when it's written down rather than inferred.
+    expected_from: Option<Position>,
+    scopes: Vec<BTreeMap<String, Local>>,
+    /// Variables that went out of scope without being read.
+    unread: Vec<(String, Position)>,
     uvars: Vec<(Type, Position)>,
+    /// Types of number literals and negations, which get the default type
+    /// if nothing else decides it.
+    numerics: Vec<(Type, Position)>,
+    /// Casts to check once all types are known: source and target type,
+    /// and value of the operand if it's a literal.
+    casts: Vec<(Type, Type, Option<usize>, Position)>,
+    /// Types of negated values, which have to be signed integers.
+    negs: Vec<(Type, Position)>,
+    /// Number literals, to check that they fit their type once it's known:
+    /// value, type and whether the literal is negated.
+    lits: Vec<(usize, Type, bool, Position)>,
+    /// Loops enclosing the expression being checked, innermost last:
+    /// type of the values they yield and whether there is a `break` in them.
+    loops: Vec<(Type, bool)>,
 }
 impl Env {
-    pub(crate) fn new(expected_ret: Type) -> Self {
+    pub(crate) fn new(expected_ret: Type, ret_pos: Option<Position>) -> Self {
         Self {
+            tcx: TypeCtx::new(),
             expected_ret,
+            ret_pos,
+            expected_from: None,
             scopes: vec![BTreeMap::new()],
+            unread: vec![],
             uvars: vec![],
+            numerics: vec![],
+            casts: vec![],
+            negs: vec![],
+            lits: vec![],
+            loops: vec![],
         }
     }
 
+    /// Allocator for unification variables that may stay unresolved,
+    /// unlike the ones from [Env::fresh_uvar].
+    pub(crate) fn tcx(&mut self) -> &mut TypeCtx {
+        &mut self.tcx
+    }
+
     pub fn fresh_uvar(&mut self, pos: &Position) -> Type {
-        let tp = Type::fresh_uvar();
+        let tp = self.tcx.fresh_uvar();
         self.uvars.push((tp.clone(), pos.clone()));
         tp
     }
 
-    pub(crate) fn add_var(&mut self, name: String, is_mut: bool, tp: Type) {
-        self.scopes
+    /// Add a variable to the current scope.
+    ///
+    /// Unless `pos` is `None`, it's reported if it's never read.
+    pub(crate) fn add_var(&mut self, name: String, is_mut: bool, tp: Type, pos: Option<&Position>) {
+        let local = Local {
+            is_mut,
+            tp,
+            pos: pos.cloned(),
+            is_read: false,
+        };
+        let shadowed = self
+            .scopes
             .last_mut()
             .expect("there should be at least one scope")
-            .insert(name, (is_mut, tp));
+            .insert(name.clone(), local);
+        if let Some(local) = shadowed {
+            self.check_read(name, local);
+        }
+    }
+
+    /// Mark a variable as read.
+    pub(crate) fn mark_read(&mut self, name: &str) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(local) = scope.get_mut(name) {
+                local.is_read = true;
+                return;
+            }
+        }
+    }
+
+    fn check_read(&mut self, name: String, local: Local) {
+        if let Some(pos) = local.pos
+            && !local.is_read
+            && !name.starts_with('_')
+        {
+            self.unread.push((name, pos));
+        }
     }
 
-    pub(crate) fn finish(self, ctx: &mut Context) -> Result<(), InternalError> {
+    pub(crate) fn finish(mut self, ctx: &mut Context) -> Result<(), InternalError> {
+        while !self.scopes.is_empty() {
+            self.leave_scope();
+        }
+        if ctx.lint_enabled(Lint::UnusedVariables) {
+            for (name, pos) in self.unread {
+                ctx.report(error::unused_variable(&pos, name));
+            }
+        }
+        // All unification is done by now, so literals whose type is still
+        // unknown get the default one, before anything else looks at them.
+        for (tp, pos) in self.numerics {
+            if let TypeView::NumericUVar(uvar) = tp.view() {
+                // Literals nothing else constrains default to a signed type,
+                // so they satisfy the requirements of negations checked later.
+                uvar.resolve(Type::builtin("i32"));
+                if ctx.lint_enabled(Lint::DefaultedLiterals) {
+                    ctx.report(error::defaulted_literal(&pos));
+                }
+            }
+        }
         // TODO: also check inside compound types (or perform smart occurs check)
         for (tp, pos) in self.uvars {
             check_resolved(ctx, tp, &pos);
         }
+        for (from, to, lit, pos) in self.casts {
+            if !is_scalar(&from) || !is_scalar(&to) {
+                ctx.report(error::invalid_cast(&pos, from, to));
+            } else if !valid_char_cast(&from, &to) {
+                ctx.report(error::invalid_char_cast(&pos, from, to));
+            } else if ctx.lint_enabled(Lint::NarrowingCast) {
+                check_cast(ctx, from, to, lit, &pos);
+            }
+        }
+        for (tp, pos) in self.negs {
+            if let Some((0, _)) = int_range(&tp) {
+                ctx.report(error::negate_unsigned(&pos, tp));
+            }
+        }
+        for (value, tp, negated, pos) in self.lits {
+            check_lit(ctx, value, tp, negated, &pos);
+        }
         Ok(())
     }
 
     pub(crate) fn lookup(&self, name: &String) -> (bool, &Type) {
         for scope in self.scopes.iter().rev() {
-            if let Some((is_mut, tp)) = scope.get(name) {
-                return (*is_mut, tp);
+            if let Some(local) = scope.get(name) {
+                return (local.is_mut, &local.tp);
             }
         }
         unreachable!()
@@ -57,30 +173,171 @@ impl Env {
     }
 
     pub(crate) fn leave_scope(&mut self) {
-        self.scopes.pop();
+        let scope = self.scopes.pop().expect("there should be a scope to leave");
+        for (name, local) in scope {
+            self.check_read(name, local);
+        }
+    }
+
+    pub(crate) fn enter_loop(&mut self, tp: Type) {
+        self.loops.push((tp, false))
+    }
+
+    /// Returns whether the loop is left with `break`.
+    pub(crate) fn leave_loop(&mut self) -> bool {
+        let (_, has_break) = self.loops.pop().expect("there should be a loop to leave");
+        has_break
+    }
+
+    /// Whether `break` and `continue` are allowed here.
+    pub(crate) fn in_loop(&self) -> bool {
+        !self.loops.is_empty()
+    }
+
+    /// Record a `break` of the innermost loop.
+    ///
+    /// Returns type of the value it should yield, or `None` outside of loops.
+    pub(crate) fn add_break(&mut self) -> Option<Type> {
+        let (tp, has_break) = self.loops.last_mut()?;
+        *has_break = true;
+        Some(tp.clone())
     }
 
     pub(crate) fn expected_ret(&self) -> Type {
         self.expected_ret.clone()
     }
 
+    pub(crate) fn ret_pos(&self) -> Option<Position> {
+        self.ret_pos.clone()
+    }
+
+    /// Record where the type expected of the next checked expression
+    /// comes from, to point at it when the expression doesn't match.
+    pub(crate) fn expect_from(&mut self, pos: Option<Position>) {
+        self.expected_from = pos;
+    }
+
+    /// Source of the expected type, cleared so it isn't used for
+    /// subexpressions.
+    pub(crate) fn take_expected_from(&mut self) -> Option<Position> {
+        self.expected_from.take()
+    }
+
+    /// Remember a cast, to check it once types are resolved.
+    pub(crate) fn add_cast(&mut self, from: Type, to: Type, lit: Option<usize>, pos: &Position) {
+        self.casts.push((from, to, lit, pos.clone()));
+    }
+
+    /// Remember a negation, to check that the type is signed once it's resolved.
+    pub(crate) fn add_neg(&mut self, tp: Type, pos: &Position) {
+        self.negs.push((tp, pos.clone()));
+    }
+
+    /// Remember a number literal, to check that it fits its type.
+    pub(crate) fn add_lit(&mut self, value: usize, tp: Type, pos: &Position) {
+        self.lits.push((value, tp, false, pos.clone()));
+    }
+
+    /// Mark the literal added last as negated, so it's checked
+    /// against the negative part of the range of its type.
+    pub(crate) fn negate_last_lit(&mut self) {
+        let (_, _, negated, _) = self.lits.last_mut().expect("there should be a literal");
+        *negated = true;
+    }
+
     pub(crate) fn numeric_uvar(&mut self, pos: &Position) -> Type {
-        let tp = Type::numeric_uvar();
-        self.uvars.push((tp.clone(), pos.clone()));
+        let tp = self.tcx.numeric_uvar();
+        self.numerics.push((tp.clone(), pos.clone()));
         tp
     }
 }
 
+/// Warn about a cast to an integer type that cannot hold the operand.
+///
+/// Literals are checked against the range of the target type,
+/// other operands only against the range of their type.
+fn check_cast(ctx: &mut Context, from: Type, to: Type, lit: Option<usize>, pos: &Position) {
+    let Some((min, max)) = int_range(&to) else {
+        return;
+    };
+    let narrowing = match lit {
+        Some(n) => n as i128 > max,
+        None => match int_range(&from) {
+            Some((from_min, from_max)) => from_max - from_min > max - min,
+            None => false,
+        },
+    };
+    if narrowing {
+        ctx.report(error::narrowing_cast(pos, from, to));
+    }
+}
+
+/// Report a literal that doesn't fit its integer type.
+///
+/// Negated literals of unsigned types are reported as invalid negations.
+fn check_lit(ctx: &mut Context, value: usize, tp: Type, negated: bool, pos: &Position) {
+    let Some((min, max)) = int_range(&tp) else {
+        return;
+    };
+    let (value, fits) = match negated {
+        true => (-(value as i128), min == 0 || -(value as i128) >= min),
+        false => (value as i128, value as i128 <= max),
+    };
+    if !fits {
+        ctx.report(error::literal_out_of_range(pos, value, tp, min, max));
+    }
+}
+
+/// Only integers, `bool`, `char` and pointers can be cast.
+///
+/// Types that are still unknown were reported already.
+fn is_scalar(tp: &Type) -> bool {
+    match tp.view() {
+        TypeView::NamedVar(tvar, _) | TypeView::Var(tvar) => tvar.builtin_as_primitive().is_some(),
+        TypeView::Ptr(_)
+        | TypeView::MutPtr(_)
+        | TypeView::Fun(_, _)
+        | TypeView::UVar(_)
+        | TypeView::NumericUVar(_)
+        | TypeView::Unknown => true,
+        TypeView::Tuple(_)
+        | TypeView::Array(_, _)
+        | TypeView::Slice(_)
+        | TypeView::MutSlice(_)
+        | TypeView::TypeApp(_, _, _) => false,
+    }
+}
+
+/// `char` can only be cast to and from u32, the type of its code.
+///
+/// Types that are still unknown were reported already.
+fn valid_char_cast(from: &Type, to: &Type) -> bool {
+    let is = |tp: &Type, name| matches!(tp.view(), TypeView::NamedVar(tvar, _) if tvar == TVar::of_builtin(name));
+    let other = match (is(from, "char"), is(to, "char")) {
+        (true, false) => to,
+        (false, true) => from,
+        _ => return true,
+    };
+    is(other, "u32")
+        || matches!(
+            other.view(),
+            TypeView::UVar(_) | TypeView::NumericUVar(_) | TypeView::Unknown
+        )
+}
+
+fn int_range(tp: &Type) -> Option<(i128, i128)> {
+    match tp.view() {
+        TypeView::NamedVar(tvar, _) | TypeView::Var(tvar) => tvar.int_range(),
+        _ => None,
+    }
+}
+
 fn check_resolved(ctx: &mut Context, tp: Type, pos: &Position) {
     match tp.view() {
-        TypeView::UVar(_) => {
+        // numeric ones were given the default type already
+        TypeView::UVar(_) | TypeView::NumericUVar(_) => {
             ctx.report(error::cannot_infer_type(pos));
         }
-        TypeView::NumericUVar(uvar) => {
-            println!("Resolving {:?} at {:?}", uvar, pos);
-            uvar.resolve(Type::builtin("i32"));
-            println!("Resolved? {:?}", uvar.try_resolved());
-        }
         TypeView::Unknown | TypeView::Var(_) | TypeView::NamedVar(_, _) => {}
         TypeView::Tuple(items) => {
             for it in items {
@@ -96,6 +353,7 @@ fn check_resolved(ctx: &mut Context, tp: Type, pos: &Position) {
         }
         TypeView::Ptr(tp) => check_resolved(ctx, *tp, pos),
         TypeView::MutPtr(tp) => check_resolved(ctx, *tp, pos),
+        TypeView::Slice(tp) | TypeView::MutSlice(tp) => check_resolved(ctx, *tp, pos),
         TypeView::TypeApp(_, _, items) => {
             for it in items {
                 check_resolved(ctx, it, pos);
@@ -1,3 +1,5 @@
+use colored::Color;
+
 use crate::{
     common::Position,
     error::diagnostic::{Diagnostic, Label},
@@ -10,15 +12,176 @@ pub(crate) fn type_mismatch(pos: &Position, exp: Type, got: Type) -> Diagnostic
     })))
 }
 
+/// Type mismatch with the expected type written down at `origin`.
+pub(crate) fn type_mismatch_from(
+    pos: &Position,
+    origin: Option<&Position>,
+    exp: Type,
+    got: Type,
+) -> Diagnostic {
+    with_origin(type_mismatch(pos, exp.clone(), got), origin, exp)
+}
+
+/// Add a label pointing at the annotation the expected type comes from.
+fn with_origin(diag: Diagnostic, origin: Option<&Position>, exp: Type) -> Diagnostic {
+    match origin {
+        Some(origin) => diag.with_label(
+            Label::new(origin)
+                .with_msg(Box::new(move || {
+                    format!("expected {} because of this", exp)
+                }))
+                .with_color(Color::Blue),
+        ),
+        None => diag,
+    }
+}
+
+/// Expression of type `()` used where a value of type `exp` is expected.
+pub(crate) fn unit_as_value(
+    pos: &Position,
+    origin: Option<&Position>,
+    what: &'static str,
+    exp: Type,
+) -> Diagnostic {
+    let diag = Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("{} evaluate to () and can't be used as a value here", what)
+    })));
+    with_origin(diag, origin, exp)
+}
+
+pub(crate) fn if_without_else(pos: &Position, tp: Type) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!(
+                "`if` used as a value of type {} must have an else branch",
+                tp
+            )
+        })))
+        .with_note("an `if` without `else` has type ()".into())
+}
+
 pub(crate) fn expected_mutable(pos: &Position) -> Diagnostic {
     Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
         format!("cannot assign to immutable variable")
     })))
 }
 
-pub(crate) fn not_a_function(pos: &Position) -> Diagnostic {
+pub(crate) fn immutable_slice(pos: &Position, tp: Type) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("cannot assign through a slice of type {}", tp)
+        })))
+        .with_note("use a mutable slice `[]mut T` to allow changes".into())
+}
+
+pub(crate) fn deref_fun(pos: &Position, tp: Type) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("cannot dereference a function pointer of type {}", tp)
+        })))
+        .with_note("call the function instead".into())
+}
+
+pub(crate) fn assign_through_ptr(pos: &Position, tp: Type) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("cannot assign through a `*` pointer of type {}", tp)
+        })))
+        .with_note("use `*mut` for pointers that are written through".into())
+}
+
+pub(crate) fn outside_loop(pos: &Position, keyword: &'static str) -> Diagnostic {
+    Diagnostic::error(pos).with_label(
+        Label::new(pos).with_msg(Box::new(move || format!("`{}` outside of a loop", keyword))),
+    )
+}
+
+pub(crate) fn unused_variable(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::warning(pos)
+        .with_label(
+            Label::new(pos)
+                .with_msg(Box::new(move || {
+                    format!("variable `{}` is never read", name)
+                }))
+                .with_color(Color::Yellow),
+        )
+        .with_note("enabled by `-W unused_variables`, prefix the name with `_` to allow it".into())
+}
+
+pub(crate) fn dead_store(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::warning(pos)
+        .with_label(
+            Label::new(pos)
+                .with_msg(Box::new(move || {
+                    format!("value assigned to `{}` is never read", name)
+                }))
+                .with_color(Color::Yellow),
+        )
+        .with_note("enabled by `-W dead_stores`".into())
+}
+
+pub(crate) fn unused_must_use(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::warning(pos)
+        .with_label(
+            Label::new(pos)
+                .with_msg(Box::new(move || {
+                    format!("result of `{}` is discarded", name)
+                }))
+                .with_color(Color::Yellow),
+        )
+        .with_note("the function is marked `@must_use`, bind the result to a `_`-prefixed variable to discard it".into())
+}
+
+pub(crate) fn unreachable_code(pos: &Position, cause: &Position) -> Diagnostic {
+    Diagnostic::warning(pos)
+        .with_label(
+            Label::new(pos)
+                .with_msg(Box::new(|| "unreachable expression".to_string()))
+                .with_color(Color::Yellow),
+        )
+        .with_label(
+            Label::new(cause)
+                .with_msg(Box::new(|| {
+                    "any code following this is unreachable".to_string()
+                }))
+                .with_color(Color::Yellow),
+        )
+        .with_note("enabled by `-W unreachable_code`".into())
+}
+
+pub(crate) fn negate_unsigned(pos: &Position, tp: Type) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("cannot negate a value of unsigned type {}", tp)
+    })))
+}
+
+pub(crate) fn literal_out_of_range(
+    pos: &Position,
+    value: i128,
+    tp: Type,
+    min: i128,
+    max: i128,
+) -> Diagnostic {
+    let note = format!("values of type {} range from {} to {}", tp, min, max);
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("literal {} doesn't fit in type {}", value, tp)
+        })))
+        .with_note(note)
+}
+
+pub(crate) fn not_a_function(pos: &Position, tp: Type) -> Diagnostic {
     Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
-        format!("it's not a function and cannot be called")
+        format!(
+            "value of type {} is not a function and cannot be called",
+            tp
+        )
+    })))
+}
+
+pub(crate) fn type_as_value(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("`{}` is a type, not a value", name)
     })))
 }
 
@@ -33,12 +196,31 @@ pub(crate) fn unexpected_argument(id: usize, pos: &Position) -> Diagnostic {
         .with_label(Label::new(pos).with_msg(Box::new(move || format!("unexpected arg #{}", id))))
 }
 
+pub(crate) fn bad_variadic_argument(pos: &Position, tp: Type) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!(
+                "value of type {} can't be passed as a variadic argument",
+                tp
+            )
+        })))
+        .with_note("only integers of at least 32 bits and pointers can be passed".into())
+}
+
 pub(crate) fn no_such_field(field_name: String, arg: Type, pos: &Position) -> Diagnostic {
     Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
         format!("no field named {} on type {}", field_name, arg)
     })))
 }
 
+pub(crate) fn assign_to_len(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(
+            Label::new(pos).with_msg(Box::new(move || format!("cannot assign to the length"))),
+        )
+        .with_note("lengths of arrays and slices are fixed".into())
+}
+
 pub(crate) fn missing_field(pos: &Position, f_name: String, f_type: Type) -> Diagnostic {
     Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
         format!("missing field `{}` of type {}", f_name, f_type)
@@ -74,8 +256,174 @@ pub(crate) fn not_yet_supported(pos: &Position) -> Diagnostic {
         .with_label(Label::new(pos).with_msg(Box::new(move || format!("not yet supported"))))
 }
 
+pub(crate) fn non_exhaustive_match(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("this match may not match a value")
+        })))
+        .with_note("add an arm matching the remaining values, like `_`".into())
+}
+
+pub(crate) fn tuple_pattern_mismatch(pos: &Position, tp: Type, len: usize) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!(
+            "expected a value of type {}, found a tuple of {} fields",
+            tp, len
+        )
+    })))
+}
+
+pub(crate) fn not_a_constructor(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("{} is not an enum constructor", name)
+    })))
+}
+
+pub(crate) fn constructor_arity(
+    pos: &Position,
+    name: String,
+    expected: usize,
+    found: usize,
+) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        let fields = if expected == 1 { "field" } else { "fields" };
+        format!(
+            "constructor {} has {} {}, but the pattern has {}",
+            name, expected, fields, found
+        )
+    })))
+}
+
 pub(crate) fn unsized_type(pos: &Position) -> Diagnostic {
     Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
         format!("this expression uses type of unknown size")
     })))
 }
+
+pub(crate) fn defaulted_literal(pos: &Position) -> Diagnostic {
+    Diagnostic::warning(pos)
+        .with_label(
+            Label::new(pos)
+                .with_msg(Box::new(move || {
+                    format!("type of this number is not constrained, defaulting to i32")
+                }))
+                .with_color(Color::Yellow),
+        )
+        .with_note("enabled by `-W defaulted_literals`, annotate the type to choose another".into())
+}
+
+pub(crate) fn invalid_cast(pos: &Position, from: Type, to: Type) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(
+            Label::new(pos).with_msg(Box::new(move || format!("cannot cast {} to {}", from, to))),
+        )
+        .with_note("only integers, bool, char and pointers can be cast".into())
+}
+
+pub(crate) fn invalid_char_cast(pos: &Position, from: Type, to: Type) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(
+            Label::new(pos).with_msg(Box::new(move || format!("cannot cast {} to {}", from, to))),
+        )
+        .with_note("char can only be cast to and from u32".into())
+}
+
+pub(crate) fn narrowing_cast(pos: &Position, from: Type, to: Type) -> Diagnostic {
+    Diagnostic::warning(pos)
+        .with_label(
+            Label::new(pos)
+                .with_msg(Box::new(move || {
+                    format!("cast from {} to {} may lose information", from, to)
+                }))
+                .with_color(Color::Yellow),
+        )
+        .with_note("enabled by `-W narrowing_cast`".into())
+}
+
+pub(crate) fn aliased_mut_ref(
+    mut_pos: &Position,
+    other_pos: &Position,
+    name: String,
+) -> Diagnostic {
+    let other_name = name.clone();
+    Diagnostic::error(mut_pos)
+        .with_label(Label::new(mut_pos).with_msg(Box::new(move || {
+            format!("mutable reference to `{}` is passed here", name)
+        })))
+        .with_label(
+            Label::new(other_pos)
+                .with_msg(Box::new(move || {
+                    format!("while `{}` is also borrowed by this argument", other_name)
+                }))
+                .with_color(Color::Yellow),
+        )
+        .with_note("a mutable reference cannot alias any other argument".into())
+}
+
+pub(crate) fn assign_to_const(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("cannot assign to constant `{}`", name)
+    })))
+}
+
+pub(crate) fn global_as_struct(pos: &Position, name: String, what: &'static str) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("`{}` is a {}, not a struct", name, what)
+    })))
+}
+
+pub(crate) fn const_type(pos: &Position, what: &'static str, tp: Type) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("{} cannot have type {}", what, tp)
+        })))
+        .with_note(format!("{}s must be integers or `bool`", what))
+}
+
+pub(crate) fn not_constant(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("expression cannot be evaluated at compile time")
+        })))
+        .with_note(
+            "constants may only use literals, other constants, arithmetic, comparisons and logical operators"
+                .into(),
+        )
+}
+
+pub(crate) fn const_div_by_zero(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos).with_label(
+        Label::new(pos).with_msg(Box::new(move || format!("division by zero in a constant"))),
+    )
+}
+
+pub(crate) fn const_overflow(
+    pos: &Position,
+    what: &'static str,
+    value: i128,
+    tp: Type,
+) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("value {} of the {} doesn't fit in type {}", value, what, tp)
+    })))
+}
+
+pub(crate) fn assign_to_static(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::error(pos)
+        .with_label(Label::new(pos).with_msg(Box::new(move || {
+            format!("cannot assign to immutable static `{}`", name)
+        })))
+        .with_note("declare it with `static mut` to allow changes".into())
+}
+
+pub(crate) fn cyclic_const(pos: &Position, name: String) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("constant `{}` depends on its own value", name)
+    })))
+}
+
+pub(crate) fn const_arith_overflow(pos: &Position) -> Diagnostic {
+    Diagnostic::error(pos).with_label(Label::new(pos).with_msg(Box::new(move || {
+        format!("arithmetic overflow in a constant")
+    })))
+}
@@ -1,20 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::error::context::Context;
 
 pub mod ast;
+mod const_eval;
+mod dead_stores;
 mod env;
 mod error;
+mod pretty;
 
-use crate::error::InternalError;
+use crate::common::{NodeID, Position};
+use crate::error::{InternalError, Lint};
 use crate::resolve::ast as in_a;
 use crate::symtable::{SymKind, SymTable, TypeKind};
-use crate::tp::{TVar, Type, TypeView, unify};
+use crate::tp::{TVar, Type, TypeCtx, TypeView, unify};
 use crate::typecheck::env::Env;
 use ast as out_a;
 
 pub fn translate(ctx: &mut Context, prog: in_a::Program) -> Result<out_a::Program, InternalError> {
-    let sym_table = prog.sym_table;
+    let mut sym_table = prog.sym_table;
+
+    let mut consts = vec![];
+    for c in prog.consts {
+        if let Some(c) = check_const(ctx, &sym_table, c)? {
+            consts.push(c);
+        }
+    }
+    const_eval::eval_consts(ctx, &mut sym_table, consts);
 
     let functions = prog
         .functions
@@ -29,22 +41,51 @@ pub fn translate(ctx: &mut Context, prog: in_a::Program) -> Result<out_a::Progra
     Ok(prog)
 }
 
+/// Typecheck the initializer of a constant or a static.
+///
+/// Returns the item back if it can be evaluated,
+/// that is if its type is valid and the initializer has no errors.
+fn check_const(
+    ctx: &mut Context,
+    sym_table: &SymTable,
+    c: in_a::Const,
+) -> Result<Option<in_a::Const>, InternalError> {
+    let err_count = ctx.err_count();
+    let valid_type = const_eval::const_range(&c.tp).is_some();
+    if !valid_type && !matches!(c.tp.view(), TypeView::Unknown) {
+        let what = const_eval::item_kind(sym_table, c.id);
+        ctx.report(error::const_type(&c.pos, what, c.tp.clone()));
+    }
+    let mut env = Env::new(c.tp.clone(), None);
+    check_expr(ctx, sym_table, &mut env, c.expr.clone(), &c.tp, false)?;
+    env.finish(ctx)?;
+    Ok((valid_type && ctx.err_count() == err_count).then_some(c))
+}
+
 fn tr_func(
     ctx: &mut Context,
     sym_table: &SymTable,
     func: in_a::Func,
 ) -> Result<out_a::Func, InternalError> {
-    let mut env = Env::new(func.ret_type.clone());
+    if ctx.lint_enabled(Lint::DeadStores) {
+        dead_stores::check_func(ctx, &func);
+    }
+
+    let mut env = Env::new(func.ret_type.clone(), func.ret_type_pos.clone());
+    let is_extern = sym_table.find_sym_info(func.id).is_extern;
 
     let args = func
         .args
         .into_iter()
         .map(|arg| {
-            env.add_var(arg.name.clone(), arg.is_mut, arg.tp.clone());
+            // Signatures of extern functions are fixed by the other side.
+            let pos = (!is_extern).then_some(&arg.pos);
+            env.add_var(arg.name.clone(), arg.is_mut, arg.tp.clone(), pos);
             Ok((arg.name, arg.is_mut, arg.tp))
         })
         .collect::<Result<_, InternalError>>()?;
 
+    env.expect_from(func.ret_type_pos);
     let body = check_expr(ctx, sym_table, &mut env, func.body, &func.ret_type, false)?;
 
     env.finish(ctx)?;
@@ -68,16 +109,29 @@ fn check_expr(
     exp_tp: &Type,
     exp_mut: bool,
 ) -> Result<out_a::Expr, InternalError> {
+    let origin = env.take_expected_from();
+    if !exp_mut && matches!(exp_tp.view(), TypeView::Slice(_) | TypeView::MutSlice(_)) {
+        return check_slice(ctx, sym_table, env, expr, exp_tp);
+    }
     let pos = &expr.pos;
     Ok(match expr.data {
         in_a::ExprData::Var(sym_ref) => match sym_ref {
             in_a::SymRef::Local(name) => {
+                // Writing to a variable doesn't count as reading it.
+                if !exp_mut {
+                    env.mark_read(&name);
+                }
                 let (is_mut, tp) = env.lookup(&name);
                 if exp_mut && !is_mut {
                     ctx.report(error::expected_mutable(pos));
                 }
                 if !unify(exp_tp, tp) {
-                    ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                    ctx.report(error::type_mismatch_from(
+                        pos,
+                        origin.as_ref(),
+                        exp_tp.clone(),
+                        tp.clone(),
+                    ));
                 }
                 out_a::Expr::LocalVar {
                     name,
@@ -94,76 +148,104 @@ fn check_expr(
                             .collect();
                         let tp = Type::fun(args.clone(), ret.clone()).substitute(&subst);
                         if !unify(exp_tp, &tp) {
-                            ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                            ctx.report(error::type_mismatch_from(
+                                pos,
+                                origin.as_ref(),
+                                exp_tp.clone(),
+                                tp.clone(),
+                            ));
                         }
                         out_a::Expr::GlobalVar {
                             id: node_id,
                             tp: tp.clone(),
+                            type_args: params.iter().map(|tv| subst[tv].clone()).collect(),
                         }
                     }
                     SymKind::EnumCons { id, args, parent } => {
-                        let sym_info = sym_table.find_sym_info(*parent);
-                        let (params, tvar, name) = match &sym_info.kind {
-                            SymKind::Func { params, args, ret } => todo!(),
-                            SymKind::Enum(tvar) => {
-                                let type_info = sym_table.find_type_info(*tvar);
-                                match &type_info.kind {
-                                    TypeKind::Enum {
-                                        params,
-                                        constructors,
-                                    } => (params, tvar, type_info.name.clone()),
-                                    _ => unreachable!("this is 100% a struct"),
-                                }
-                            }
-                            SymKind::EnumCons { id, args, parent } => todo!(),
-                            SymKind::Struct(tvar) => todo!(),
-                        };
-                        let subst: HashMap<TVar, Type> = params
-                            .iter()
-                            .map(|tv| (*tv, env.fresh_uvar(&pos)))
-                            .collect();
-                        let tp = unsafe {
-                            if params.len() == 0 {
-                                Type::named_var(*tvar, &name, &pos).unwrap_unchecked()
-                            } else {
-                                Type::type_app(
-                                    *tvar,
-                                    &name,
-                                    subst.values().map(|tp| tp.clone()).collect(),
-                                    &pos,
-                                )
-                                .unwrap_unchecked()
-                            }
-                        };
+                        let (tp, subst) = enum_type(sym_table, env, *parent, pos);
                         let tp = if args.is_empty() {
                             tp
                         } else {
                             Type::fun(args.clone(), tp).substitute(&subst)
                         };
                         if !unify(exp_tp, &tp) {
-                            ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                            ctx.report(error::type_mismatch_from(
+                                pos,
+                                origin.as_ref(),
+                                exp_tp.clone(),
+                                tp.clone(),
+                            ));
+                        }
+                        out_a::Expr::GlobalVar {
+                            id: node_id,
+                            tp,
+                            type_args: vec![],
                         }
-                        out_a::Expr::GlobalVar { id: node_id, tp }
                     }
-                    SymKind::Struct(tvar) => todo!(),
-                    SymKind::Enum(tvar) => todo!(),
+                    SymKind::Const { tp, .. } => {
+                        if exp_mut {
+                            ctx.report(error::assign_to_const(pos, sym.name.clone()));
+                        }
+                        if !unify(exp_tp, tp) {
+                            ctx.report(error::type_mismatch_from(
+                                pos,
+                                origin.as_ref(),
+                                exp_tp.clone(),
+                                tp.clone(),
+                            ));
+                        }
+                        out_a::Expr::GlobalVar {
+                            id: node_id,
+                            tp: tp.clone(),
+                            type_args: vec![],
+                        }
+                    }
+                    SymKind::Static { tp, is_mut, .. } => {
+                        if exp_mut && !is_mut {
+                            ctx.report(error::assign_to_static(pos, sym.name.clone()));
+                        }
+                        if !unify(exp_tp, tp) {
+                            ctx.report(error::type_mismatch_from(
+                                pos,
+                                origin.as_ref(),
+                                exp_tp.clone(),
+                                tp.clone(),
+                            ));
+                        }
+                        out_a::Expr::GlobalVar {
+                            id: node_id,
+                            tp: tp.clone(),
+                            type_args: vec![],
+                        }
+                    }
+                    SymKind::Struct(_) | SymKind::Enum(_) => {
+                        ctx.report(error::type_as_value(pos, sym.name.clone()));
+                        out_a::Expr::Error
+                    }
                 }
             }
         },
         in_a::ExprData::FunCall(expr, expr_nodes) => {
             let fn_tp = env.fresh_uvar(&pos);
             let ref expr_pos = expr.pos.clone();
+            let is_variadic = variadic_callee(sym_table, &expr);
             let ch_expr = check_expr(ctx, sym_table, env, *expr, &fn_tp, false)?;
-            let (args_tp, ret) = match fn_tp.view() {
+            let (mut args_tp, ret) = match fn_tp.view() {
                 TypeView::Fun(args, ret) => (args, ret),
+                // The callee has already been reported.
+                _ if matches!(ch_expr, out_a::Expr::Error) => {
+                    let _ = unify(&fn_tp, &Type::unknown());
+                    return Ok(out_a::Expr::Error);
+                }
                 _ => {
-                    ctx.report(error::not_a_function(expr_pos));
+                    ctx.report(error::not_a_function(expr_pos, fn_tp.clone()));
                     return Ok(out_a::Expr::Error);
                 }
             };
+            check_aliasing(ctx, &expr_nodes);
             let mut args_iter = expr_nodes.into_iter();
             let mut id = 0;
-            let args = args_tp
+            let mut args: Vec<_> = args_tp
                 .clone()
                 .iter_mut()
                 .map(|arg| {
@@ -178,10 +260,25 @@ fn check_expr(
                 .collect::<Result<_, _>>()?;
             while let Some(arg) = args_iter.next() {
                 id += 1;
-                ctx.report(error::unexpected_argument(id, &arg.pos));
+                if !is_variadic {
+                    ctx.report(error::unexpected_argument(id, &arg.pos));
+                    continue;
+                }
+                let tp = env.fresh_uvar(&arg.pos);
+                let arg_pos = arg.pos.clone();
+                args.push(check_expr(ctx, sym_table, env, arg, &tp, false)?);
+                if !is_variadic_arg(&tp) {
+                    ctx.report(error::bad_variadic_argument(&arg_pos, tp.clone()));
+                }
+                args_tp.push(tp);
             }
             if !unify(exp_tp, &ret) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), *ret.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    *ret.clone(),
+                ));
             }
             out_a::Expr::FunCall {
                 expr: Box::new(ch_expr),
@@ -193,6 +290,13 @@ fn check_expr(
         in_a::ExprData::FieldAccess(expr, field_name) => {
             let tp = env.fresh_uvar(&pos);
             let expr = check_expr(ctx, sym_table, env, *expr, &tp, exp_mut)?;
+            let is_sequence = matches!(
+                tp.view(),
+                TypeView::Array(_, _) | TypeView::Slice(_) | TypeView::MutSlice(_)
+            );
+            if is_sequence && field_name == "len" {
+                return Ok(check_len(ctx, pos, expr, tp, exp_tp, exp_mut));
+            }
             let (field_id, field_tp) = match tp.view() {
                 TypeView::NamedVar(tvar, _) | TypeView::Var(tvar) => {
                     let type_info = sym_table.find_type_info(tvar);
@@ -216,7 +320,12 @@ fn check_expr(
                 }
             };
             if !unify(exp_tp, field_tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), field_tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    field_tp.clone(),
+                ));
             }
             out_a::Expr::FieldAccess {
                 object: Box::new(expr),
@@ -225,8 +334,34 @@ fn check_expr(
                 field_tp: field_tp.clone(),
             }
         }
+        in_a::ExprData::TupleField(expr, n) => {
+            let tp = env.fresh_uvar(&pos);
+            let expr = check_expr(ctx, sym_table, env, *expr, &tp, exp_mut)?;
+            let field_tp = match tp.view() {
+                TypeView::Tuple(items) if n < items.len() => items[n].clone(),
+                _ => {
+                    ctx.report(error::no_such_field(n.to_string(), tp, &pos));
+                    return Ok(out_a::Expr::Error);
+                }
+            };
+            if !unify(exp_tp, &field_tp) {
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    field_tp.clone(),
+                ));
+            }
+            out_a::Expr::FieldAccess {
+                object: Box::new(expr),
+                field_id: n,
+                struct_tp: tp,
+                field_tp,
+            }
+        }
         in_a::ExprData::Return(expr) => {
             let tp = env.expected_ret();
+            env.expect_from(env.ret_pos());
             let expr = check_expr(ctx, sym_table, env, *expr, &tp, false)?;
             if !unify(exp_tp, &Type::builtin("never")) {
                 unreachable!("never always coerces")
@@ -238,10 +373,47 @@ fn check_expr(
         }
         in_a::ExprData::Block(expr_nodes, expr) => {
             env.new_scope();
+            // Statement after which the rest of the block is unreachable.
+            let mut diverging: Option<Position> = None;
+            let mut unreachable_reported = false;
+            let check_unreachable = ctx.lint_enabled(Lint::UnreachableCode);
             let exprs = expr_nodes
                 .into_iter()
-                .map(|expr| check_expr(ctx, sym_table, env, expr, &Type::fresh_uvar(), false))
-                .collect::<Result<_, _>>()?;
+                .map(|expr| {
+                    let tp = env.tcx().fresh_uvar();
+                    let must_use = must_use_callee(sym_table, &expr);
+                    let pos = expr.pos.clone();
+                    if let Some(cause) = &diverging
+                        && check_unreachable
+                        && !unreachable_reported
+                    {
+                        ctx.report(error::unreachable_code(&pos, cause));
+                        unreachable_reported = true;
+                    }
+                    let expr = check_expr(ctx, sym_table, env, expr, &tp, false)?;
+                    if let Some(name) = must_use
+                        && !matches!(tp.view(), TypeView::Tuple(items) if items.is_empty())
+                    {
+                        ctx.report(error::unused_must_use(&pos, name));
+                    }
+                    if diverging.is_none() && diverges(&expr) {
+                        diverging = Some(pos);
+                    }
+                    Ok(expr)
+                })
+                .collect::<Result<_, InternalError>>()?;
+            // The unit a block without a trailing expression ends with is implicit.
+            let implicit_unit =
+                matches!(&expr.data, in_a::ExprData::Tuple(items) if items.is_empty());
+            if let Some(cause) = &diverging
+                && check_unreachable
+                && !unreachable_reported
+                && !implicit_unit
+            {
+                ctx.report(error::unreachable_code(&expr.pos, cause));
+            }
+            // the block's value is the trailing expression, so is its expected type
+            env.expect_from(origin.clone());
             let expr = check_expr(ctx, sym_table, env, *expr, exp_tp, exp_mut)?;
             env.leave_scope();
             out_a::Expr::Block {
@@ -256,16 +428,22 @@ fn check_expr(
             tp,
             expr,
         } => {
-            let tp = match tp {
-                Some(tp) => tp,
-                None => env.fresh_uvar(&pos),
+            let (tp, tp_pos) = match tp {
+                Some((tp, tp_pos)) => (tp, Some(tp_pos)),
+                None => (env.fresh_uvar(&pos), None),
             };
             env.new_scope();
+            env.expect_from(tp_pos);
             let expr = check_expr(ctx, sym_table, env, *expr, &tp, false)?;
             env.leave_scope();
-            env.add_var(name.clone(), is_mut, tp.clone());
+            env.add_var(name.clone(), is_mut, tp.clone(), Some(pos));
             if !unify(exp_tp, &Type::unit()) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), Type::unit()));
+                ctx.report(error::unit_as_value(
+                    pos,
+                    origin.as_ref(),
+                    "`let` bindings",
+                    exp_tp.clone(),
+                ));
             };
             out_a::Expr::Let {
                 name,
@@ -274,13 +452,37 @@ fn check_expr(
                 expr: Box::new(expr),
             }
         }
-        in_a::ExprData::If(pr, th, el) => {
+        in_a::ExprData::If(pr, th, None) => {
+            // without an else branch the `if` has no value, and neither may the then branch
+            let tp = env.fresh_uvar(&pos);
+            let pr = check_expr(ctx, sym_table, env, *pr, &Type::builtin("bool"), false)?;
+            let th = check_expr(ctx, sym_table, env, *th, &tp, exp_mut)?;
+            if !unify(&tp, &Type::unit()) {
+                ctx.report(error::if_without_else(pos, tp.clone()));
+                // take the type of the then branch, to report only the missing branch
+                unify(exp_tp, &tp);
+            } else if !unify(exp_tp, &Type::unit()) {
+                ctx.report(error::if_without_else(pos, exp_tp.clone()));
+            }
+            out_a::Expr::If {
+                pred: Box::new(pr),
+                th: Box::new(th),
+                el: Box::new(out_a::Expr::Tuple(vec![], Type::unit())),
+                block_tp: tp,
+            }
+        }
+        in_a::ExprData::If(pr, th, Some(el)) => {
             let tp = env.fresh_uvar(&pos);
             let pr = check_expr(ctx, sym_table, env, *pr, &Type::builtin("bool"), false)?;
             let el = check_expr(ctx, sym_table, env, *el, &tp, exp_mut)?;
             let th = check_expr(ctx, sym_table, env, *th, &tp, exp_mut)?;
             if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
             };
             out_a::Expr::If {
                 pred: Box::new(pr),
@@ -294,7 +496,12 @@ fn check_expr(
             let lval = check_expr(ctx, sym_table, env, *lval, &tp, true)?;
             let rval = check_expr(ctx, sym_table, env, *rval, &tp, false)?;
             if !unify(exp_tp, &Type::unit()) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), Type::unit()));
+                ctx.report(error::unit_as_value(
+                    pos,
+                    origin.as_ref(),
+                    "assignments",
+                    exp_tp.clone(),
+                ));
             }
             out_a::Expr::Assign {
                 lval: Box::new(lval),
@@ -307,7 +514,12 @@ fn check_expr(
             let expr = check_expr(ctx, sym_table, env, *expr_node, &tp, false)?;
             let tp = Type::ptr(tp);
             if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
             }
             out_a::Expr::Ref {
                 expr: Box::new(expr),
@@ -315,11 +527,20 @@ fn check_expr(
             }
         }
         in_a::ExprData::RefMut(expr_node) => {
+            // The borrowed variable may be read through the reference.
+            if let Some(name) = borrowed_local(&expr_node) {
+                env.mark_read(name);
+            }
             let tp = env.fresh_uvar(&pos);
             let expr = check_expr(ctx, sym_table, env, *expr_node, &tp, true)?;
             let tp = Type::mut_ptr(tp);
             if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
             }
             out_a::Expr::RefMut {
                 expr: Box::new(expr),
@@ -333,22 +554,64 @@ fn check_expr(
             } else {
                 Type::ptr(in_tp.clone())
             };
-            let expr = check_expr(ctx, sym_table, env, *expr_node, &tp, false)?;
+            let op_pos = expr_node.pos.clone();
+            let op_tp = env.fresh_uvar(&pos);
+            let expr = check_expr(ctx, sym_table, env, *expr_node, &op_tp, false)?;
+            // functions are pointers to code, there is nothing to read
+            if let TypeView::Fun(_, _) = op_tp.view() {
+                ctx.report(error::deref_fun(&op_pos, op_tp));
+                // don't report the type of the result as unknown again
+                unify(&in_tp, &Type::unknown());
+                unify(exp_tp, &Type::unknown());
+                return Ok(out_a::Expr::Error);
+            }
+            if let (true, TypeView::Ptr(pointee)) = (exp_mut, op_tp.view()) {
+                ctx.report(error::assign_through_ptr(&op_pos, op_tp.clone()));
+                unify(&in_tp, &pointee);
+            } else if !unify(&tp, &op_tp) {
+                ctx.report(error::type_mismatch(&op_pos, tp.clone(), op_tp.clone()));
+            }
             if !unify(exp_tp, &in_tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), in_tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    in_tp.clone(),
+                ));
             }
             out_a::Expr::Deref {
                 expr: Box::new(expr),
                 in_tp: in_tp,
             }
         }
-        in_a::ExprData::NumLit(lit) => {
-            let tp = env.numeric_uvar(&pos);
+        in_a::ExprData::NumLit(lit, suffix) => {
+            let tp = match suffix {
+                Some(suffix) => Type::builtin(&suffix),
+                None => env.numeric_uvar(&pos),
+            };
             if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
             }
+            env.add_lit(lit, tp.clone(), &pos);
             out_a::Expr::NumLit(lit, tp)
         }
+        in_a::ExprData::BoolLit(b) => {
+            let tp = Type::builtin("bool");
+            if !unify(exp_tp, &tp) {
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
+            }
+            out_a::Expr::NumLit(b as usize, tp)
+        }
         in_a::ExprData::Tuple(exprs) => {
             let mut tps = vec![];
             let mut ch_exprs = vec![];
@@ -360,17 +623,20 @@ fn check_expr(
             }
             let tp = Type::tuple(tps.clone());
             if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
             }
             out_a::Expr::Tuple(ch_exprs, tp)
         }
         in_a::ExprData::String(s) => {
-            let size = s.as_bytes().len();
-            let tp = Type::ptr(Type::array(size, Type::builtin("u8")));
-            if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
-            }
-            out_a::Expr::StringLit(s, tp)
+            check_string_lit(ctx, exp_tp, origin.as_ref(), s.into_bytes(), pos)
+        }
+        in_a::ExprData::ByteString(bytes) => {
+            check_string_lit(ctx, exp_tp, origin.as_ref(), bytes, pos)
         }
         in_a::ExprData::MethodCall(expr, method_name, exprs) => {
             ctx.report(error::not_yet_supported(&pos));
@@ -382,6 +648,12 @@ fn check_expr(
                 SymKind::Func { params, args, ret } => todo!(),
                 SymKind::Enum(tvar) => todo!(),
                 SymKind::EnumCons { id, args, parent } => todo!(),
+                SymKind::Const { .. } | SymKind::Static { .. } => {
+                    let what = const_eval::item_kind(sym_table, id);
+                    ctx.report(error::global_as_struct(pos, sym_info.name.clone(), what));
+                    unify(exp_tp, &Type::unknown());
+                    return Ok(out_a::Expr::Error);
+                }
                 SymKind::Struct(tvar) => {
                     let type_info = sym_table.find_type_info(*tvar);
                     match &type_info.kind {
@@ -422,14 +694,19 @@ fn check_expr(
                     Type::type_app(
                         *tvar,
                         &name,
-                        subst.values().map(|tp| tp.clone()).collect(),
+                        params.iter().map(|tv| subst[tv].clone()).collect(),
                         &pos,
                     )
                     .unwrap_unchecked()
                 }
             };
             if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
             }
             out_a::Expr::StructCons {
                 id,
@@ -439,11 +716,20 @@ fn check_expr(
         }
         in_a::ExprData::Error => out_a::Expr::Error,
         in_a::ExprData::IndexAccess(arr, index) => {
-            let tp = env.fresh_uvar(&pos);
-            let arr = check_expr(ctx, sym_table, env, *arr, &tp, exp_mut)?;
+            // Elements of a slice are behind its pointer,
+            // writing them doesn't change the slice itself.
+            let arr_mut = exp_mut && !local_is_slice(env, &arr);
+            let arr_tp = env.fresh_uvar(&pos);
+            let arr = check_expr(ctx, sym_table, env, *arr, &arr_tp, arr_mut)?;
             let index = check_expr(ctx, sym_table, env, *index, &Type::builtin("usize"), false)?;
-            let tp = match tp.view() {
-                TypeView::Array(_, tp) => *tp,
+            let tp = match arr_tp.view() {
+                TypeView::Array(_, tp) | TypeView::MutSlice(tp) => *tp,
+                TypeView::Slice(tp) => {
+                    if exp_mut {
+                        ctx.report(error::immutable_slice(pos, arr_tp.clone()));
+                    }
+                    *tp
+                }
                 TypeView::Unknown => todo!(),
                 TypeView::UVar(uvar) | TypeView::NumericUVar(uvar) => {
                     todo!()
@@ -461,37 +747,141 @@ fn check_expr(
                 TypeView::TypeApp(tvar, _, items) => todo!(),
             };
             if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp.clone()));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
             }
             out_a::Expr::IndexAccess {
                 arr: Box::new(arr),
                 index: Box::new(index),
+                arr_tp,
                 tp: tp,
             }
         }
-        in_a::ExprData::Match(expr_node, match_clauses) => {
-            ctx.report(error::not_yet_supported(&pos));
-            out_a::Expr::Error
+        in_a::ExprData::Match(expr, clauses) => {
+            if exp_mut {
+                ctx.report(error::expected_mutable(pos));
+            }
+            let tp = env.fresh_uvar(pos);
+            let expr = check_match(ctx, sym_table, env, *expr, clauses, &tp, pos)?;
+            if !unify(exp_tp, &tp) {
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
+            }
+            expr
         }
         in_a::ExprData::While(pred, block) => {
             if exp_mut {
                 ctx.report(error::expected_mutable(pos));
             }
             let pred = check_expr(ctx, sym_table, env, *pred, &Type::builtin("bool"), false)?;
+            env.enter_loop(Type::unit());
             let block = check_expr(ctx, sym_table, env, *block, &Type::unit(), false)?;
+            env.leave_loop();
             if !unify(exp_tp, &Type::unit()) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), Type::unit()));
+                ctx.report(error::unit_as_value(
+                    pos,
+                    origin.as_ref(),
+                    "`while` loops",
+                    exp_tp.clone(),
+                ));
             }
             out_a::Expr::While {
                 pred: Box::new(pred),
                 block: Box::new(block),
             }
         }
+        in_a::ExprData::Loop(body) => {
+            if exp_mut {
+                ctx.report(error::expected_mutable(pos));
+            }
+            let tp = env.fresh_uvar(pos);
+            env.enter_loop(tp.clone());
+            let body = check_expr(ctx, sym_table, env, *body, &Type::unit(), false)?;
+            let tp = if env.leave_loop() {
+                if !unify(exp_tp, &tp) {
+                    ctx.report(error::type_mismatch_from(
+                        pos,
+                        origin.as_ref(),
+                        exp_tp.clone(),
+                        tp.clone(),
+                    ));
+                }
+                tp
+            } else {
+                // Without a `break` the loop never yields a value.
+                unify(&tp, &Type::unit());
+                if !unify(exp_tp, &Type::builtin("never")) {
+                    unreachable!("never always coerces")
+                };
+                Type::unit()
+            };
+            out_a::Expr::Loop {
+                body: Box::new(body),
+                tp,
+            }
+        }
+        in_a::ExprData::Break(expr) => {
+            let tp = match env.add_break() {
+                Some(tp) => tp,
+                None => {
+                    ctx.report(error::outside_loop(pos, "break"));
+                    env.tcx().fresh_uvar()
+                }
+            };
+            let expr = check_expr(ctx, sym_table, env, *expr, &tp, false)?;
+            if !unify(exp_tp, &Type::builtin("never")) {
+                unreachable!("never always coerces")
+            };
+            out_a::Expr::Break(Box::new(expr))
+        }
+        in_a::ExprData::Continue => {
+            if !env.in_loop() {
+                ctx.report(error::outside_loop(pos, "continue"));
+            }
+            if !unify(exp_tp, &Type::builtin("never")) {
+                unreachable!("never always coerces")
+            };
+            out_a::Expr::Continue
+        }
         in_a::ExprData::Cast(expr, to_type) => {
             let tp = env.fresh_uvar(&pos);
+            let lit = match expr.data {
+                in_a::ExprData::NumLit(n, _) => Some(n),
+                _ => None,
+            };
+            env.add_cast(tp.clone(), to_type.clone(), lit, &pos);
             let expr = check_expr(ctx, sym_table, env, *expr, &tp, exp_mut)?;
             if !unify(exp_tp, &to_type) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), to_type));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    to_type.clone(),
+                ));
+            }
+            out_a::Expr::Cast {
+                expr: Box::new(expr),
+                from: tp,
+                to: to_type,
+            }
+        }
+        in_a::ExprData::Ascription(expr, tp) => {
+            let expr = check_expr(ctx, sym_table, env, *expr, &tp, exp_mut)?;
+            if !unify(exp_tp, &tp) {
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp,
+                ));
             }
             expr
         }
@@ -507,7 +897,12 @@ fn check_expr(
                 .collect::<Result<_, _>>()?;
             let arr_tp = Type::array(size, tp.clone());
             if !unify(exp_tp, &arr_tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), arr_tp));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    arr_tp,
+                ));
             }
             out_a::Expr::ArrayInitExact(exprs, tp)
         }
@@ -519,38 +914,680 @@ fn check_expr(
             let expr = check_expr(ctx, sym_table, env, *expr, &tp, false)?;
             let arr_tp = Type::array(size, tp.clone());
             if !unify(exp_tp, &arr_tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), arr_tp));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    arr_tp,
+                ));
             }
             out_a::Expr::ArrayInitRepeat(Box::new(expr), size, tp)
         }
         in_a::ExprData::Char(c) => {
+            let tp = Type::builtin("char");
+            if !unify(exp_tp, &tp) {
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
+            }
+            out_a::Expr::NumLit(c as usize, tp)
+        }
+        in_a::ExprData::Byte(b) => {
             let tp = Type::builtin("u8");
             if !unify(exp_tp, &tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), tp));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
+            }
+            out_a::Expr::NumLit(b as usize, tp)
+        }
+        in_a::ExprData::Neg(expr) => {
+            let tp = env.numeric_uvar(pos);
+            let is_lit = matches!(expr.data, in_a::ExprData::NumLit(_, _));
+            let expr = check_expr(ctx, sym_table, env, *expr, &tp, false)?;
+            if is_lit {
+                env.negate_last_lit();
+            }
+            if !unify(exp_tp, &tp) {
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    tp.clone(),
+                ));
             }
-            out_a::Expr::Char(c)
+            env.add_neg(tp.clone(), pos);
+            out_a::Expr::Builtin("ineg".into(), vec![expr], vec![tp])
         }
         in_a::ExprData::Builtin(name, expr_nodes) => {
-            let (args_tp, ret_tp) = get_builtin_type(&name);
+            let (args_tp, ret_tp) = get_builtin_type(env.tcx(), &name);
             let mut args = vec![];
             for (expr, tp) in expr_nodes.into_iter().zip(args_tp.iter()) {
                 let e = check_expr(ctx, sym_table, env, expr, tp, false)?;
                 args.push(e);
             }
             if !unify(exp_tp, &ret_tp) {
-                ctx.report(error::type_mismatch(pos, exp_tp.clone(), ret_tp));
+                ctx.report(error::type_mismatch_from(
+                    pos,
+                    origin.as_ref(),
+                    exp_tp.clone(),
+                    ret_tp,
+                ));
             }
-            out_a::Expr::Builtin(name, args)
+            out_a::Expr::Builtin(name, args, args_tp)
         }
     })
 }
 
-fn get_builtin_type(name: &str) -> (Vec<Type>, Type) {
+/// String and byte string literals are pointers to `[N]u8`, or the array
+/// itself when one is expected.
+fn check_string_lit(
+    ctx: &mut Context,
+    exp_tp: &Type,
+    origin: Option<&Position>,
+    bytes: Vec<u8>,
+    pos: &Position,
+) -> out_a::Expr {
+    let size = bytes.len();
+    let tp = match exp_tp.view() {
+        // string literal can initialize a byte array in place
+        TypeView::Array(_, _) => Type::array(size, Type::builtin("u8")),
+        _ => Type::ptr(Type::array(size, Type::builtin("u8"))),
+    };
+    if !unify(exp_tp, &tp) {
+        ctx.report(error::type_mismatch_from(
+            pos,
+            origin,
+            exp_tp.clone(),
+            tp.clone(),
+        ));
+    }
+    out_a::Expr::StringLit(bytes, tp)
+}
+
+/// Report arguments of a single call that borrow the same local,
+/// when at least one of the borrows is mutable.
+fn check_aliasing(ctx: &mut Context, args: &[in_a::ExprNode]) {
+    let mut borrows: Vec<(&str, bool, &Position)> = vec![];
+    for arg in args {
+        let (place, is_mut) = match &arg.data {
+            in_a::ExprData::Ref(place) => (place, false),
+            in_a::ExprData::RefMut(place) => (place, true),
+            _ => continue,
+        };
+        let Some(name) = borrowed_local(place) else {
+            continue;
+        };
+        let conflict = borrows
+            .iter()
+            .find(|(other, other_mut, _)| *other == name && (is_mut || *other_mut));
+        if let Some((_, other_mut, other_pos)) = conflict {
+            let (mut_pos, other_pos) = match other_mut {
+                true => (*other_pos, &arg.pos),
+                false => (&arg.pos, *other_pos),
+            };
+            ctx.report(error::aliased_mut_ref(mut_pos, other_pos, name.to_string()));
+        }
+        borrows.push((name, is_mut, &arg.pos));
+    }
+}
+
+/// Returns the local variable a place expression is a part of.
+///
+/// Places behind a pointer are not owned by a local, so they're ignored.
+fn borrowed_local(place: &in_a::ExprNode) -> Option<&str> {
+    match &place.data {
+        in_a::ExprData::Var(in_a::SymRef::Local(name)) => Some(name),
+        in_a::ExprData::FieldAccess(object, _) | in_a::ExprData::TupleField(object, _) => {
+            borrowed_local(object)
+        }
+        in_a::ExprData::IndexAccess(arr, _) => borrowed_local(arr),
+        _ => None,
+    }
+}
+
+/// Typecheck a match and lower it to a chain of conditionals.
+///
+/// The scrutinee is bound to a variable, then each arm tests the parts
+/// of it refuted by its pattern and binds the variables of the pattern:
+///
+/// ```mst
+/// {
+///     let $match = e;
+///     if cond_1 { let x = $match.0; arm_1 } else if ... else { arm_n }
+/// }
+/// ```
+///
+/// The last arm is taken without testing it, so the arms must match every
+/// value. That's the case when an arm is irrefutable, or when every
+/// constructor of an enum is matched by an arm with irrefutable fields.
+fn check_match(
+    ctx: &mut Context,
+    sym_table: &SymTable,
+    env: &mut Env,
+    expr: in_a::ExprNode,
+    clauses: Vec<in_a::MatchClause>,
+    tp: &Type,
+    pos: &Position,
+) -> Result<out_a::Expr, InternalError> {
+    let scrut_tp = env.fresh_uvar(&expr.pos);
+    let scrut = check_expr(ctx, sym_table, env, expr, &scrut_tp, false)?;
+    // users can't name a variable like this, and nested matches get distinct names
+    let name = format!("$match{}", pos.start);
+    let place = out_a::Expr::LocalVar {
+        name: name.clone(),
+        tp: scrut_tp.clone(),
+    };
+    let mut arms = vec![];
+    // Pattern after which every value has been matched.
+    let mut exhaustive: Option<Position> = None;
+    let mut covered = HashSet::new();
+    for clause in clauses {
+        env.new_scope();
+        let pat_pos = clause.pattern.pos.clone();
+        let irrefutable = is_irrefutable(&clause.pattern);
+        let cons = covered_constructor(sym_table, &clause.pattern);
+        let mut tests = PatternTests::default();
+        check_pattern(
+            ctx,
+            sym_table,
+            env,
+            clause.pattern,
+            place.clone(),
+            &scrut_tp,
+            &mut tests,
+        )?;
+        let body = check_expr(ctx, sym_table, env, clause.expr, tp, false)?;
+        env.leave_scope();
+        match &exhaustive {
+            Some(cause) => {
+                if ctx.lint_enabled(Lint::UnreachableCode) {
+                    ctx.report(error::unreachable_code(&clause.pos, cause));
+                }
+            }
+            None => {
+                if let Some((cons, count)) = cons {
+                    covered.insert(cons);
+                    if covered.len() == count {
+                        exhaustive = Some(pat_pos.clone());
+                    }
+                }
+                if irrefutable {
+                    exhaustive = Some(pat_pos);
+                }
+                arms.push((tests, body));
+            }
+        }
+    }
+    if exhaustive.is_none() {
+        ctx.report(error::non_exhaustive_match(pos));
+        return Ok(out_a::Expr::Error);
+    }
+    let arm = |binds, body| out_a::Expr::Block {
+        exprs: binds,
+        last_expr: Box::new(body),
+        block_tp: tp.clone(),
+    };
+    let mut arms = arms.into_iter().rev();
+    let (tests, body) = arms.next().expect("the arm matching the remaining values");
+    let mut chain = arm(tests.binds, body);
+    for (tests, body) in arms {
+        let pred = tests
+            .conds
+            .into_iter()
+            .rev()
+            .reduce(|rest, cond| out_a::Expr::If {
+                pred: Box::new(cond),
+                th: Box::new(rest),
+                el: Box::new(out_a::Expr::NumLit(0, Type::builtin("bool"))),
+                block_tp: Type::builtin("bool"),
+            })
+            // only invalid patterns test nothing before the last arm
+            .unwrap_or(out_a::Expr::Error);
+        chain = out_a::Expr::If {
+            pred: Box::new(pred),
+            th: Box::new(arm(tests.binds, body)),
+            el: Box::new(chain),
+            block_tp: tp.clone(),
+        };
+    }
+    let bind = out_a::Expr::Let {
+        name,
+        tp: scrut_tp,
+        is_mut: false,
+        expr: Box::new(scrut),
+    };
+    Ok(out_a::Expr::Block {
+        exprs: vec![bind],
+        last_expr: Box::new(chain),
+        block_tp: tp.clone(),
+    })
+}
+
+/// Whether the pattern matches every value of its type.
+fn is_irrefutable(pattern: &in_a::PatternNode) -> bool {
+    match &pattern.data {
+        in_a::PatternData::Error | in_a::PatternData::Wildcard | in_a::PatternData::Var(_) => true,
+        in_a::PatternData::Tuple(pats) => pats.iter().all(is_irrefutable),
+        in_a::PatternData::Number(_) | in_a::PatternData::TupleCons(_, _) => false,
+    }
+}
+
+/// Index of the constructor whose every value the pattern matches,
+/// and the number of constructors of its enum.
+fn covered_constructor(
+    sym_table: &SymTable,
+    pattern: &in_a::PatternNode,
+) -> Option<(usize, usize)> {
+    let in_a::PatternData::TupleCons(id, pats) = &pattern.data else {
+        return None;
+    };
+    let SymKind::EnumCons { id, parent, .. } = &sym_table.find_sym_info(*id).kind else {
+        return None;
+    };
+    let SymKind::Enum(tvar) = &sym_table.find_sym_info(*parent).kind else {
+        unreachable!("parent of a constructor is an enum")
+    };
+    let TypeKind::Enum { constructors, .. } = &sym_table.find_type_info(*tvar).kind else {
+        unreachable!("type of an enum symbol is an enum")
+    };
+    pats.iter()
+        .all(is_irrefutable)
+        .then_some((*id, constructors.len()))
+}
+
+/// Conditions under which a pattern matches, and `let` bindings of its variables.
+#[derive(Default)]
+struct PatternTests {
+    conds: Vec<out_a::Expr>,
+    binds: Vec<out_a::Expr>,
+}
+
+/// Typecheck a pattern matched against `place` of type `tp`.
+fn check_pattern(
+    ctx: &mut Context,
+    sym_table: &SymTable,
+    env: &mut Env,
+    pattern: in_a::PatternNode,
+    place: out_a::Expr,
+    tp: &Type,
+    tests: &mut PatternTests,
+) -> Result<(), InternalError> {
+    let pos = pattern.pos;
+    match pattern.data {
+        in_a::PatternData::Error | in_a::PatternData::Wildcard => (),
+        in_a::PatternData::Var(name) => {
+            env.add_var(name.clone(), false, tp.clone(), Some(&pos));
+            tests.binds.push(out_a::Expr::Let {
+                name,
+                tp: tp.clone(),
+                is_mut: false,
+                expr: Box::new(place),
+            });
+        }
+        in_a::PatternData::Number(n) => {
+            // checked like the literal, so its range and sign are checked too
+            let mut lit = in_a::ExprNode {
+                data: in_a::ExprData::NumLit(n.unsigned_abs() as usize, None),
+                pos: pos.clone(),
+            };
+            if n < 0 {
+                lit = in_a::ExprNode {
+                    data: in_a::ExprData::Neg(Box::new(lit)),
+                    pos,
+                };
+            }
+            let lit = check_expr(ctx, sym_table, env, lit, tp, false)?;
+            tests.conds.push(out_a::Expr::Builtin(
+                "eq".into(),
+                vec![place, lit],
+                vec![tp.clone(), tp.clone()],
+            ));
+        }
+        in_a::PatternData::Tuple(pats) => {
+            // the variables are bound even if the pattern doesn't fit,
+            // with types inferred from their uses
+            let items: Vec<_> = pats.iter().map(|_| env.tcx().fresh_uvar()).collect();
+            if !unify(tp, &Type::tuple(items.clone())) {
+                ctx.report(error::tuple_pattern_mismatch(&pos, tp.clone(), pats.len()));
+            }
+            for (field_id, (pat, field_tp)) in pats.into_iter().zip(items).enumerate() {
+                let place = out_a::Expr::FieldAccess {
+                    object: Box::new(place.clone()),
+                    field_id,
+                    struct_tp: tp.clone(),
+                    field_tp: field_tp.clone(),
+                };
+                check_pattern(ctx, sym_table, env, pat, place, &field_tp, tests)?;
+            }
+        }
+        in_a::PatternData::TupleCons(id, pats) => {
+            let sym_info = sym_table.find_sym_info(id);
+            let SymKind::EnumCons {
+                id: cons,
+                args,
+                parent,
+            } = &sym_info.kind
+            else {
+                ctx.report(error::not_a_constructor(&pos, sym_info.name.clone()));
+                for pat in pats {
+                    let tp = env.tcx().fresh_uvar();
+                    check_pattern(ctx, sym_table, env, pat, out_a::Expr::Error, &tp, tests)?;
+                }
+                return Ok(());
+            };
+            let (enum_tp, subst) = enum_type(sym_table, env, *parent, &pos);
+            if !unify(tp, &enum_tp) {
+                ctx.report(error::type_mismatch(&pos, tp.clone(), enum_tp.clone()));
+            }
+            if pats.len() != args.len() {
+                ctx.report(error::constructor_arity(
+                    &pos,
+                    sym_info.name.clone(),
+                    args.len(),
+                    pats.len(),
+                ));
+            }
+            let u32_tp = Type::builtin("u32");
+            let tag = out_a::Expr::EnumTag {
+                object: Box::new(place.clone()),
+                enum_tp: enum_tp.clone(),
+            };
+            tests.conds.push(out_a::Expr::Builtin(
+                "eq".into(),
+                vec![tag, out_a::Expr::NumLit(*cons, u32_tp.clone())],
+                vec![u32_tp.clone(), u32_tp],
+            ));
+            for (field_id, pat) in pats.into_iter().enumerate() {
+                let (place, field_tp) = match args.get(field_id) {
+                    Some(arg) => {
+                        let field_tp = arg.substitute(&subst);
+                        let place = out_a::Expr::EnumField {
+                            object: Box::new(place.clone()),
+                            enum_tp: enum_tp.clone(),
+                            cons: *cons,
+                            field_id,
+                            field_tp: field_tp.clone(),
+                        };
+                        (place, field_tp)
+                    }
+                    // reported above
+                    None => (out_a::Expr::Error, env.tcx().fresh_uvar()),
+                };
+                check_pattern(ctx, sym_table, env, pat, place, &field_tp, tests)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Type of the enum with given symbol, with fresh unification variables
+/// for its parameters, and the substitution of the parameters.
+fn enum_type(
+    sym_table: &SymTable,
+    env: &mut Env,
+    enum_id: NodeID,
+    pos: &Position,
+) -> (Type, HashMap<TVar, Type>) {
+    let SymKind::Enum(tvar) = &sym_table.find_sym_info(enum_id).kind else {
+        unreachable!("parent of a constructor is an enum")
+    };
+    let type_info = sym_table.find_type_info(*tvar);
+    let TypeKind::Enum { params, .. } = &type_info.kind else {
+        unreachable!("type of an enum symbol is an enum")
+    };
+    let name = &type_info.name;
+    let subst: HashMap<TVar, Type> = params.iter().map(|tv| (*tv, env.fresh_uvar(pos))).collect();
+    let tp = unsafe {
+        if params.len() == 0 {
+            Type::named_var(*tvar, name, pos).unwrap_unchecked()
+        } else {
+            Type::type_app(
+                *tvar,
+                name,
+                params.iter().map(|tv| subst[tv].clone()).collect(),
+                pos,
+            )
+            .unwrap_unchecked()
+        }
+    };
+    (tp, subst)
+}
+
+/// Typecheck an expression expected to be a slice.
+///
+/// Pointers to arrays coerce to slices of their elements. Unlike other
+/// coercions this one changes the representation, so it's done here,
+/// where the slice can be built from the pointer and the array length.
+fn check_slice(
+    ctx: &mut Context,
+    sym_table: &SymTable,
+    env: &mut Env,
+    expr: in_a::ExprNode,
+    exp_tp: &Type,
+) -> Result<out_a::Expr, InternalError> {
+    let pos = expr.pos.clone();
+    let tp = env.fresh_uvar(&pos);
+    let expr = check_expr(ctx, sym_table, env, expr, &tp, false)?;
+    let array = match (exp_tp.view(), tp.view()) {
+        (TypeView::Slice(elem), TypeView::Ptr(arr) | TypeView::MutPtr(arr))
+        | (TypeView::MutSlice(elem), TypeView::MutPtr(arr)) => match arr.view() {
+            TypeView::Array(len, arr_elem) => Some((elem, len, arr_elem)),
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some((elem, len, arr_elem)) = array else {
+        if !unify(exp_tp, &tp) {
+            ctx.report(error::type_mismatch(&pos, exp_tp.clone(), tp.clone()));
+        }
+        return Ok(expr);
+    };
+    if !unify(&elem, &arr_elem) {
+        ctx.report(error::type_mismatch(&pos, exp_tp.clone(), tp.clone()));
+    }
+    Ok(out_a::Expr::ArrayToSlice {
+        ptr: Box::new(expr),
+        len,
+        tp: exp_tp.clone(),
+    })
+}
+
+/// Typecheck `.len` of an already checked array or slice.
+///
+/// Length of an array is known statically, length of a slice
+/// is the second word of the slice.
+fn check_len(
+    ctx: &mut Context,
+    pos: &Position,
+    object: out_a::Expr,
+    tp: Type,
+    exp_tp: &Type,
+    exp_mut: bool,
+) -> out_a::Expr {
+    if exp_mut {
+        ctx.report(error::assign_to_len(pos));
+    }
+    let usize = Type::builtin("usize");
+    if !unify(exp_tp, &usize) {
+        ctx.report(error::type_mismatch(pos, exp_tp.clone(), usize.clone()));
+    }
+    match tp.view() {
+        TypeView::Array(len, _) => match object {
+            out_a::Expr::LocalVar { .. } | out_a::Expr::GlobalVar { .. } => {
+                out_a::Expr::NumLit(len, usize)
+            }
+            // keep the effects of the array expression
+            object => out_a::Expr::Block {
+                exprs: vec![object],
+                last_expr: Box::new(out_a::Expr::NumLit(len, usize.clone())),
+                block_tp: usize,
+            },
+        },
+        _ => out_a::Expr::FieldAccess {
+            object: Box::new(object),
+            field_id: 1,
+            struct_tp: tp,
+            field_tp: usize,
+        },
+    }
+}
+
+/// Whether the expression is a local variable already known to be a slice.
+fn local_is_slice(env: &Env, expr: &in_a::ExprNode) -> bool {
+    let in_a::ExprData::Var(in_a::SymRef::Local(name)) = &expr.data else {
+        return false;
+    };
+    matches!(
+        env.lookup(name).1.view(),
+        TypeView::Slice(_) | TypeView::MutSlice(_)
+    )
+}
+
+/// Whether evaluation of the expression never finishes normally,
+/// so the code following it is unreachable.
+///
+/// This is an approximation: it follows control flow within the expression,
+/// but only knows about calls that can't return from the declared type.
+fn diverges(expr: &out_a::Expr) -> bool {
+    match expr {
+        out_a::Expr::Return { .. } | out_a::Expr::Break(_) | out_a::Expr::Continue => true,
+        out_a::Expr::Block {
+            exprs, last_expr, ..
+        } => exprs.iter().any(diverges) || diverges(last_expr),
+        out_a::Expr::If { pred, th, el, .. } => diverges(pred) || (diverges(th) && diverges(el)),
+        out_a::Expr::Loop { body, .. } => !breaks(body),
+        out_a::Expr::While { pred, .. } => diverges(pred),
+        out_a::Expr::Let { expr, .. }
+        | out_a::Expr::Ref { expr, .. }
+        | out_a::Expr::RefMut { expr, .. }
+        | out_a::Expr::Deref { expr, .. }
+        | out_a::Expr::Cast { expr, .. } => diverges(expr),
+        out_a::Expr::Assign { lval, rval, .. } => diverges(rval) || diverges(lval),
+        out_a::Expr::FunCall {
+            expr, args, ret_tp, ..
+        } => {
+            diverges(expr)
+                || args.iter().any(diverges)
+                || matches!(ret_tp.view(), TypeView::NamedVar(tvar, _) | TypeView::Var(tvar) if tvar.is_never())
+        }
+        out_a::Expr::Tuple(exprs, _)
+        | out_a::Expr::ArrayInitExact(exprs, _)
+        | out_a::Expr::Builtin(_, exprs, _) => exprs.iter().any(diverges),
+        out_a::Expr::FieldAccess { object, .. }
+        | out_a::Expr::EnumTag { object, .. }
+        | out_a::Expr::EnumField { object, .. } => diverges(object),
+        out_a::Expr::IndexAccess { arr, index, .. } => diverges(arr) || diverges(index),
+        out_a::Expr::ArrayInitRepeat(expr, _, _) | out_a::Expr::ArrayToSlice { ptr: expr, .. } => {
+            diverges(expr)
+        }
+        out_a::Expr::StructCons { initializers, .. } => {
+            initializers.values().any(|(_, expr)| diverges(expr))
+        }
+        out_a::Expr::NumLit(_, _)
+        | out_a::Expr::StringLit(_, _)
+        | out_a::Expr::LocalVar { .. }
+        | out_a::Expr::GlobalVar { .. }
+        | out_a::Expr::Error => false,
+    }
+}
+
+/// Whether the loop body contains a `break` of this loop.
+///
+/// Breaks in nested loops leave only those loops.
+fn breaks(expr: &out_a::Expr) -> bool {
+    match expr {
+        out_a::Expr::Break(_) => true,
+        out_a::Expr::Loop { .. } | out_a::Expr::While { .. } => false,
+        out_a::Expr::Block {
+            exprs, last_expr, ..
+        } => exprs.iter().any(breaks) || breaks(last_expr),
+        out_a::Expr::If { pred, th, el, .. } => breaks(pred) || breaks(th) || breaks(el),
+        out_a::Expr::Let { expr, .. } | out_a::Expr::Return { expr, .. } => breaks(expr),
+        out_a::Expr::Assign { lval, rval, .. } => breaks(lval) || breaks(rval),
+        out_a::Expr::FunCall { expr, args, .. } => breaks(expr) || args.iter().any(breaks),
+        out_a::Expr::Tuple(exprs, _)
+        | out_a::Expr::ArrayInitExact(exprs, _)
+        | out_a::Expr::Builtin(_, exprs, _) => exprs.iter().any(breaks),
+        out_a::Expr::Ref { expr, .. }
+        | out_a::Expr::RefMut { expr, .. }
+        | out_a::Expr::Deref { expr, .. }
+        | out_a::Expr::Cast { expr, .. }
+        | out_a::Expr::ArrayInitRepeat(expr, _, _)
+        | out_a::Expr::ArrayToSlice { ptr: expr, .. } => breaks(expr),
+        out_a::Expr::FieldAccess { object, .. }
+        | out_a::Expr::EnumTag { object, .. }
+        | out_a::Expr::EnumField { object, .. } => breaks(object),
+        out_a::Expr::IndexAccess { arr, index, .. } => breaks(arr) || breaks(index),
+        out_a::Expr::StructCons { initializers, .. } => {
+            initializers.values().any(|(_, expr)| breaks(expr))
+        }
+        out_a::Expr::NumLit(_, _)
+        | out_a::Expr::StringLit(_, _)
+        | out_a::Expr::LocalVar { .. }
+        | out_a::Expr::GlobalVar { .. }
+        | out_a::Expr::Continue
+        | out_a::Expr::Error => false,
+    }
+}
+
+/// Returns the name of the function called by the expression,
+/// if it's marked `@must_use`.
+fn must_use_callee(sym_table: &SymTable, expr: &in_a::ExprNode) -> Option<String> {
+    let in_a::ExprData::FunCall(callee, _) = &expr.data else {
+        return None;
+    };
+    let in_a::ExprData::Var(in_a::SymRef::Global(id)) = &callee.data else {
+        return None;
+    };
+    let sym = sym_table.find_sym_info(*id);
+    sym.must_use.then(|| sym.name.clone())
+}
+
+/// Check if the callee is a variadic function, which takes arguments
+/// past the declared ones.
+fn variadic_callee(sym_table: &SymTable, callee: &in_a::ExprNode) -> bool {
+    match &callee.data {
+        in_a::ExprData::Var(in_a::SymRef::Global(id)) => {
+            sym_table.find_sym_info(*id).variadic.is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Check if a value of given type can be passed in place of `...`.
+///
+/// C promotes smaller integers to `int` before passing them,
+/// so only integers of at least 32 bits and pointers are accepted.
+fn is_variadic_arg(tp: &Type) -> bool {
+    match tp.view() {
+        TypeView::NumericUVar(_) | TypeView::Ptr(_) | TypeView::MutPtr(_) => true,
+        TypeView::Var(tv) | TypeView::NamedVar(tv, _) => {
+            tv.is_numeric()
+                && !["u8", "u16", "i8", "i16"]
+                    .iter()
+                    .any(|name| TVar::of_builtin(name) == tv)
+        }
+        _ => false,
+    }
+}
+
+fn get_builtin_type(tcx: &mut TypeCtx, name: &str) -> (Vec<Type>, Type) {
     match name {
-        "iadd" => {
-            let tp = Type::numeric_uvar();
+        "iadd" | "isub" | "imul" | "idiv" => {
+            let tp = tcx.numeric_uvar();
             (vec![tp.clone(), tp.clone()], tp)
         }
+        "lt" | "le" | "eq" | "ne" | "gt" | "ge" => {
+            let tp = tcx.numeric_uvar();
+            (vec![tp.clone(), tp], Type::builtin("bool"))
+        }
         _ => panic!("unknown builtin name"),
     }
 }
@@ -0,0 +1,270 @@
+//! Source-like printing of typechecked programs.
+//!
+//! Every expression with a type of its own is shown together with it, as
+//! `(expr : type)`, or `{ ... } : type` for blocks. Types are printed after
+//! following unification variables, so unresolved ones stand out.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    symtable::{SymTable, TypeKind},
+    tp::{Type, TypeView},
+    typecheck::ast::{Expr, Func, Program},
+};
+
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer {
+            f,
+            sym_table: &self.sym_table,
+            indent: 0,
+        };
+        for (i, func) in self.functions.iter().enumerate() {
+            if i != 0 {
+                writeln!(printer.f)?;
+            }
+            printer.func(func)?;
+            writeln!(printer.f)?;
+        }
+        Ok(())
+    }
+}
+
+struct Printer<'a, 'b> {
+    f: &'a mut Formatter<'b>,
+    sym_table: &'a SymTable,
+    indent: usize,
+}
+
+impl Printer<'_, '_> {
+    fn func(&mut self, func: &Func) -> fmt::Result {
+        write!(self.f, "fn {}(", func.name)?;
+        for (i, (name, is_mut, tp)) in func.args.iter().enumerate() {
+            if i != 0 {
+                write!(self.f, ", ")?;
+            }
+            let m = if *is_mut { "mut " } else { "" };
+            write!(self.f, "{}{}: {}", m, name, tp)?;
+        }
+        write!(self.f, ") -> {} ", func.ret_type)?;
+        self.expr(&func.body)
+    }
+
+    fn newline(&mut self) -> fmt::Result {
+        write!(self.f, "\n{:1$}", "", self.indent * 4)
+    }
+
+    fn list(&mut self, exprs: &[Expr]) -> fmt::Result {
+        for (i, e) in exprs.iter().enumerate() {
+            if i != 0 {
+                write!(self.f, ", ")?;
+            }
+            self.expr(e)?;
+        }
+        Ok(())
+    }
+
+    /// Print an expression wrapped in parentheses, annotated with its type.
+    fn typed(
+        &mut self,
+        tp: impl Display,
+        inner: impl FnOnce(&mut Self) -> fmt::Result,
+    ) -> fmt::Result {
+        write!(self.f, "(")?;
+        inner(self)?;
+        write!(self.f, " : {})", tp)
+    }
+
+    fn expr(&mut self, e: &Expr) -> fmt::Result {
+        match e {
+            Expr::NumLit(n, tp) if *tp == Type::builtin("char") => {
+                let c = char::from_u32(*n as u32).expect("literal should be a valid char");
+                self.typed(tp, |p| write!(p.f, "{:?}", c))
+            }
+            Expr::NumLit(n, tp) => self.typed(tp, |p| write!(p.f, "{}", n)),
+            Expr::StringLit(s, tp) => self.typed(tp, |p| write!(p.f, "b\"{}\"", s.escape_ascii())),
+            Expr::LocalVar { name, tp } => self.typed(tp, |p| write!(p.f, "{}", name)),
+            Expr::GlobalVar { id, tp, type_args } => self.typed(tp, |p| {
+                write!(p.f, "{}", p.sym_table.find_sym_info(*id).name)?;
+                if !type_args.is_empty() {
+                    let args: Vec<String> = type_args.iter().map(|tp| tp.to_string()).collect();
+                    write!(p.f, "<{}>", args.join(", "))?;
+                }
+                Ok(())
+            }),
+            Expr::Tuple(exprs, tp) => self.typed(tp, |p| {
+                write!(p.f, "(")?;
+                p.list(exprs)?;
+                if exprs.len() == 1 {
+                    write!(p.f, ",")?;
+                }
+                write!(p.f, ")")
+            }),
+            Expr::FunCall {
+                expr, args, ret_tp, ..
+            } => self.typed(ret_tp, |p| {
+                p.expr(expr)?;
+                write!(p.f, "(")?;
+                p.list(args)?;
+                write!(p.f, ")")
+            }),
+            Expr::FieldAccess {
+                object,
+                field_id,
+                struct_tp,
+                field_tp,
+            } => self.typed(field_tp, |p| {
+                p.expr(object)?;
+                write!(p.f, ".{}", p.field_name(struct_tp, *field_id))
+            }),
+            Expr::EnumTag { object, .. } => self.typed(&Type::builtin("u32"), |p| {
+                p.expr(object)?;
+                write!(p.f, ".tag")
+            }),
+            Expr::EnumField {
+                object,
+                cons,
+                field_id,
+                field_tp,
+                ..
+            } => self.typed(field_tp, |p| {
+                p.expr(object)?;
+                write!(p.f, ".{}.{}", cons, field_id)
+            }),
+            Expr::Block {
+                exprs,
+                last_expr,
+                block_tp,
+            } => {
+                write!(self.f, "{{")?;
+                self.indent += 1;
+                for e in exprs {
+                    self.newline()?;
+                    self.expr(e)?;
+                    write!(self.f, ";")?;
+                }
+                self.newline()?;
+                self.expr(last_expr)?;
+                self.indent -= 1;
+                self.newline()?;
+                write!(self.f, "}} : {}", block_tp)
+            }
+            Expr::Return { expr, .. } => {
+                write!(self.f, "return ")?;
+                self.expr(expr)
+            }
+            Expr::Let {
+                name,
+                tp,
+                is_mut,
+                expr,
+            } => {
+                let m = if *is_mut { "mut " } else { "" };
+                write!(self.f, "let {}{}: {} = ", m, name, tp)?;
+                self.expr(expr)
+            }
+            Expr::If {
+                pred,
+                th,
+                el,
+                block_tp,
+            } => self.typed(block_tp, |p| {
+                write!(p.f, "if ")?;
+                p.expr(pred)?;
+                write!(p.f, " ")?;
+                p.expr(th)?;
+                write!(p.f, " else ")?;
+                p.expr(el)
+            }),
+            Expr::StructCons {
+                initializers, tp, ..
+            } => self.typed(tp, |p| {
+                let mut fields: Vec<_> = initializers.iter().collect();
+                fields.sort_by_key(|(_, (idx, _))| *idx);
+                write!(p.f, "{} {{", tp)?;
+                for (i, (name, (_, e))) in fields.into_iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(p.f, "{}{} = ", sep, name)?;
+                    p.expr(e)?;
+                }
+                write!(p.f, " }}")
+            }),
+            Expr::Assign { lval, rval, .. } => {
+                self.expr(lval)?;
+                write!(self.f, " = ")?;
+                self.expr(rval)
+            }
+            Expr::Ref { expr, tp } => self.typed(tp, |p| {
+                write!(p.f, "&")?;
+                p.expr(expr)
+            }),
+            Expr::RefMut { expr, tp } => self.typed(tp, |p| {
+                write!(p.f, "&mut ")?;
+                p.expr(expr)
+            }),
+            Expr::Deref { expr, in_tp } => self.typed(in_tp, |p| {
+                write!(p.f, "*")?;
+                p.expr(expr)
+            }),
+            Expr::Cast { expr, to, .. } => self.typed(to, |p| {
+                p.expr(expr)?;
+                write!(p.f, " as {}", to)
+            }),
+            Expr::Error => write!(self.f, "<error>"),
+            // arrays are annotated with their type, not the type of elements
+            Expr::ArrayInitRepeat(expr, size, tp) => self.typed(format!("[{}]{}", size, tp), |p| {
+                write!(p.f, "[")?;
+                p.expr(expr)?;
+                write!(p.f, "; {}]", size)
+            }),
+            Expr::ArrayInitExact(exprs, tp) => {
+                self.typed(format!("[{}]{}", exprs.len(), tp), |p| {
+                    write!(p.f, "[")?;
+                    p.list(exprs)?;
+                    write!(p.f, "]")
+                })
+            }
+            Expr::ArrayToSlice { ptr, len, tp } => self.typed(tp, |p| {
+                p.expr(ptr)?;
+                write!(p.f, ".(..{})", len)
+            }),
+            Expr::While { pred, block } => {
+                write!(self.f, "while ")?;
+                self.expr(pred)?;
+                write!(self.f, " ")?;
+                self.expr(block)
+            }
+            Expr::Loop { body, tp } => self.typed(tp, |p| {
+                write!(p.f, "loop ")?;
+                p.expr(body)
+            }),
+            Expr::Break(expr) => {
+                write!(self.f, "break ")?;
+                self.expr(expr)
+            }
+            Expr::Continue => write!(self.f, "continue"),
+            Expr::IndexAccess { arr, index, tp, .. } => self.typed(tp, |p| {
+                p.expr(arr)?;
+                write!(p.f, ".(")?;
+                p.expr(index)?;
+                write!(p.f, ")")
+            }),
+            Expr::Builtin(name, exprs, _) => {
+                write!(self.f, "@{}(", name)?;
+                self.list(exprs)?;
+                write!(self.f, ")")
+            }
+        }
+    }
+
+    /// Name of a field of a struct, or its position for other types.
+    fn field_name(&self, struct_tp: &Type, field_id: usize) -> String {
+        if let TypeView::NamedVar(tvar, _) | TypeView::TypeApp(tvar, _, _) = struct_tp.view()
+            && let TypeKind::Struct { fields, .. } = &self.sym_table.find_type_info(tvar).kind
+            && let Some((name, _)) = fields.iter().find(|(_, (idx, _))| *idx == field_id)
+        {
+            return name.clone();
+        }
+        field_id.to_string()
+    }
+}
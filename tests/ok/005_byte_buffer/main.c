@@ -0,0 +1,5 @@
+unsigned char second(void);
+
+int main(void) {
+    return second() == 'e' ? 42 : 1;
+}
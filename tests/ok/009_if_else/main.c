@@ -0,0 +1,18 @@
+#include <stdbool.h>
+
+int choose(bool b, int x, int y);
+int bump(bool b, int x);
+int first(bool b);
+int early(bool b, int x);
+
+int main(void) {
+    if (choose(true, 1, 2) != 1 || choose(false, 1, 2) != 2)
+        return 1;
+    if (bump(true, 10) != 11 || bump(false, 10) != 10)
+        return 2;
+    if (first(true) != 1 || first(false) != 3)
+        return 3;
+    if (early(true, 5) != 5 || early(false, 5) != 11)
+        return 4;
+    return 42;
+}
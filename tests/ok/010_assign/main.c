@@ -0,0 +1,18 @@
+#include <stdbool.h>
+
+int twice(int n);
+int maybe_add(bool b, int x);
+int fields(void);
+int whole(void);
+
+int main(void) {
+    if (twice(21) != 42)
+        return 1;
+    if (maybe_add(true, 1) != 11 || maybe_add(false, 1) != 1)
+        return 2;
+    if (fields() != 41)
+        return 3;
+    if (whole() != 7)
+        return 4;
+    return 42;
+}
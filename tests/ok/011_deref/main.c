@@ -0,0 +1,29 @@
+struct Point {
+    long x;
+    long y;
+};
+
+int read(const int *p);
+int write(int *p, int v);
+long sum(const struct Point *p);
+long copy_point(const struct Point *p);
+int move_point(struct Point *dst, const struct Point *src);
+
+int main(void) {
+    int n = 5;
+    if (read(&n) != 5)
+        return 1;
+    write(&n, 42);
+    if (n != 42)
+        return 2;
+    struct Point p = {1, 2};
+    if (sum(&p) != 3)
+        return 3;
+    if (copy_point(&p) != 2 || p.x != 1)
+        return 4;
+    struct Point q = {0, 0};
+    move_point(&q, &p);
+    if (q.x != 7 || q.y != 2 || p.x != 1)
+        return 5;
+    return 42;
+}
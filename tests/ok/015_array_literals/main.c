@@ -0,0 +1,41 @@
+#include <stdint.h>
+
+struct Point {
+    long x;
+    long y;
+};
+
+int exact(int32_t dst[3]);
+int short_repeat(uint8_t dst[4]);
+int long_repeat(int32_t dst[100], int32_t v);
+int points(struct Point dst[20]);
+
+int main(void) {
+    int32_t a[3] = {0};
+    exact(a);
+    if (a[0] != 1 || a[1] != 2 || a[2] != 3)
+        return 1;
+
+    uint8_t b[5] = {0};
+    short_repeat(b);
+    for (int i = 0; i < 4; i++)
+        if (b[i] != 7)
+            return 2;
+    if (b[4] != 0)
+        return 3;
+
+    int32_t c[101] = {0};
+    long_repeat(c, 9);
+    for (int i = 0; i < 100; i++)
+        if (c[i] != 9)
+            return 4;
+    if (c[100] != 0)
+        return 5;
+
+    struct Point p[20] = {0};
+    points(p);
+    for (int i = 0; i < 20; i++)
+        if (p[i].x != 1 || p[i].y != 2)
+            return 6;
+    return 42;
+}
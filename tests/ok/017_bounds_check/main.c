@@ -0,0 +1,15 @@
+#include <stddef.h>
+#include <stdint.h>
+
+int32_t get(size_t i);
+int32_t set(size_t i);
+
+int main(void) {
+    if (get(2) != 30)
+        return 1;
+    if (set(2) != 12)
+        return 2;
+    // out of bounds, the program should be aborted here
+    get(3);
+    return 3;
+}
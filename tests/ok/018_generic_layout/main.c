@@ -0,0 +1,35 @@
+#include <stdint.h>
+
+struct PairLongByte {
+    long first;
+    uint8_t second;
+};
+
+struct Wrapper {
+    long tag;
+    struct {
+        uint8_t first;
+        struct {
+            long a;
+            long b;
+        } second;
+    } inner;
+};
+
+int make_pair(struct PairLongByte *dst);
+int make_wrapper(struct Wrapper *dst, long x);
+
+int main(void) {
+    struct PairLongByte p = {0};
+    make_pair(&p);
+    if (p.first != 40 || p.second != 2)
+        return 1;
+
+    struct Wrapper w = {0};
+    make_wrapper(&w, 5);
+    if (w.tag != 3 || w.inner.first != 1)
+        return 2;
+    if (w.inner.second.a != 5 || w.inner.second.b != 7)
+        return 3;
+    return p.first + p.second;
+}
@@ -0,0 +1,17 @@
+#include <stdint.h>
+
+uint8_t truncate(int64_t x);
+int64_t widen(uint8_t x);
+int64_t widen_signed(int8_t x);
+uint8_t fits(void);
+uint8_t too_big(void);
+
+int main(void) {
+    if (truncate(4294967338) != 42) return 1;
+    if (truncate(-214) != 42) return 2;
+    if (widen(200) != 200) return 3;
+    if (widen_signed(-3) != -3) return 4;
+    if (fits() != 200) return 5;
+    if (too_big() != 44) return 6;
+    return 42;
+}
@@ -0,0 +1,25 @@
+#include <stdint.h>
+
+struct Mixed {
+    uint8_t a;
+    int32_t b;
+    uint16_t c;
+    int64_t d;
+    uint8_t e;
+};
+
+int fill(struct Mixed dst[2]);
+int64_t sum(struct Mixed src[2]);
+
+int main(void) {
+    struct Mixed m[3] = {0};
+    fill(m);
+    for (int i = 0; i < 2; i++)
+        if (m[i].a != 1 || m[i].b != 2 || m[i].c != 3 || m[i].d != 4 || m[i].e != 5)
+            return 1;
+    if (m[2].a != 0 || m[2].d != 0)
+        return 2;
+
+    m[1].d = 14;
+    return sum(m);
+}
@@ -0,0 +1,45 @@
+#include <stdint.h>
+
+struct Shape {
+    uint32_t tag;
+    union {
+        int64_t circle;
+        struct {
+            int32_t w;
+            int32_t h;
+        } rect;
+    } data;
+};
+
+struct OptionByte {
+    uint32_t tag;
+    uint8_t some;
+};
+
+int make_circle(struct Shape *dst, int64_t r);
+int make_rect(struct Shape *dst);
+int make_empty(struct Shape *dst);
+int make_some(struct OptionByte *dst);
+int make_none(struct OptionByte *dst);
+
+int main(void) {
+    struct Shape s = {0};
+    make_circle(&s, 5);
+    if (s.tag != 0 || s.data.circle != 5)
+        return 1;
+    make_rect(&s);
+    if (s.tag != 1 || s.data.rect.w != 3 || s.data.rect.h != 4)
+        return 2;
+    make_empty(&s);
+    if (s.tag != 2)
+        return 3;
+
+    struct OptionByte o = {0};
+    make_some(&o);
+    if (o.tag != 0 || o.some != 7)
+        return 4;
+    make_none(&o);
+    if (o.tag != 1)
+        return 5;
+    return 42;
+}
@@ -0,0 +1,5 @@
+#include <stdbool.h>
+
+int loops(bool go, bool stop);
+
+int main(void) { return loops(true, false); }
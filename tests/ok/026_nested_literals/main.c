@@ -0,0 +1,30 @@
+#include <stdint.h>
+
+struct Inner {
+    uint8_t a;
+    int64_t b;
+};
+
+struct Outer {
+    struct {
+        uint8_t first;
+        struct Inner second;
+    } t;
+    int32_t c;
+    struct Inner inner;
+};
+
+int make_outer(struct Outer *dst, int64_t x);
+int64_t sum_outer(int64_t x);
+
+int main(void) {
+    struct Outer o = {0};
+    make_outer(&o, 6);
+    if (o.t.first != 1 || o.t.second.a != 2 || o.t.second.b != 30)
+        return 1;
+    if (o.c != 4 || o.inner.a != 5 || o.inner.b != 6)
+        return 2;
+    if (sum_outer(20) != 42)
+        return 3;
+    return 42;
+}
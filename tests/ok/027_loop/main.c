@@ -0,0 +1,10 @@
+#include <stdbool.h>
+
+int second(bool go, bool stop);
+int halt(bool go);
+
+int main(void) {
+    if (halt(true) != 42)
+        return 1;
+    return second(true, false);
+}
@@ -0,0 +1,24 @@
+#include <stdbool.h>
+#include <stdint.h>
+
+int cmp_signed(int32_t a, int32_t b);
+int cmp_unsigned(uint8_t a, uint8_t b);
+int64_t sum_below(int64_t n);
+bool cmp_byte(int8_t a, int8_t b);
+bool cmp_wide(uint64_t a, uint64_t b);
+
+int main(void) {
+    if (cmp_signed(-1, 1) != 1 + 2 + 8)
+        return 1;
+    if (cmp_signed(3, 3) != 2 + 4 + 32)
+        return 2;
+    if (cmp_unsigned(200, 100) != 8 + 16 + 32)
+        return 3;
+    if (sum_below(9) != 36)
+        return 4;
+    if (!cmp_byte(-128, 127) || cmp_byte(5, -5))
+        return 5;
+    if (!cmp_wide(UINT64_MAX, 1) || cmp_wide(0, UINT64_MAX))
+        return 6;
+    return 42;
+}
@@ -0,0 +1,15 @@
+#include <stdint.h>
+
+int arith(int32_t a);
+int places(void);
+int divide(uint8_t a, int8_t b);
+
+int main(void) {
+    if (arith(-5) != 4)
+        return 1;
+    if (places() != 82)
+        return 2;
+    if (divide(200, -9) != 3)
+        return 3;
+    return 42;
+}
@@ -0,0 +1,12 @@
+#include <stdint.h>
+
+int negate(int32_t x);
+int literals(void);
+
+int main(void) {
+    if (negate(-42) != 42 || negate(7) != -7)
+        return 1;
+    if (literals() != 7)
+        return 2;
+    return 42;
+}
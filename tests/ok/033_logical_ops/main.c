@@ -0,0 +1,28 @@
+#include <stdbool.h>
+#include <stdint.h>
+
+int truth(bool a, bool b, bool c);
+int evaluated(bool a, bool b);
+int in_range(int32_t x);
+
+int main(void) {
+    if (truth(false, false, true) != 0)
+        return 1;
+    if (truth(false, true, false) != 2)
+        return 2;
+    if (truth(false, true, true) != 2 + 4)
+        return 3;
+    if (truth(true, false, false) != 2 + 4)
+        return 4;
+    if (truth(true, true, false) != 1 + 2 + 4)
+        return 5;
+    if (evaluated(true, false) != 1 + 10)
+        return 6;
+    if (evaluated(false, false) != 1)
+        return 7;
+    if (evaluated(false, true) != 1 + 10)
+        return 8;
+    if (in_range(5) != 1 || in_range(10) != 0 || in_range(0) != 0)
+        return 9;
+    return 42;
+}
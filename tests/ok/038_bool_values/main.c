@@ -0,0 +1,18 @@
+#include <stdbool.h>
+#include <stdint.h>
+
+bool both_positive(int32_t x, int32_t y);
+bool negate(bool b);
+uint8_t same_order(uint8_t o);
+
+int main(void) {
+    if (!both_positive(1, 2))
+        return 1;
+    if (both_positive(1, -2) || both_positive(-1, 2))
+        return 2;
+    if (negate(true) || !negate(false))
+        return 3;
+    if (same_order(2) != 2)
+        return 4;
+    return 42;
+}
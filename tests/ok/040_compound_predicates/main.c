@@ -0,0 +1,21 @@
+#include <stdbool.h>
+#include <stdint.h>
+
+int32_t count(int32_t limit, int32_t stop_at);
+int32_t first_odd_above(int32_t n, bool enabled);
+
+int main(void) {
+    if (count(10, 4) != 4)
+        return 1;
+    if (count(10, 20) != 10)
+        return 2;
+    if (count(10, 0) != 0)
+        return 3;
+    if (first_odd_above(6, true) != 7)
+        return 4;
+    if (first_odd_above(7, true) != 9)
+        return 5;
+    if (first_odd_above(7, false) != 0)
+        return 6;
+    return 42;
+}
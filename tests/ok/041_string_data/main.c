@@ -0,0 +1,20 @@
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+const char *greeting(void);
+const char *greeting_again(void);
+size_t measure(void);
+uint8_t third(void);
+
+int main(void) {
+    if (strcmp(greeting(), "hello") != 0)
+        return 1;
+    if (greeting() != greeting_again())
+        return 2;
+    if (measure() != 11)
+        return 3;
+    if (third() != 'l')
+        return 4;
+    return 42;
+}
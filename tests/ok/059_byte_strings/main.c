@@ -0,0 +1,16 @@
+#include <stdint.h>
+#include <string.h>
+
+const uint8_t *magic(void);
+const uint8_t *request(void);
+uint8_t last_byte(void);
+
+int main(void) {
+    if (memcmp(magic(), "\x7F" "ELF", 5) != 0)
+        return 1;
+    if (memcmp(request(), "GET\r\n", 6) != 0)
+        return 2;
+    if (last_byte() != 0xFF)
+        return 3;
+    return 42;
+}
@@ -0,0 +1,18 @@
+#include <stdint.h>
+
+int32_t classify(int32_t x);
+int32_t sign(int32_t x);
+
+int main(void) {
+    if (classify(-5) != 1)
+        return 1;
+    if (classify(0) != 2)
+        return 2;
+    if (classify(7) != 3)
+        return 3;
+    if (classify(100) != 4)
+        return 4;
+    if (sign(-3) != -1 || sign(0) != 0 || sign(3) != 1)
+        return 5;
+    return 42;
+}
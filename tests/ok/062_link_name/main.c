@@ -0,0 +1,8 @@
+#include <stdint.h>
+
+int32_t c_add_three(int32_t x) { return x + 3; }
+int32_t c_times_two(int32_t x) { return x * 2; }
+
+int32_t mst_compute(int32_t x);
+
+int main(void) { return mst_compute(18); }
@@ -0,0 +1,36 @@
+#include <stdarg.h>
+#include <stdint.h>
+
+int32_t sum(int32_t n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    int32_t s = 0;
+    for (int32_t i = 0; i < n; i++)
+        s += va_arg(ap, int32_t);
+    va_end(ap);
+    return s;
+}
+
+int64_t sum_wide(int32_t n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    int64_t s = 0;
+    for (int32_t i = 0; i < n; i++)
+        s += va_arg(ap, int64_t);
+    va_end(ap);
+    return s;
+}
+
+int32_t sum_pointed(int32_t n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    int32_t s = 0;
+    for (int32_t i = 0; i < n; i++)
+        s += *va_arg(ap, int32_t *);
+    va_end(ap);
+    return s;
+}
+
+int32_t compute(void);
+
+int main(void) { return compute(); }
@@ -1,23 +1,783 @@
+use std::fs;
 use std::io::Write;
+use std::path::Path;
+use std::process::Command;
 
 use assert_cmd::cargo::cargo_bin_cmd;
 
-fn test_path(s: &str) {
-    let output = cargo_bin_cmd!("mustcc").arg(s).output().unwrap();
+/// Only parse the project, for features not supported by later passes yet.
+///
+/// If the project contains `expected.stdout`, the printed syntax tree
+/// has to match it.
+fn test_parse(s: &str) {
+    let output = cargo_bin_cmd!("mustcc")
+        .arg(s)
+        .arg("--emit=ast")
+        .output()
+        .unwrap();
+
+    std::io::stderr()
+        .write_all(output.stderr.as_slice())
+        .unwrap();
+
+    assert!(output.status.code() == Some(0), "non-zero exit code");
+    check_golden_file(s, "expected.stdout", &output.stdout)
+}
+
+/// Check that compilation of the project fails with given number of errors.
+///
+/// If the project contains `expected.stderr`, the reported diagnostics
+/// (without colors) have to match it.
+fn test_error(s: &str, exp_errors: usize) {
+    test_error_with_args(s, &[], exp_errors)
+}
+
+/// Like [test_error], passing additional arguments to the compiler.
+fn test_error_with_args(s: &str, args: &[&str], exp_errors: usize) {
+    let output = cargo_bin_cmd!("mustcc")
+        .arg(s)
+        .arg("--emit=typed")
+        .args(args)
+        .output()
+        .unwrap();
+
+    std::io::stderr()
+        .write_all(output.stderr.as_slice())
+        .unwrap();
+
+    assert!(
+        output.status.code() == Some(1),
+        "expected compilation to fail"
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .contains(&format!("{} errors occurred", exp_errors)),
+        "unexpected number of errors"
+    );
+    check_golden(s, &output.stderr)
+}
+
+/// Compare diagnostics with `expected.stderr` of the project, if it exists.
+///
+/// Run with `MUSTCC_BLESS=1` to write the current diagnostics there instead.
+fn check_golden(s: &str, stderr: &[u8]) {
+    check_golden_file(s, "expected.stderr", stderr)
+}
+
+/// Compare output with the golden file `name` of the project, if it exists.
+fn check_golden_file(s: &str, name: &str, output: &[u8]) {
+    let golden = Path::new(s).join(name);
+    let output = strip_colors(&String::from_utf8_lossy(output));
+    if std::env::var_os("MUSTCC_BLESS").is_some() {
+        fs::write(&golden, output).unwrap();
+    } else if let Ok(expected) = fs::read_to_string(&golden) {
+        assert!(
+            output == expected,
+            "output differs from {}",
+            golden.display()
+        )
+    }
+}
+
+/// Remove ANSI color codes from compiler output.
+fn strip_colors(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Compile the project and check that a warning was reported.
+///
+/// Like in [test_error], diagnostics are compared with `expected.stderr`.
+fn test_warning(s: &str) {
+    test_warning_with_args(s, &[])
+}
+
+/// Like [test_warning], passing additional arguments to the compiler.
+fn test_warning_with_args(s: &str, args: &[&str]) {
+    let output = cargo_bin_cmd!("mustcc").arg(s).args(args).output().unwrap();
+
+    std::io::stderr()
+        .write_all(output.stderr.as_slice())
+        .unwrap();
+
+    assert!(output.status.code() == Some(0), "non-zero exit code");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Warning"),
+        "no warning reported"
+    );
+    check_golden(s, &output.stderr)
+}
+
+/// Compile the project, link it using system C compiler
+/// and check the exit code of resulting executable.
+///
+/// If the project contains `main.c`, it's linked in as well.
+fn test_run(s: &str, exp_code: i32) {
+    test_run_with_args(s, &[], Some(exp_code))
+}
+
+/// Like [test_run], passing additional arguments to the compiler.
+///
+/// Expected exit code of `None` means the executable should be killed by a signal.
+fn test_run_with_args(s: &str, args: &[&str], exp_code: Option<i32>) {
+    let name = Path::new(s).file_name().unwrap().to_str().unwrap();
+    let suffix = args.concat();
+    let tmp = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let obj = tmp.join(format!("{}{}.o", name, suffix));
+    let exe = tmp.join(format!("{}{}", name, suffix));
+
+    let output = cargo_bin_cmd!("mustcc")
+        .arg(s)
+        .args(args)
+        .arg("-o")
+        .arg(&obj)
+        .output()
+        .unwrap();
 
     std::io::stderr()
         .write_all(output.stderr.as_slice())
         .unwrap();
 
-    assert!(output.status.code() == Some(0), "non-zero exit code")
+    assert!(output.status.code() == Some(0), "non-zero exit code");
+
+    let mut cc = Command::new("cc");
+    cc.arg(&obj);
+    let driver = Path::new(s).join("main.c");
+    if driver.exists() {
+        cc.arg(driver);
+    }
+    let status = cc.arg("-o").arg(&exe).status().unwrap();
+
+    assert!(status.success(), "linking failed");
+
+    let status = Command::new(&exe).status().unwrap();
+
+    assert_eq!(status.code(), exp_code, "unexpected exit code");
 }
 
 #[test]
 fn test_001() {
-    test_path("tests/ok/001_functions")
+    test_run("tests/ok/001_functions", 117)
 }
 
 #[test]
 fn test_002() {
-    test_path("tests/ok/002_modules")
+    test_run("tests/ok/002_modules", 42)
+}
+
+#[test]
+fn test_003() {
+    test_run("tests/ok/003_fn_fields", 42)
+}
+
+#[test]
+fn test_004() {
+    test_run("tests/ok/004_extern_call", 65)
+}
+
+#[test]
+fn test_005() {
+    test_run("tests/ok/005_byte_buffer", 42)
+}
+
+#[test]
+fn test_006() {
+    test_run("tests/ok/006_fn_pointers", 42)
+}
+
+#[test]
+fn test_007() {
+    test_warning_with_args("tests/ok/007_large_enum", &["-W", "large_enum_variant"])
+}
+
+#[test]
+fn test_008() {
+    test_run("tests/ok/008_block_return", 42)
+}
+
+#[test]
+fn test_009() {
+    test_run("tests/ok/009_if_else", 42)
+}
+
+#[test]
+fn test_010() {
+    test_run("tests/ok/010_assign", 42)
+}
+
+#[test]
+fn test_011() {
+    test_run("tests/ok/011_deref", 42)
+}
+
+#[test]
+fn test_012() {
+    test_run("tests/ok/012_refs", 100)
+}
+
+#[test]
+fn test_013() {
+    test_run("tests/ok/013_immutable_fields", 41)
+}
+
+#[test]
+fn test_013_no_opt() {
+    test_run_with_args("tests/ok/013_immutable_fields", &["-O0"], Some(41))
+}
+
+#[test]
+fn test_014() {
+    test_parse("tests/ok/014_negative_patterns")
+}
+
+#[test]
+fn test_014_run() {
+    test_run("tests/ok/014_negative_patterns", 37)
+}
+
+#[test]
+fn test_015() {
+    test_run("tests/ok/015_array_literals", 42)
+}
+
+#[test]
+fn test_016() {
+    test_run("tests/ok/016_index", 45)
+}
+
+#[test]
+fn test_017() {
+    test_run_with_args("tests/ok/017_bounds_check", &["--debug-assertions"], None)
+}
+
+#[test]
+fn test_018() {
+    test_run("tests/ok/018_generic_layout", 42)
+}
+
+#[test]
+fn test_019() {
+    test_warning_with_args(
+        "tests/ok/019_narrowing_cast",
+        &["--emit=typed", "-W", "narrowing_cast"],
+    )
+}
+
+#[test]
+fn test_019_run() {
+    test_run("tests/ok/019_narrowing_cast", 42)
+}
+
+#[test]
+fn test_020() {
+    test_run("tests/ok/020_alignment", 42)
+}
+
+#[test]
+fn test_err_001() {
+    test_error("tests/err/001_aliased_mut_ref", 3)
+}
+
+#[test]
+fn test_err_002() {
+    test_error("tests/err/002_deref_fn", 2)
+}
+
+#[test]
+fn test_021() {
+    test_run("tests/ok/021_enums", 42)
+}
+
+#[test]
+fn test_err_003() {
+    test_error("tests/err/003_assign_through_ptr", 2)
+}
+
+#[test]
+fn test_022() {
+    test_parse("tests/ok/022_postfix_chains")
+}
+
+#[test]
+fn test_err_004() {
+    test_error("tests/err/004_duplicate_field", 1)
+}
+
+#[test]
+fn test_023() {
+    test_run("tests/ok/023_nested_fields", 71)
+}
+
+#[test]
+fn test_missing_src() {
+    let output = cargo_bin_cmd!("mustcc").arg("tests").output().unwrap();
+
+    assert!(
+        output.status.code() == Some(1),
+        "expected compilation to fail"
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("no `src` directory"),
+        "missing directory not reported"
+    )
+}
+
+#[test]
+fn test_missing_dir() {
+    let output = cargo_bin_cmd!("mustcc")
+        .arg("tests/does_not_exist")
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.code() == Some(1),
+        "expected compilation to fail"
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("does not exist"),
+        "missing directory not reported"
+    )
+}
+
+#[test]
+fn test_024() {
+    test_run("tests/ok/024_nested_stores", 42)
+}
+
+#[test]
+fn test_err_005() {
+    test_error("tests/err/005_duplicate_fn", 1)
+}
+
+#[test]
+fn test_err_006() {
+    test_error("tests/err/006_bad_escape", 1)
+}
+
+#[test]
+fn test_025() {
+    test_run("tests/ok/025_loop_control", 42)
+}
+
+#[test]
+fn test_err_007() {
+    test_error("tests/err/007_loop_control", 2)
+}
+
+#[test]
+fn test_026() {
+    test_run("tests/ok/026_nested_literals", 42)
+}
+
+#[test]
+fn test_027() {
+    test_run("tests/ok/027_loop", 42)
+}
+
+#[test]
+fn test_err_008() {
+    test_error("tests/err/008_loop_break_type", 1)
+}
+
+#[test]
+fn test_err_009() {
+    test_error("tests/err/009_type_errors", 2)
+}
+
+#[test]
+fn test_028() {
+    test_run("tests/ok/028_comparisons", 42)
+}
+
+#[test]
+fn test_029() {
+    test_run("tests/ok/029_compound_assign", 42)
+}
+
+#[test]
+fn test_err_010() {
+    test_error("tests/err/010_immutable_compound_assign", 1)
+}
+
+#[test]
+fn test_030() {
+    test_run("tests/ok/030_underscore_names", 42)
+}
+
+#[test]
+fn test_031() {
+    test_run("tests/ok/031_negation", 42)
+}
+
+#[test]
+fn test_err_011() {
+    test_error("tests/err/011_negate_unsigned", 2)
+}
+
+#[test]
+fn test_032() {
+    test_warning_with_args(
+        "tests/ok/032_unused_variables",
+        &["--emit=typed", "-W", "unused_variables"],
+    )
+}
+
+#[test]
+fn test_033() {
+    test_run("tests/ok/033_logical_ops", 42)
+}
+
+#[test]
+fn test_034() {
+    test_warning_with_args(
+        "tests/ok/034_dead_stores",
+        &["--emit=typed", "-W", "dead_stores"],
+    )
+}
+
+#[test]
+fn test_035() {
+    test_run("tests/ok/035_tuple_fields", 43)
+}
+
+#[test]
+fn test_err_012() {
+    test_error("tests/err/012_tuple_field", 2)
+}
+
+#[test]
+fn test_err_013() {
+    test_error("tests/err/013_missing_body", 1)
+}
+
+#[test]
+fn test_036() {
+    test_run("tests/ok/036_field_assign", 42)
+}
+
+#[test]
+fn test_037() {
+    test_run("tests/ok/037_ascription", 42)
+}
+
+#[test]
+fn test_err_014() {
+    test_error("tests/err/014_ascription", 1)
+}
+
+#[test]
+fn test_038() {
+    test_run("tests/ok/038_bool_values", 42)
+}
+
+#[test]
+fn test_039() {
+    test_run("tests/ok/039_struct_return", 36)
+}
+
+#[test]
+fn test_040() {
+    test_run("tests/ok/040_compound_predicates", 42)
+}
+
+#[test]
+fn test_041() {
+    test_run("tests/ok/041_string_data", 42)
+}
+
+#[test]
+fn test_042() {
+    test_run("tests/ok/042_signed_default", 42)
+}
+
+#[test]
+fn test_err_015() {
+    test_error("tests/err/015_negate_inferred_unsigned", 1)
+}
+
+#[test]
+fn test_err_016() {
+    test_error("tests/err/016_call_non_function", 4)
+}
+
+#[test]
+fn test_043() {
+    test_warning("tests/ok/043_must_use")
+}
+
+#[test]
+fn test_044() {
+    test_run("tests/ok/044_import_shadowing", 42)
+}
+
+#[test]
+fn test_err_017() {
+    test_error("tests/err/017_ambiguous_import", 1)
+}
+
+#[test]
+fn test_err_018() {
+    test_error("tests/err/018_similar_names", 5)
+}
+
+#[test]
+fn test_045() {
+    test_warning_with_args(
+        "tests/ok/045_unreachable_code",
+        &["--emit=typed", "-W", "unreachable_code"],
+    )
+}
+
+#[test]
+fn test_046() {
+    test_run("tests/ok/046_type_aliases", 42)
+}
+
+#[test]
+fn test_err_019() {
+    test_error("tests/err/019_type_aliases", 8)
+}
+
+#[test]
+fn test_047() {
+    test_run("tests/ok/047_consts", 42)
+}
+
+#[test]
+fn test_err_020() {
+    test_error("tests/err/020_consts", 10)
+}
+
+#[test]
+fn test_048() {
+    test_run("tests/ok/048_statics", 42)
+}
+
+#[test]
+fn test_049() {
+    test_run("tests/ok/049_generics", 42)
+}
+
+#[test]
+fn test_050() {
+    test_run("tests/ok/050_slices", 42)
+}
+
+#[test]
+fn test_051() {
+    test_run_with_args("tests/ok/051_slice_bounds", &["--debug-assertions"], None)
+}
+
+#[test]
+fn test_052() {
+    test_run("tests/ok/052_len", 42)
+}
+
+#[test]
+fn test_053() {
+    test_run("tests/ok/053_never", 42)
+}
+
+#[test]
+fn test_054() {
+    test_warning_with_args(
+        "tests/ok/054_defaulted_literals",
+        &["--emit=typed", "-W", "defaulted_literals"],
+    )
+}
+
+#[test]
+fn test_055() {
+    test_run("tests/ok/055_number_literals", 42)
+}
+
+#[test]
+fn test_056() {
+    test_run("tests/ok/056_literal_suffixes", 42)
+}
+
+#[test]
+fn test_057() {
+    test_run("tests/ok/057_comments", 42)
+}
+
+#[test]
+fn test_058() {
+    test_run("tests/ok/058_chars", 42)
+}
+
+#[test]
+fn test_059() {
+    test_run("tests/ok/059_byte_strings", 42)
+}
+
+#[test]
+fn test_060() {
+    test_run("tests/ok/060_else_if", 42)
+}
+
+#[test]
+fn test_061() {
+    test_run("tests/ok/061_let_else", 46)
+}
+
+#[test]
+fn test_062() {
+    test_run("tests/ok/062_link_name", 42)
+}
+
+#[test]
+fn test_063() {
+    test_run("tests/ok/063_variadic", 42)
+}
+
+#[test]
+fn test_064() {
+    test_run("tests/ok/064_let_copies", 5)
+}
+
+#[test]
+fn test_err_021() {
+    test_error("tests/err/021_statics", 6)
+}
+
+#[test]
+fn test_err_022() {
+    test_error("tests/err/022_slices", 5)
+}
+
+#[test]
+fn test_err_023() {
+    test_error("tests/err/023_len", 6)
+}
+
+#[test]
+fn test_err_024() {
+    test_error("tests/err/024_recursive_types", 3)
+}
+
+#[test]
+fn test_err_025() {
+    test_error("tests/err/025_literal_ranges", 7)
+}
+
+#[test]
+fn test_err_026() {
+    test_error("tests/err/026_number_literals", 6)
+}
+
+#[test]
+fn test_err_027() {
+    test_error("tests/err/027_literal_suffixes", 6)
+}
+
+#[test]
+fn test_err_028() {
+    test_error("tests/err/028_comments", 2)
+}
+
+#[test]
+fn test_err_029() {
+    test_error("tests/err/029_chars", 7)
+}
+
+#[test]
+fn test_err_030() {
+    test_error("tests/err/030_byte_strings", 5)
+}
+
+#[test]
+fn test_err_031() {
+    test_error("tests/err/031_mismatch_origins", 6)
+}
+
+#[test]
+fn test_err_032() {
+    test_error_with_args(
+        "tests/err/032_json_diagnostics",
+        &["--error-format=json", "-W", "unused_variables"],
+        2,
+    )
+}
+
+#[test]
+fn test_err_033() {
+    test_error("tests/err/033_duplicate_diagnostics", 3)
+}
+
+#[test]
+fn test_err_034() {
+    test_error_with_args("tests/err/034_error_limit", &["--error-limit", "2"], 4)
+}
+
+#[test]
+fn test_err_035() {
+    test_error("tests/err/035_else_if", 1)
+}
+
+#[test]
+fn test_err_036() {
+    test_error("tests/err/036_if_without_else", 3)
+}
+
+#[test]
+fn test_err_037() {
+    test_error("tests/err/037_unit_values", 3)
+}
+
+#[test]
+fn test_err_038() {
+    test_error("tests/err/038_let_else", 2)
+}
+
+#[test]
+fn test_err_039() {
+    test_error("tests/err/039_attributes", 2)
+}
+
+#[test]
+fn test_err_040() {
+    test_error("tests/err/040_link_name", 3)
+}
+
+#[test]
+fn test_err_041() {
+    test_error("tests/err/041_variadic", 5)
+}
+
+#[test]
+fn test_err_042() {
+    test_error_with_args(
+        "tests/err/042_variadic_target",
+        &["--target", "aarch64-apple-darwin"],
+        1,
+    )
+}
+
+#[test]
+fn test_err_043() {
+    test_error("tests/err/043_invalid_cast", 3)
+}
+
+#[test]
+fn test_err_044() {
+    test_error("tests/err/044_negative_size", 2)
+}
+
+#[test]
+fn test_err_045() {
+    test_error("tests/err/045_match", 4)
+}
+
+#[test]
+fn test_err_046() {
+    test_error("tests/err/046_let_else_diverge", 5)
 }